@@ -0,0 +1,42 @@
+//! `SeaORM` Entity for accounting_periods
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "accounting_periods")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub period_id: i32,
+
+    pub fiscal_year_id: i32,
+
+    /// First and last day of the period, inclusive
+    pub period_start: Date,
+    pub period_end: Date,
+
+    /// Locked periods refuse postings dated inside them
+    pub is_locked: bool,
+    pub locked_at: Option<DateTimeWithTimeZone>,
+    pub locked_by: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::fiscal_years::Entity",
+        from = "Column::FiscalYearId",
+        to = "super::fiscal_years::Column::FiscalYearId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    FiscalYears,
+}
+
+impl Related<super::fiscal_years::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FiscalYears.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -15,6 +15,11 @@ pub struct Model {
     #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
     pub allocated_value: Decimal,
     pub allocated_by: i32,
+
+    /// Set when the record has been reversed
+    pub voided_at: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "Text")]
+    pub void_reason: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
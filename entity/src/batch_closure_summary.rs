@@ -15,6 +15,29 @@ pub struct Model {
     pub revenue: Decimal,
     #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
     pub gross_profit: Decimal,
+
+    /// Cost sheet: allocated stock value by category plus farmer commission
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub chick_cost: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub feed_cost: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub medicine_cost: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub farmer_commission: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub total_cost: Decimal,
+
+    /// Initial count plus later additions
+    pub birds_placed: i32,
+    /// Deaths recorded in bird_count_history
+    pub mortality: i32,
+    #[sea_orm(column_type = "Decimal(Some((6, 2)))")]
+    pub mortality_pct: Decimal,
+    pub birds_sold: i32,
+
+    pub closed_at: DateTimeWithTimeZone,
+    pub closed_by: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
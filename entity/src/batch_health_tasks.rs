@@ -0,0 +1,48 @@
+//! `SeaORM` Entity for batch_health_tasks
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "batch_health_tasks")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub task_id: i32,
+    pub batch_id: i32,
+
+    /// Program step the task was copied from
+    pub step_id: Option<i32>,
+    pub task_name: String,
+    pub item_code: Option<String>,
+    #[sea_orm(column_type = "Decimal(Some((10, 4)))")]
+    pub dose_per_bird: Option<Decimal>,
+    pub due_date: Date,
+
+    pub completed_at: Option<DateTimeWithTimeZone>,
+    pub completed_by: Option<i32>,
+
+    /// Requirement raised for the medicine when the task was completed
+    pub requirement_id: Option<i32>,
+    #[sea_orm(column_type = "Text")]
+    pub notes: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::batches::Entity",
+        from = "Column::BatchId",
+        to = "super::batches::Column::BatchId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Batches,
+}
+
+impl Related<super::batches::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Batches.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,51 @@
+//! `SeaORM` Entity for batch_placements
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// Birds of one chick item placed when the batch was stocked
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "batch_placements")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub placement_id: i32,
+    pub batch_id: i32,
+    pub item_code: String,
+    pub bird_count: i32,
+    pub allocation_id: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::batches::Entity",
+        from = "Column::BatchId",
+        to = "super::batches::Column::BatchId",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Batches,
+    #[sea_orm(
+        belongs_to = "super::items::Entity",
+        from = "Column::ItemCode",
+        to = "super::items::Column::ItemCode",
+        on_update = "Cascade",
+        on_delete = "Restrict"
+    )]
+    Items,
+}
+
+impl Related<super::batches::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Batches.def()
+    }
+}
+
+impl Related<super::items::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Items.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -16,6 +16,9 @@ pub struct Model {
     pub quantity: Decimal,
     pub status: super::sea_orm_active_enums::RequirementStatus,
     pub request_date: Date,
+
+    /// Raised by `create_batch` for the chicks the batch was stocked with
+    pub is_stocking: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
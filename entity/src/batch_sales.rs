@@ -15,6 +15,20 @@ pub struct Model {
     pub quantity: Decimal,
     pub value: Decimal,
     pub created_at: DateTimeWithTimeZone,
+
+    /// Sold on credit: the value is owed by the trader
+    pub on_credit: bool,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub amount_due: Decimal,
+
+    /// Settled so far by trader receipts
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub amount_received: Decimal,
+
+    /// Set when the record has been reversed
+    pub voided_at: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "Text")]
+    pub void_reason: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
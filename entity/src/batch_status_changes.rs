@@ -0,0 +1,42 @@
+//! `SeaORM` Entity for batch_status_changes
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "batch_status_changes")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub change_id: i32,
+
+    pub batch_id: i32,
+
+    /// `batch_status` values the batch moved between
+    pub from_status: String,
+    pub to_status: String,
+
+    #[sea_orm(column_type = "Text")]
+    pub reason: Option<String>,
+    pub changed_by: Option<i32>,
+    pub changed_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::batches::Entity",
+        from = "Column::BatchId",
+        to = "super::batches::Column::BatchId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Batches,
+}
+
+impl Related<super::batches::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Batches.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -18,6 +18,8 @@ pub struct Model {
     pub current_bird_count: Option<i32>,
     pub status: BatchStatus,
     pub created_at: DateTimeWithTimeZone,
+    /// Picks the `breed_standards` rows growth is compared against
+    pub breed: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -15,6 +15,10 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub notes: String,
     pub created_at: DateTimeWithTimeZone,
+
+    /// Birds placed when the batch was stocked; already counted in
+    /// `batches.initial_bird_count`
+    pub is_stocking: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,25 @@
+//! `SeaORM` Entity for breed_standards
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "breed_standards")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub standard_id: i32,
+
+    pub breed: String,
+    pub age_days: i32,
+
+    /// Target body weight and feed eaten per bird up to this age
+    #[sea_orm(column_type = "Decimal(Some((8, 3)))")]
+    pub body_weight_kg: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((8, 3)))")]
+    pub cumulative_feed_kg: Decimal,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,22 @@
+//! `SeaORM` Entity for category_costing_methods
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+use crate::sea_orm_active_enums::{CostingMethod, ItemCategory};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "category_costing_methods")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub item_category: ItemCategory,
+
+    /// How lots of this category are drawn down and costed
+    pub costing_method: CostingMethod,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,54 @@
+//! `SeaORM` Entity for farmer_advances
+
+use super::sea_orm_active_enums::FarmerAdvanceType;
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "farmer_advances")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub advance_id: i32,
+    pub farmer_id: i32,
+    /// Batch the advance was taken against, if any
+    pub batch_id: Option<i32>,
+
+    /// Cash advanced, or a charge deducted from what the farmer is owed
+    pub advance_type: FarmerAdvanceType,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub amount: Decimal,
+    pub advance_date: Date,
+    #[sea_orm(column_type = "Text")]
+    pub description: Option<String>,
+    pub created_by: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+
+    /// Set when the record has been reversed
+    pub voided_at: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "Text")]
+    pub void_reason: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::farmers::Entity",
+        from = "Column::FarmerId",
+        to = "super::farmers::Column::FarmerId"
+    )]
+    Farmers,
+    #[sea_orm(
+        belongs_to = "super::batches::Entity",
+        from = "Column::BatchId",
+        to = "super::batches::Column::BatchId"
+    )]
+    Batches,
+}
+
+impl Related<super::farmers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Farmers.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
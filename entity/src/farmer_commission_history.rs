@@ -14,6 +14,14 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub description: Option<String>,
     pub created_at: DateTimeWithTimeZone,
+
+    /// Batch the commission was earned on, if any
+    pub batch_id: Option<i32>,
+
+    /// Set when the record has been reversed
+    pub voided_at: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "Text")]
+    pub void_reason: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,84 @@
+//! `SeaORM` Entity for farmer_settlements
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "farmer_settlements")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub settlement_id: i32,
+
+    /// A batch is settled once
+    #[sea_orm(unique)]
+    pub batch_id: i32,
+    pub farmer_id: i32,
+    pub rate_table_id: i32,
+
+    /// Inputs taken from the batch's cost sheet and sales
+    pub birds_placed: i32,
+    pub mortality: i32,
+    #[sea_orm(column_type = "Decimal(Some((6, 2)))")]
+    pub mortality_pct: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((12, 3)))")]
+    pub live_weight_kg: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((14, 2)))")]
+    pub production_cost: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((10, 2)))")]
+    pub cost_per_kg: Option<Decimal>,
+
+    /// Breakdown: gross = base + incentive - penalty, net = gross - advances
+    #[sea_orm(column_type = "Decimal(Some((14, 2)))")]
+    pub base_amount: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((14, 2)))")]
+    pub cost_incentive: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((14, 2)))")]
+    pub mortality_penalty: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((14, 2)))")]
+    pub gross_amount: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((14, 2)))")]
+    pub advances_deducted: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((14, 2)))")]
+    pub net_amount: Decimal,
+
+    /// Commission the gross amount was booked as
+    pub commission_id: i32,
+    pub approved_by: Option<i32>,
+    pub approved_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::batches::Entity",
+        from = "Column::BatchId",
+        to = "super::batches::Column::BatchId"
+    )]
+    Batches,
+    #[sea_orm(
+        belongs_to = "super::farmers::Entity",
+        from = "Column::FarmerId",
+        to = "super::farmers::Column::FarmerId"
+    )]
+    Farmers,
+    #[sea_orm(
+        belongs_to = "super::settlement_rate_tables::Entity",
+        from = "Column::RateTableId",
+        to = "super::settlement_rate_tables::Column::RateTableId"
+    )]
+    SettlementRateTables,
+    #[sea_orm(
+        belongs_to = "super::farmer_commission_history::Entity",
+        from = "Column::CommissionId",
+        to = "super::farmer_commission_history::Column::Id"
+    )]
+    FarmerCommissionHistory,
+}
+
+impl Related<super::settlement_rate_tables::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SettlementRateTables.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,40 @@
+//! `SeaORM` Entity for fiscal_years
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "fiscal_years")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub fiscal_year_id: i32,
+
+    #[sea_orm(unique)]
+    pub name: String,
+
+    pub start_date: Date,
+    pub end_date: Date,
+
+    /// Set once the year-end closing entries are posted
+    pub closed_at: Option<DateTimeWithTimeZone>,
+    pub closed_by: Option<i32>,
+
+    /// Ledger transaction holding the closing entries
+    pub closing_txn_group_id: Option<Uuid>,
+
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::accounting_periods::Entity")]
+    AccountingPeriods,
+}
+
+impl Related<super::accounting_periods::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AccountingPeriods.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
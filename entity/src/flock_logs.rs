@@ -0,0 +1,56 @@
+//! `SeaORM` Entity for flock_logs
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "flock_logs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub log_id: i32,
+
+    /// One log per batch per day
+    pub batch_id: i32,
+    pub log_date: Date,
+
+    /// Feed eaten that day, out of what was allocated to the farm
+    #[sea_orm(column_type = "Decimal(Some((10, 3)))")]
+    pub feed_consumed_kg: Decimal,
+
+    /// Birds weighed and their average weight
+    pub sample_size: Option<i32>,
+    #[sea_orm(column_type = "Decimal(Some((8, 3)))")]
+    pub sample_avg_weight_kg: Option<Decimal>,
+
+    #[sea_orm(column_type = "Decimal(Some((10, 2)))")]
+    pub water_litres: Option<Decimal>,
+    #[sea_orm(column_type = "Text")]
+    pub notes: Option<String>,
+
+    pub recorded_by: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+
+    /// Set when the log has been corrected after it was first recorded
+    pub corrected_at: Option<DateTimeWithTimeZone>,
+    pub corrected_by: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::batches::Entity",
+        from = "Column::BatchId",
+        to = "super::batches::Column::BatchId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Batches,
+}
+
+impl Related<super::batches::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Batches.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
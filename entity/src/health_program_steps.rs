@@ -0,0 +1,43 @@
+//! `SeaORM` Entity for health_program_steps
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "health_program_steps")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub step_id: i32,
+    pub program_id: i32,
+
+    /// Due this many days after the batch's start date
+    pub day_of_age: i32,
+    pub task_name: String,
+
+    /// Medicine or vaccine given, and how much of it per bird
+    pub item_code: Option<String>,
+    #[sea_orm(column_type = "Decimal(Some((10, 4)))")]
+    pub dose_per_bird: Option<Decimal>,
+    #[sea_orm(column_type = "Text")]
+    pub notes: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::health_programs::Entity",
+        from = "Column::ProgramId",
+        to = "super::health_programs::Column::ProgramId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    HealthPrograms,
+}
+
+impl Related<super::health_programs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::HealthPrograms.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
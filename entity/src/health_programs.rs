@@ -0,0 +1,31 @@
+//! `SeaORM` Entity for health_programs
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "health_programs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub program_id: i32,
+    #[sea_orm(unique)]
+    pub name: String,
+
+    /// Applied to new batches that don't name a program
+    pub is_default: bool,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::health_program_steps::Entity")]
+    HealthProgramSteps,
+}
+
+impl Related<super::health_program_steps::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::HealthProgramSteps.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,42 @@
+//! `SeaORM` Entity for inventory_locations
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "inventory_locations")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub item_code: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub location_id: i32,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub current_qty: Decimal,
+    pub last_updated: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::items::Entity",
+        from = "Column::ItemCode",
+        to = "super::items::Column::ItemCode",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Items,
+    #[sea_orm(
+        belongs_to = "super::storage_locations::Entity",
+        from = "Column::LocationId",
+        to = "super::storage_locations::Column::LocationId"
+    )]
+    StorageLocations,
+}
+
+impl Related<super::storage_locations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StorageLocations.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
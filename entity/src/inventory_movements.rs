@@ -16,6 +16,8 @@ pub struct Model {
     pub movement_type: MovementType,
     pub reference_id: Option<i32>,
     pub movement_date: DateTimeWithTimeZone,
+    /// Location the quantity entered or left
+    pub location_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
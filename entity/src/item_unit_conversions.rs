@@ -0,0 +1,38 @@
+//! `SeaORM` Entity for item_unit_conversions
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "item_unit_conversions")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub conversion_id: i32,
+    pub item_code: String,
+    pub unit: String,
+
+    /// How many of the item's base units one of `unit` holds
+    #[sea_orm(column_type = "Decimal(Some((14, 4)))")]
+    pub factor: Decimal,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::items::Entity",
+        from = "Column::ItemCode",
+        to = "super::items::Column::ItemCode",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Items,
+}
+
+impl Related<super::items::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Items.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -13,6 +13,14 @@ pub struct Model {
     pub item_name: String,
     pub unit: Option<String>,
     pub item_category: ItemCategory,
+
+    /// Stock level at which the item should be reordered; None if untracked
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub reorder_point: Option<Decimal>,
+    /// Buffer kept on top of the reorder point when suggesting an order
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub safety_stock: Decimal,
+    pub preferred_supplier_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -35,6 +35,9 @@ pub struct Model {
     /// Grouping UUID to tie the two sides of a transaction together
     pub txn_group_id: Uuid,
 
+    /// Set on reversal entries: the `txn_group_id` they mirror
+    pub reverses_txn_group_id: Option<Uuid>,
+
     /// Metadata
     pub created_at: DateTimeWithTimeZone,
     pub created_by: Option<i32>,
@@ -0,0 +1,49 @@
+//! `SeaORM` Entity for low_stock_alerts
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "low_stock_alerts")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub alert_id: i32,
+    pub item_code: String,
+    pub alert_date: Date,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub on_hand_qty: Decimal,
+
+    /// Quantity asked for by pending batch requirements
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub committed_qty: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub available_qty: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub reorder_point: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub safety_stock: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub suggested_order_qty: Decimal,
+    pub preferred_supplier_id: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::items::Entity",
+        from = "Column::ItemCode",
+        to = "super::items::Column::ItemCode",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Items,
+}
+
+impl Related<super::items::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Items.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -2,26 +2,53 @@
 
 pub mod prelude;
 
+pub mod accounting_periods;
 pub mod batch_allocation_lines;
 pub mod batch_allocations;
 pub mod batch_closure_summary;
+pub mod batch_health_tasks;
+pub mod batch_placements;
 pub mod batch_requirements;
 pub mod batch_sales;
+pub mod batch_status_changes;
 pub mod batches;
 pub mod bird_count_history;
 pub mod bird_sell_history;
+pub mod breed_standards;
+pub mod category_costing_methods;
+pub mod farmer_advances;
 pub mod farmer_commission_history;
+pub mod farmer_settlements;
 pub mod farmers;
+pub mod fiscal_years;
+pub mod flock_logs;
+pub mod health_program_steps;
+pub mod health_programs;
 pub mod inventory;
+pub mod inventory_locations;
 pub mod inventory_movements;
+pub mod item_unit_conversions;
 pub mod items;
 pub mod ledger_accounts;
 pub mod ledger_entries;
+pub mod low_stock_alerts;
 pub mod post;
+pub mod posting_rules;
 pub mod production_lines;
 pub mod purchases;
 pub mod sea_orm_active_enums;
+pub mod settlement_rate_tables;
+pub mod stock_adjustment_lines;
+pub mod stock_adjustments;
 pub mod stock_receipts;
+pub mod stock_take_lines;
+pub mod stock_takes;
+pub mod stock_transfers;
+pub mod storage_locations;
+pub mod supplier_payment_allocations;
+pub mod supplier_payments;
 pub mod suppliers;
+pub mod trader_receipt_allocations;
+pub mod trader_receipts;
 pub mod traders;
 pub mod users;
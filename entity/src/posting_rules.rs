@@ -0,0 +1,52 @@
+//! `SeaORM` Entity for posting_rules
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+use crate::sea_orm_active_enums::{ItemCategory, PostingEvent};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "posting_rules")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub rule_id: i32,
+
+    /// Business event this rule books
+    pub event: PostingEvent,
+
+    /// Item category the rule applies to; `None` is the fallback for the event
+    pub item_category: Option<ItemCategory>,
+
+    /// Account debited when the event is posted
+    pub debit_account_id: i32,
+
+    /// Account credited when the event is posted
+    pub credit_account_id: i32,
+
+    #[sea_orm(column_type = "Text")]
+    pub description: Option<String>,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::ledger_accounts::Entity",
+        from = "Column::DebitAccountId",
+        to = "super::ledger_accounts::Column::AccountId",
+        on_update = "Cascade",
+        on_delete = "Restrict"
+    )]
+    DebitAccount,
+
+    #[sea_orm(
+        belongs_to = "super::ledger_accounts::Entity",
+        from = "Column::CreditAccountId",
+        to = "super::ledger_accounts::Column::AccountId",
+        on_update = "Cascade",
+        on_delete = "Restrict"
+    )]
+    CreditAccount,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -9,7 +9,7 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub purchase_id: i32,
     pub item_code: String,
-    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    #[sea_orm(column_type = "Decimal(Some((14, 4)))")]
     pub cost_per_unit: Decimal,
     #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
     pub total_cost: Option<Decimal>,
@@ -17,6 +17,22 @@ pub struct Model {
     pub purchase_date: Date,
     pub supplier: Option<String>,
     pub created_by: Option<i32>,
+
+    /// Supplier the purchase is owed to; `supplier` is the legacy free-text name
+    pub supplier_id: Option<i32>,
+
+    /// Portion of the cost booked to payables rather than paid up front
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub amount_due: Decimal,
+
+    /// Settled so far by supplier payments
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub amount_paid: Decimal,
+
+    /// Set when the record has been reversed
+    pub voided_at: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "Text")]
+    pub void_reason: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -38,6 +54,15 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Items,
+
+    #[sea_orm(
+        belongs_to = "super::suppliers::Entity",
+        from = "Column::SupplierId",
+        to = "super::suppliers::Column::SupplierId",
+        on_update = "Cascade",
+        on_delete = "Restrict"
+    )]
+    Suppliers,
 }
 
 impl Related<super::users::Entity> for Entity {
@@ -52,4 +77,10 @@ impl Related<super::items::Entity> for Entity {
     }
 }
 
+impl Related<super::suppliers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Suppliers.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -6,10 +6,16 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "batch_status")]
 pub enum BatchStatus {
+    #[sea_orm(string_value = "planned")]
+    Planned,
     #[sea_orm(string_value = "open")]
     Open,
+    #[sea_orm(string_value = "selling")]
+    Selling,
     #[sea_orm(string_value = "closed")]
     Closed,
+    #[sea_orm(string_value = "settled")]
+    Settled,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
@@ -115,3 +121,85 @@ pub enum ItemCategory {
     #[sea_orm(string_value = "finished_birds")]
     FinishedBirds,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "posting_event")]
+pub enum PostingEvent {
+    #[sea_orm(string_value = "purchase")]
+    Purchase,
+    #[sea_orm(string_value = "allocation")]
+    Allocation,
+    #[sea_orm(string_value = "batch_sale")]
+    BatchSale,
+    #[sea_orm(string_value = "farmer_commission")]
+    FarmerCommission,
+    #[sea_orm(string_value = "supplier_payment")]
+    SupplierPayment,
+    #[sea_orm(string_value = "trader_receipt")]
+    TraderReceipt,
+    #[sea_orm(string_value = "farmer_advance")]
+    FarmerAdvance,
+    #[sea_orm(string_value = "farmer_deduction")]
+    FarmerDeduction,
+    #[sea_orm(string_value = "stock_loss")]
+    StockLoss,
+    #[sea_orm(string_value = "stock_gain")]
+    StockGain,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "farmer_advance_type"
+)]
+pub enum FarmerAdvanceType {
+    #[sea_orm(string_value = "advance")]
+    Advance,
+    #[sea_orm(string_value = "deduction")]
+    Deduction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "adjustment_reason")]
+pub enum AdjustmentReason {
+    #[sea_orm(string_value = "damaged")]
+    Damaged,
+    #[sea_orm(string_value = "expired")]
+    Expired,
+    #[sea_orm(string_value = "spillage")]
+    Spillage,
+    #[sea_orm(string_value = "count_correction")]
+    CountCorrection,
+    #[sea_orm(string_value = "other")]
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "location_type")]
+pub enum LocationType {
+    #[sea_orm(string_value = "warehouse")]
+    Warehouse,
+    #[sea_orm(string_value = "farm")]
+    Farm,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "stock_take_status")]
+pub enum StockTakeStatus {
+    #[sea_orm(string_value = "open")]
+    Open,
+    #[sea_orm(string_value = "approved")]
+    Approved,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "costing_method")]
+pub enum CostingMethod {
+    #[sea_orm(string_value = "fifo")]
+    Fifo,
+    #[sea_orm(string_value = "weighted_average")]
+    WeightedAverage,
+}
@@ -0,0 +1,52 @@
+//! `SeaORM` Entity for settlement_rate_tables
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "settlement_rate_tables")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub rate_table_id: i32,
+
+    /// Rate tables are never edited; a change is a new version. A batch is
+    /// settled on the latest version in effect on its start date.
+    #[sea_orm(unique)]
+    pub version: i32,
+    pub effective_from: Date,
+
+    /// Paid per kg of live weight sold
+    #[sea_orm(column_type = "Decimal(Some((10, 2)))")]
+    pub base_rate_per_kg: Decimal,
+
+    /// Target production cost per kg; the grower shares in the difference
+    #[sea_orm(column_type = "Decimal(Some((10, 2)))")]
+    pub standard_cost_per_kg: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((5, 2)))")]
+    pub incentive_share_pct: Decimal,
+
+    /// Deaths above the allowance are charged per bird
+    #[sea_orm(column_type = "Decimal(Some((5, 2)))")]
+    pub mortality_allowance_pct: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((10, 2)))")]
+    pub mortality_penalty_per_bird: Decimal,
+
+    #[sea_orm(column_type = "Text")]
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::farmer_settlements::Entity")]
+    FarmerSettlements,
+}
+
+impl Related<super::farmer_settlements::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FarmerSettlements.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
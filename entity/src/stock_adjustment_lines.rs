@@ -0,0 +1,45 @@
+//! `SeaORM` Entity for stock_adjustment_lines
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "stock_adjustment_lines")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub adjustment_line_id: i32,
+    pub adjustment_id: i32,
+    pub lot_id: i32,
+    pub qty: Decimal,
+    pub unit_cost: Decimal,
+    pub line_value: Decimal,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::stock_adjustments::Entity",
+        from = "Column::AdjustmentId",
+        to = "super::stock_adjustments::Column::AdjustmentId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    StockAdjustments,
+
+    #[sea_orm(
+        belongs_to = "super::stock_receipts::Entity",
+        from = "Column::LotId",
+        to = "super::stock_receipts::Column::LotId",
+        on_update = "Cascade",
+        on_delete = "Restrict"
+    )]
+    StockReceipts,
+}
+
+impl Related<super::stock_adjustments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StockAdjustments.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
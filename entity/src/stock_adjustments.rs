@@ -0,0 +1,51 @@
+//! `SeaORM` Entity for stock_adjustments
+
+use super::sea_orm_active_enums::AdjustmentReason;
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "stock_adjustments")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub adjustment_id: i32,
+    pub item_code: String,
+
+    /// Signed quantity: negative for write-offs, positive for stock found
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub qty_change: Decimal,
+    pub reason: AdjustmentReason,
+
+    /// Cost of the lots consumed, or of the lot created
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub value: Decimal,
+
+    /// Lot created by a positive adjustment
+    pub lot_id: Option<i32>,
+    pub location_id: Option<i32>,
+    pub adjustment_date: Date,
+    #[sea_orm(column_type = "Text")]
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::items::Entity",
+        from = "Column::ItemCode",
+        to = "super::items::Column::ItemCode"
+    )]
+    Items,
+    #[sea_orm(has_many = "super::stock_adjustment_lines::Entity")]
+    StockAdjustmentLines,
+}
+
+impl Related<super::stock_adjustment_lines::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StockAdjustmentLines.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -13,6 +13,14 @@ pub struct Model {
     pub unit_cost: Decimal,
     pub received_date: Date,
     pub supplier: Option<String>,
+    /// Where the lot is held
+    pub location_id: i32,
+    /// Lot this one was split from by a transfer
+    pub source_lot_id: Option<i32>,
+    /// Manufacturer's batch/lot number printed on the pack
+    pub manufacturer_lot_no: Option<String>,
+    /// Not to be issued after this date
+    pub expiry_date: Option<Date>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,57 @@
+//! `SeaORM` Entity for stock_take_lines
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "stock_take_lines")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub line_id: i32,
+    pub stock_take_id: i32,
+    pub item_code: String,
+
+    /// Stock on the books at the location when the count was opened
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub book_qty: Decimal,
+    /// Remaining quantity in the location's lots when the count was opened
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub lot_qty: Decimal,
+
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub counted_qty: Option<Decimal>,
+    pub counted_by: Option<i32>,
+    pub counted_at: Option<DateTimeWithTimeZone>,
+
+    /// Counted less book, fixed when the count is approved
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub variance: Option<Decimal>,
+    /// Count correction posted for the variance
+    pub adjustment_id: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::stock_takes::Entity",
+        from = "Column::StockTakeId",
+        to = "super::stock_takes::Column::StockTakeId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    StockTakes,
+    #[sea_orm(
+        belongs_to = "super::items::Entity",
+        from = "Column::ItemCode",
+        to = "super::items::Column::ItemCode"
+    )]
+    Items,
+}
+
+impl Related<super::stock_takes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StockTakes.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
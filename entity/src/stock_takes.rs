@@ -0,0 +1,42 @@
+//! `SeaORM` Entity for stock_takes
+
+use super::sea_orm_active_enums::StockTakeStatus;
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "stock_takes")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub stock_take_id: i32,
+    /// Location being counted
+    pub location_id: i32,
+    pub status: StockTakeStatus,
+    pub count_date: Date,
+    #[sea_orm(column_type = "Text")]
+    pub notes: Option<String>,
+    pub opened_by: Option<i32>,
+    pub opened_at: DateTimeWithTimeZone,
+    pub approved_by: Option<i32>,
+    pub approved_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::stock_take_lines::Entity")]
+    StockTakeLines,
+    #[sea_orm(
+        belongs_to = "super::storage_locations::Entity",
+        from = "Column::LocationId",
+        to = "super::storage_locations::Column::LocationId"
+    )]
+    StorageLocations,
+}
+
+impl Related<super::stock_take_lines::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StockTakeLines.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,40 @@
+//! `SeaORM` Entity for stock_transfers
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "stock_transfers")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub transfer_id: i32,
+    pub item_code: String,
+    pub from_location_id: i32,
+    pub to_location_id: i32,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub quantity: Decimal,
+
+    /// Cost of the lots moved
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub value: Decimal,
+    pub transfer_date: Date,
+
+    /// Set when the stock was sent to a farm for a batch allocation
+    pub allocation_id: Option<i32>,
+    #[sea_orm(column_type = "Text")]
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::items::Entity",
+        from = "Column::ItemCode",
+        to = "super::items::Column::ItemCode"
+    )]
+    Items,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,43 @@
+//! `SeaORM` Entity for storage_locations
+
+use super::sea_orm_active_enums::LocationType;
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "storage_locations")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub location_id: i32,
+    #[sea_orm(unique)]
+    pub name: String,
+    pub location_type: LocationType,
+
+    /// Farmer whose farm this is; farm locations only
+    #[sea_orm(unique)]
+    pub farmer_id: Option<i32>,
+
+    /// Warehouse used when a request names no location
+    pub is_default: bool,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::farmers::Entity",
+        from = "Column::FarmerId",
+        to = "super::farmers::Column::FarmerId"
+    )]
+    Farmers,
+    #[sea_orm(has_many = "super::inventory_locations::Entity")]
+    InventoryLocations,
+}
+
+impl Related<super::inventory_locations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::InventoryLocations.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,51 @@
+//! `SeaORM` Entity for supplier_payment_allocations
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// The part of a supplier payment applied to one purchase.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "supplier_payment_allocations")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub payment_id: i32,
+    pub purchase_id: i32,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub amount: Decimal,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::supplier_payments::Entity",
+        from = "Column::PaymentId",
+        to = "super::supplier_payments::Column::PaymentId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    SupplierPayments,
+
+    #[sea_orm(
+        belongs_to = "super::purchases::Entity",
+        from = "Column::PurchaseId",
+        to = "super::purchases::Column::PurchaseId",
+        on_update = "Cascade",
+        on_delete = "Restrict"
+    )]
+    Purchases,
+}
+
+impl Related<super::supplier_payments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SupplierPayments.def()
+    }
+}
+
+impl Related<super::purchases::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Purchases.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,59 @@
+//! `SeaORM` Entity for supplier_payments
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "supplier_payments")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub payment_id: i32,
+    pub supplier_id: i32,
+    pub payment_date: Date,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub amount: Decimal,
+
+    /// Cash or bank account the payment was made from
+    pub payment_account_id: i32,
+
+    /// Cheque number, UTR or similar
+    pub reference: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+
+    /// Set when the record has been reversed
+    pub voided_at: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "Text")]
+    pub void_reason: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::suppliers::Entity",
+        from = "Column::SupplierId",
+        to = "super::suppliers::Column::SupplierId",
+        on_update = "Cascade",
+        on_delete = "Restrict"
+    )]
+    Suppliers,
+
+    #[sea_orm(has_many = "super::supplier_payment_allocations::Entity")]
+    SupplierPaymentAllocations,
+}
+
+impl Related<super::suppliers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Suppliers.def()
+    }
+}
+
+impl Related<super::supplier_payment_allocations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SupplierPaymentAllocations.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -22,6 +22,23 @@ pub struct Model {
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::purchases::Entity")]
+    Purchases,
+    #[sea_orm(has_many = "super::supplier_payments::Entity")]
+    SupplierPayments,
+}
+
+impl Related<super::purchases::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Purchases.def()
+    }
+}
+
+impl Related<super::supplier_payments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SupplierPayments.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,51 @@
+//! `SeaORM` Entity for trader_receipt_allocations
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// The part of a trader receipt applied to one batch sale.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "trader_receipt_allocations")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub receipt_id: i32,
+    pub sale_id: i32,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub amount: Decimal,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::trader_receipts::Entity",
+        from = "Column::ReceiptId",
+        to = "super::trader_receipts::Column::ReceiptId",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    TraderReceipts,
+
+    #[sea_orm(
+        belongs_to = "super::batch_sales::Entity",
+        from = "Column::SaleId",
+        to = "super::batch_sales::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Restrict"
+    )]
+    BatchSales,
+}
+
+impl Related<super::trader_receipts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TraderReceipts.def()
+    }
+}
+
+impl Related<super::batch_sales::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BatchSales.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
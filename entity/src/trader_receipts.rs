@@ -0,0 +1,59 @@
+//! `SeaORM` Entity for trader_receipts
+
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "trader_receipts")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub receipt_id: i32,
+    pub trader_id: i32,
+    pub receipt_date: Date,
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub amount: Decimal,
+
+    /// Cash or bank account the money was received into
+    pub receipt_account_id: i32,
+
+    /// Cheque number, UTR or similar
+    pub reference: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+
+    /// Set when the record has been reversed
+    pub voided_at: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "Text")]
+    pub void_reason: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::traders::Entity",
+        from = "Column::TraderId",
+        to = "super::traders::Column::TraderId",
+        on_update = "Cascade",
+        on_delete = "Restrict"
+    )]
+    Traders,
+
+    #[sea_orm(has_many = "super::trader_receipt_allocations::Entity")]
+    TraderReceiptAllocations,
+}
+
+impl Related<super::traders::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Traders.def()
+    }
+}
+
+impl Related<super::trader_receipt_allocations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TraderReceiptAllocations.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
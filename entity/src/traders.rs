@@ -17,12 +17,18 @@ pub struct Model {
     pub bank_name: String,
     pub ifsc_code: String,
     pub created_at: DateTimeWithTimeZone,
+
+    /// Cap on unpaid credit sales; `None` means no limit
+    #[sea_orm(column_type = "Decimal(Some((12, 2)))")]
+    pub credit_limit: Option<Decimal>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::bird_sell_history::Entity")]
     BirdSellHistory,
+    #[sea_orm(has_many = "super::trader_receipts::Entity")]
+    TraderReceipts,
 }
 
 impl Related<super::bird_sell_history::Entity> for Entity {
@@ -31,4 +37,10 @@ impl Related<super::bird_sell_history::Entity> for Entity {
     }
 }
 
+impl Related<super::trader_receipts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TraderReceipts.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -1,5 +1,7 @@
 pub use sea_orm_migration::prelude::*;
 
+pub mod reconcile;
+
 mod m20220101_000001_create_table;
 mod m20250810_161418_iteration1;
 mod m20250819_083602_iteration_2;
@@ -8,6 +10,28 @@ mod m20250826_234204_stock_receipts;
 mod m20250901_223316_farmer_commission;
 mod m20250906_182108_closed_batches;
 mod m20250906_211511_batch_sales;
+mod m20251018_093000_posting_rules;
+mod m20251018_120000_reversals;
+mod m20251018_150000_accounting_periods;
+mod m20251019_090000_supplier_payables;
+mod m20251019_120000_trader_receivables;
+mod m20251019_150000_batch_cost_sheet;
+mod m20251020_090000_batch_lifecycle;
+mod m20251020_120000_flock_logs;
+mod m20251020_150000_health_programs;
+mod m20251021_090000_farmer_settlements;
+mod m20251021_120000_farmer_advances;
+mod m20251021_150000_batch_stocking;
+mod m20251022_090000_stock_adjustments;
+mod m20251022_120000_storage_locations;
+mod m20251022_150000_stock_takes;
+mod m20251023_090000_reorder_levels;
+mod m20251023_120000_costing_methods;
+mod m20251023_150000_lot_expiry;
+mod m20251024_090000_units_of_measure;
+mod m20251024_120000_batch_placements;
+mod m20251024_150000_posting_rule_fallbacks;
+mod m20251025_090000_stocking_history;
 
 pub struct Migrator;
 
@@ -23,6 +47,28 @@ impl MigratorTrait for Migrator {
             Box::new(m20250901_223316_farmer_commission::Migration),
             Box::new(m20250906_182108_closed_batches::Migration),
             Box::new(m20250906_211511_batch_sales::Migration),
+            Box::new(m20251018_093000_posting_rules::Migration),
+            Box::new(m20251018_120000_reversals::Migration),
+            Box::new(m20251018_150000_accounting_periods::Migration),
+            Box::new(m20251019_090000_supplier_payables::Migration),
+            Box::new(m20251019_120000_trader_receivables::Migration),
+            Box::new(m20251019_150000_batch_cost_sheet::Migration),
+            Box::new(m20251020_090000_batch_lifecycle::Migration),
+            Box::new(m20251020_120000_flock_logs::Migration),
+            Box::new(m20251020_150000_health_programs::Migration),
+            Box::new(m20251021_090000_farmer_settlements::Migration),
+            Box::new(m20251021_120000_farmer_advances::Migration),
+            Box::new(m20251021_150000_batch_stocking::Migration),
+            Box::new(m20251022_090000_stock_adjustments::Migration),
+            Box::new(m20251022_120000_storage_locations::Migration),
+            Box::new(m20251022_150000_stock_takes::Migration),
+            Box::new(m20251023_090000_reorder_levels::Migration),
+            Box::new(m20251023_120000_costing_methods::Migration),
+            Box::new(m20251023_150000_lot_expiry::Migration),
+            Box::new(m20251024_090000_units_of_measure::Migration),
+            Box::new(m20251024_120000_batch_placements::Migration),
+            Box::new(m20251024_150000_posting_rule_fallbacks::Migration),
+            Box::new(m20251025_090000_stocking_history::Migration),
         ]
     }
 }
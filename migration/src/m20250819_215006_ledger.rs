@@ -218,7 +218,7 @@ enum LedgerAccountType {
 }
 
 #[derive(DeriveIden)]
-enum LedgerAccounts {
+pub enum LedgerAccounts {
     Table,
     AccountId,
     Name,
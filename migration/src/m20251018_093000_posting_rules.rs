@@ -0,0 +1,155 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250819_215006_ledger::LedgerAccounts;
+
+/// Migration for the chart of accounts: posting_rules maps each business
+/// event (and optionally item category) to the debit/credit ledger accounts.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(PostingEvent::Table)
+                    .values([
+                        PostingEvent::Purchase,
+                        PostingEvent::Allocation,
+                        PostingEvent::BatchSale,
+                        PostingEvent::FarmerCommission,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PostingRules::Table)
+                    .if_not_exists()
+                    .col(pk_auto(PostingRules::RuleId))
+                    .col(
+                        ColumnDef::new(PostingRules::Event)
+                            .custom(PostingEvent::Table)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PostingRules::ItemCategory)
+                            .custom(Alias::new("item_category"))
+                            .null(),
+                    )
+                    .col(integer(PostingRules::DebitAccountId).not_null())
+                    .col(integer(PostingRules::CreditAccountId).not_null())
+                    .col(ColumnDef::new(PostingRules::Description).text().null())
+                    .col(
+                        timestamp_with_time_zone(PostingRules::UpdatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_posting_rules_debit_account")
+                            .from(PostingRules::Table, PostingRules::DebitAccountId)
+                            .to(LedgerAccounts::Table, LedgerAccounts::AccountId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_posting_rules_credit_account")
+                            .from(PostingRules::Table, PostingRules::CreditAccountId)
+                            .to(LedgerAccounts::Table, LedgerAccounts::AccountId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // one rule per (event, item_category)
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_unique_posting_rules_event_category")
+                    .table(PostingRules::Table)
+                    .col(PostingRules::Event)
+                    .col(PostingRules::ItemCategory)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Default chart of accounts. Accounts are matched by name, so a
+        // database whose ids differ from the seed still gets valid rules.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+        INSERT INTO ledger_accounts (name, account_type) VALUES
+            ('cash', 'asset'),
+            ('inventory-medicine', 'asset'),
+            ('inventory-feed', 'asset'),
+            ('inventory-chicks', 'asset'),
+            ('liability', 'liability'),
+            ('commission-farmer', 'expense'),
+            ('farm-expense', 'expense'),
+            ('bird-sales', 'revenue')
+        ON CONFLICT (name) DO NOTHING;
+
+        INSERT INTO posting_rules (event, item_category, debit_account_id, credit_account_id, description)
+        SELECT r.event::posting_event, r.item_category::item_category, d.account_id, c.account_id, r.description
+        FROM (VALUES
+            ('purchase', 'medicine', 'inventory-medicine', 'cash', 'Medicine purchase'),
+            ('purchase', 'feed', 'inventory-feed', 'cash', 'Feed purchase'),
+            ('purchase', 'chicks', 'inventory-chicks', 'cash', 'Chick purchase'),
+            ('allocation', 'medicine', 'farm-expense', 'inventory-medicine', 'Medicine allocated to a batch'),
+            ('allocation', 'feed', 'farm-expense', 'inventory-feed', 'Feed allocated to a batch'),
+            ('allocation', 'chicks', 'farm-expense', 'inventory-chicks', 'Chicks allocated to a batch'),
+            ('batch_sale', NULL, 'cash', 'bird-sales', 'Bird sale to trader'),
+            ('farmer_commission', NULL, 'commission-farmer', 'cash', 'Farmer commission paid')
+        ) AS r(event, item_category, debit_name, credit_name, description)
+        JOIN ledger_accounts d ON d.name = r.debit_name
+        JOIN ledger_accounts c ON c.name = r.credit_name;
+        "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PostingRules::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(PostingEvent::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum PostingEvent {
+    Table,
+    Purchase,
+    Allocation,
+    BatchSale,
+    FarmerCommission,
+}
+
+#[derive(DeriveIden)]
+pub enum PostingRules {
+    Table,
+    RuleId,
+    Event,
+    ItemCategory,
+    DebitAccountId,
+    CreditAccountId,
+    Description,
+    UpdatedAt,
+}
@@ -0,0 +1,120 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250810_161418_iteration1::{BatchAllocations, Purchases};
+
+/// Void markers on source records and a back-link from reversal entries to
+/// the transaction they reverse.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LedgerEntries::Table)
+                    .add_column(
+                        ColumnDef::new(LedgerEntries::ReversesTxnGroupId)
+                            .uuid()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ledger_entries_reverses_txn_group_id")
+                    .table(LedgerEntries::Table)
+                    .col(LedgerEntries::ReversesTxnGroupId)
+                    .to_owned(),
+            )
+            .await?;
+
+        for table in [
+            Purchases::Table.into_iden(),
+            BatchSales::Table.into_iden(),
+            FarmerCommissionHistory::Table.into_iden(),
+            BatchAllocations::Table.into_iden(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .add_column(
+                            ColumnDef::new(Void::VoidedAt)
+                                .timestamp_with_time_zone()
+                                .null(),
+                        )
+                        .add_column(ColumnDef::new(Void::VoidReason).text().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in [
+            Purchases::Table.into_iden(),
+            BatchSales::Table.into_iden(),
+            FarmerCommissionHistory::Table.into_iden(),
+            BatchAllocations::Table.into_iden(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .drop_column(Void::VoidedAt)
+                        .drop_column(Void::VoidReason)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_ledger_entries_reverses_txn_group_id")
+                    .table(LedgerEntries::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LedgerEntries::Table)
+                    .drop_column(LedgerEntries::ReversesTxnGroupId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum LedgerEntries {
+    Table,
+    ReversesTxnGroupId,
+}
+
+#[derive(DeriveIden)]
+enum BatchSales {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum FarmerCommissionHistory {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum Void {
+    VoidedAt,
+    VoidReason,
+}
@@ -0,0 +1,139 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+/// Migration for fiscal years and their monthly accounting periods. A locked
+/// period refuses postings dated inside it; closing a year locks all of its
+/// periods and books revenue and expenses into retained earnings.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(FiscalYears::Table)
+                    .if_not_exists()
+                    .col(pk_auto(FiscalYears::FiscalYearId))
+                    .col(string(FiscalYears::Name).unique_key())
+                    .col(date(FiscalYears::StartDate))
+                    .col(date(FiscalYears::EndDate))
+                    .col(
+                        ColumnDef::new(FiscalYears::ClosedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(integer_null(FiscalYears::ClosedBy))
+                    .col(ColumnDef::new(FiscalYears::ClosingTxnGroupId).uuid().null())
+                    .col(
+                        timestamp_with_time_zone(FiscalYears::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AccountingPeriods::Table)
+                    .if_not_exists()
+                    .col(pk_auto(AccountingPeriods::PeriodId))
+                    .col(integer(AccountingPeriods::FiscalYearId))
+                    .col(date(AccountingPeriods::PeriodStart))
+                    .col(date(AccountingPeriods::PeriodEnd))
+                    .col(boolean(AccountingPeriods::IsLocked).default(false))
+                    .col(
+                        ColumnDef::new(AccountingPeriods::LockedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(integer_null(AccountingPeriods::LockedBy))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_accounting_periods_fiscal_year")
+                            .from(AccountingPeriods::Table, AccountingPeriods::FiscalYearId)
+                            .to(FiscalYears::Table, FiscalYears::FiscalYearId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_unique_accounting_periods_start")
+                    .table(AccountingPeriods::Table)
+                    .col(AccountingPeriods::PeriodStart)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Equity account the year-end close books net profit into
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+        INSERT INTO ledger_accounts (name, account_type) VALUES
+            ('retained-earnings', 'equity')
+        ON CONFLICT (name) DO NOTHING;
+        "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AccountingPeriods::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(FiscalYears::Table).to_owned())
+            .await?;
+
+        // leave the retained-earnings account if anything was posted to it
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+        DELETE FROM ledger_accounts a
+        WHERE a.name = 'retained-earnings'
+          AND NOT EXISTS (SELECT 1 FROM ledger_entries e WHERE e.account_id = a.account_id);
+        "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum FiscalYears {
+    Table,
+    FiscalYearId,
+    Name,
+    StartDate,
+    EndDate,
+    ClosedAt,
+    ClosedBy,
+    ClosingTxnGroupId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum AccountingPeriods {
+    Table,
+    PeriodId,
+    FiscalYearId,
+    PeriodStart,
+    PeriodEnd,
+    IsLocked,
+    LockedAt,
+    LockedBy,
+}
@@ -0,0 +1,272 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250810_161418_iteration1::Purchases;
+use crate::m20250819_215006_ledger::LedgerAccounts;
+
+/// Migration for the supplier payables sub-ledger: purchases link to
+/// `suppliers` and track what is owed, and supplier payments settle specific
+/// purchases.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Purchases::Table)
+                    .add_column(integer_null(Payables::SupplierId))
+                    .add_column(decimal_len(Payables::AmountDue, 12, 2).default(0))
+                    .add_column(decimal_len(Payables::AmountPaid, 12, 2).default(0))
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_purchases_supplier")
+                            .from_tbl(Purchases::Table)
+                            .from_col(Payables::SupplierId)
+                            .to_tbl(Suppliers::Table)
+                            .to_col(Suppliers::SupplierId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // link existing purchases whose free-text supplier matches a supplier name
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+        UPDATE purchases p
+        SET supplier_id = s.supplier_id
+        FROM suppliers s
+        WHERE p.supplier_id IS NULL
+          AND lower(trim(p.supplier)) = lower(trim(s.name));
+        "#,
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(SupplierPayments::Table)
+                    .if_not_exists()
+                    .col(pk_auto(SupplierPayments::PaymentId))
+                    .col(integer(SupplierPayments::SupplierId))
+                    .col(date(SupplierPayments::PaymentDate))
+                    .col(decimal_len(SupplierPayments::Amount, 12, 2))
+                    .col(integer(SupplierPayments::PaymentAccountId))
+                    .col(string_null(SupplierPayments::Reference))
+                    .col(ColumnDef::new(SupplierPayments::Notes).text().null())
+                    .col(integer_null(SupplierPayments::CreatedBy))
+                    .col(
+                        timestamp_with_time_zone(SupplierPayments::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(SupplierPayments::VoidedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(SupplierPayments::VoidReason).text().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_supplier_payments_supplier")
+                            .from(SupplierPayments::Table, SupplierPayments::SupplierId)
+                            .to(Suppliers::Table, Suppliers::SupplierId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_supplier_payments_account")
+                            .from(SupplierPayments::Table, SupplierPayments::PaymentAccountId)
+                            .to(LedgerAccounts::Table, LedgerAccounts::AccountId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(SupplierPaymentAllocations::Table)
+                    .if_not_exists()
+                    .col(pk_auto(SupplierPaymentAllocations::Id))
+                    .col(integer(SupplierPaymentAllocations::PaymentId))
+                    .col(integer(SupplierPaymentAllocations::PurchaseId))
+                    .col(decimal_len(SupplierPaymentAllocations::Amount, 12, 2))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_supplier_payment_allocations_payment")
+                            .from(
+                                SupplierPaymentAllocations::Table,
+                                SupplierPaymentAllocations::PaymentId,
+                            )
+                            .to(SupplierPayments::Table, SupplierPayments::PaymentId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_supplier_payment_allocations_purchase")
+                            .from(
+                                SupplierPaymentAllocations::Table,
+                                SupplierPaymentAllocations::PurchaseId,
+                            )
+                            .to(Purchases::Table, Purchases::PurchaseId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_supplier_payment_allocations_purchase")
+                    .table(SupplierPaymentAllocations::Table)
+                    .col(SupplierPaymentAllocations::PurchaseId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Payments debit the payables account and credit cash unless the
+        // payment names another account
+        let conn = manager.get_connection();
+        recreate_posting_event_type(
+            conn,
+            &[
+                "purchase",
+                "allocation",
+                "batch_sale",
+                "farmer_commission",
+                "supplier_payment",
+            ],
+        )
+        .await?;
+
+        conn.execute_unprepared(
+            r#"
+        INSERT INTO posting_rules (event, item_category, debit_account_id, credit_account_id, description)
+        SELECT 'supplier_payment'::posting_event, NULL, d.account_id, c.account_id, 'Payment to supplier'
+        FROM ledger_accounts d, ledger_accounts c
+        WHERE d.name = 'liability' AND c.name = 'cash'
+        ON CONFLICT DO NOTHING;
+        "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        conn.execute_unprepared("DELETE FROM posting_rules WHERE event = 'supplier_payment';")
+            .await?;
+        recreate_posting_event_type(
+            conn,
+            &["purchase", "allocation", "batch_sale", "farmer_commission"],
+        )
+        .await?;
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(SupplierPaymentAllocations::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(SupplierPayments::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Purchases::Table)
+                    .drop_foreign_key(Alias::new("fk_purchases_supplier"))
+                    .drop_column(Payables::SupplierId)
+                    .drop_column(Payables::AmountDue)
+                    .drop_column(Payables::AmountPaid)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Replace `posting_event` with a type holding exactly `values`.
+///
+/// `ALTER TYPE ... ADD VALUE` can't be used in the transaction that adds it,
+/// which would stop a migration from seeding rules for the new event, so the
+/// type is rebuilt and `posting_rules.event` cast across instead.
+pub(crate) async fn recreate_posting_event_type(
+    conn: &SchemaManagerConnection<'_>,
+    values: &[&str],
+) -> Result<(), DbErr> {
+    let values = values
+        .iter()
+        .map(|v| format!("'{}'", v))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    conn.execute_unprepared(&format!(
+        r#"
+        ALTER TYPE posting_event RENAME TO posting_event_old;
+        CREATE TYPE posting_event AS ENUM ({});
+        ALTER TABLE posting_rules
+            ALTER COLUMN event TYPE posting_event USING event::text::posting_event;
+        DROP TYPE posting_event_old;
+        "#,
+        values
+    ))
+    .await?;
+
+    Ok(())
+}
+
+#[derive(DeriveIden)]
+enum Payables {
+    SupplierId,
+    AmountDue,
+    AmountPaid,
+}
+
+#[derive(DeriveIden)]
+enum Suppliers {
+    Table,
+    SupplierId,
+}
+
+#[derive(DeriveIden)]
+pub enum SupplierPayments {
+    Table,
+    PaymentId,
+    SupplierId,
+    PaymentDate,
+    Amount,
+    PaymentAccountId,
+    Reference,
+    Notes,
+    CreatedBy,
+    CreatedAt,
+    VoidedAt,
+    VoidReason,
+}
+
+#[derive(DeriveIden)]
+pub enum SupplierPaymentAllocations {
+    Table,
+    Id,
+    PaymentId,
+    PurchaseId,
+    Amount,
+}
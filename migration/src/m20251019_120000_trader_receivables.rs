@@ -0,0 +1,249 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250819_215006_ledger::LedgerAccounts;
+use crate::m20251019_090000_supplier_payables::recreate_posting_event_type;
+
+/// Migration for the trader receivables sub-ledger: batch sales can be made
+/// on credit, traders get an optional credit limit, and trader receipts
+/// settle specific sales.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Traders::Table)
+                    .add_column(decimal_len_null(Traders::CreditLimit, 12, 2))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BatchSales::Table)
+                    .add_column(boolean(BatchSales::OnCredit).default(false))
+                    .add_column(decimal_len(BatchSales::AmountDue, 12, 2).default(0))
+                    .add_column(decimal_len(BatchSales::AmountReceived, 12, 2).default(0))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TraderReceipts::Table)
+                    .if_not_exists()
+                    .col(pk_auto(TraderReceipts::ReceiptId))
+                    .col(integer(TraderReceipts::TraderId))
+                    .col(date(TraderReceipts::ReceiptDate))
+                    .col(decimal_len(TraderReceipts::Amount, 12, 2))
+                    .col(integer(TraderReceipts::ReceiptAccountId))
+                    .col(string_null(TraderReceipts::Reference))
+                    .col(ColumnDef::new(TraderReceipts::Notes).text().null())
+                    .col(integer_null(TraderReceipts::CreatedBy))
+                    .col(
+                        timestamp_with_time_zone(TraderReceipts::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(TraderReceipts::VoidedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(TraderReceipts::VoidReason).text().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_trader_receipts_trader")
+                            .from(TraderReceipts::Table, TraderReceipts::TraderId)
+                            .to(Traders::Table, Traders::TraderId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_trader_receipts_account")
+                            .from(TraderReceipts::Table, TraderReceipts::ReceiptAccountId)
+                            .to(LedgerAccounts::Table, LedgerAccounts::AccountId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TraderReceiptAllocations::Table)
+                    .if_not_exists()
+                    .col(pk_auto(TraderReceiptAllocations::Id))
+                    .col(integer(TraderReceiptAllocations::ReceiptId))
+                    .col(integer(TraderReceiptAllocations::SaleId))
+                    .col(decimal_len(TraderReceiptAllocations::Amount, 12, 2))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_trader_receipt_allocations_receipt")
+                            .from(
+                                TraderReceiptAllocations::Table,
+                                TraderReceiptAllocations::ReceiptId,
+                            )
+                            .to(TraderReceipts::Table, TraderReceipts::ReceiptId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_trader_receipt_allocations_sale")
+                            .from(
+                                TraderReceiptAllocations::Table,
+                                TraderReceiptAllocations::SaleId,
+                            )
+                            .to(BatchSales::Table, BatchSales::Id)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_trader_receipt_allocations_sale")
+                    .table(TraderReceiptAllocations::Table)
+                    .col(TraderReceiptAllocations::SaleId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Receipts debit cash and credit the receivables control account;
+        // credit sales debit that same account instead of cash
+        let conn = manager.get_connection();
+        recreate_posting_event_type(
+            conn,
+            &[
+                "purchase",
+                "allocation",
+                "batch_sale",
+                "farmer_commission",
+                "supplier_payment",
+                "trader_receipt",
+            ],
+        )
+        .await?;
+
+        conn.execute_unprepared(
+            r#"
+        INSERT INTO ledger_accounts (name, account_type) VALUES
+            ('accounts-receivable', 'asset')
+        ON CONFLICT (name) DO NOTHING;
+
+        INSERT INTO posting_rules (event, item_category, debit_account_id, credit_account_id, description)
+        SELECT 'trader_receipt'::posting_event, NULL, d.account_id, c.account_id, 'Payment received from trader'
+        FROM ledger_accounts d, ledger_accounts c
+        WHERE d.name = 'cash' AND c.name = 'accounts-receivable'
+        ON CONFLICT DO NOTHING;
+        "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        conn.execute_unprepared("DELETE FROM posting_rules WHERE event = 'trader_receipt';")
+            .await?;
+        recreate_posting_event_type(
+            conn,
+            &[
+                "purchase",
+                "allocation",
+                "batch_sale",
+                "farmer_commission",
+                "supplier_payment",
+            ],
+        )
+        .await?;
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(TraderReceiptAllocations::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(TraderReceipts::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BatchSales::Table)
+                    .drop_column(BatchSales::OnCredit)
+                    .drop_column(BatchSales::AmountDue)
+                    .drop_column(BatchSales::AmountReceived)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Traders::Table)
+                    .drop_column(Traders::CreditLimit)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Traders {
+    Table,
+    TraderId,
+    CreditLimit,
+}
+
+#[derive(DeriveIden)]
+enum BatchSales {
+    Table,
+    Id,
+    OnCredit,
+    AmountDue,
+    AmountReceived,
+}
+
+#[derive(DeriveIden)]
+pub enum TraderReceipts {
+    Table,
+    ReceiptId,
+    TraderId,
+    ReceiptDate,
+    Amount,
+    ReceiptAccountId,
+    Reference,
+    Notes,
+    CreatedBy,
+    CreatedAt,
+    VoidedAt,
+    VoidReason,
+}
+
+#[derive(DeriveIden)]
+pub enum TraderReceiptAllocations {
+    Table,
+    Id,
+    ReceiptId,
+    SaleId,
+    Amount,
+}
@@ -0,0 +1,115 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250810_161418_iteration1::Batches;
+
+/// Migration for server-side batch closing: the closure summary becomes a
+/// full cost sheet, and farmer commissions can be tied to the batch they pay
+/// for.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BatchClosureSummary::Table)
+                    .add_column(decimal_len(BatchClosureSummary::ChickCost, 12, 2).default(0))
+                    .add_column(decimal_len(BatchClosureSummary::FeedCost, 12, 2).default(0))
+                    .add_column(decimal_len(BatchClosureSummary::MedicineCost, 12, 2).default(0))
+                    .add_column(
+                        decimal_len(BatchClosureSummary::FarmerCommission, 12, 2).default(0),
+                    )
+                    .add_column(decimal_len(BatchClosureSummary::TotalCost, 12, 2).default(0))
+                    .add_column(integer(BatchClosureSummary::BirdsPlaced).default(0))
+                    .add_column(integer(BatchClosureSummary::Mortality).default(0))
+                    .add_column(decimal_len(BatchClosureSummary::MortalityPct, 6, 2).default(0))
+                    .add_column(integer(BatchClosureSummary::BirdsSold).default(0))
+                    .add_column(
+                        timestamp_with_time_zone(BatchClosureSummary::ClosedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .add_column(integer_null(BatchClosureSummary::ClosedBy))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FarmerCommissionHistory::Table)
+                    .add_column(integer_null(FarmerCommissionHistory::BatchId))
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_farmer_commission_history_batch")
+                            .from_tbl(FarmerCommissionHistory::Table)
+                            .from_col(FarmerCommissionHistory::BatchId)
+                            .to_tbl(Batches::Table)
+                            .to_col(Batches::BatchId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FarmerCommissionHistory::Table)
+                    .drop_foreign_key(Alias::new("fk_farmer_commission_history_batch"))
+                    .drop_column(FarmerCommissionHistory::BatchId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BatchClosureSummary::Table)
+                    .drop_column(BatchClosureSummary::ChickCost)
+                    .drop_column(BatchClosureSummary::FeedCost)
+                    .drop_column(BatchClosureSummary::MedicineCost)
+                    .drop_column(BatchClosureSummary::FarmerCommission)
+                    .drop_column(BatchClosureSummary::TotalCost)
+                    .drop_column(BatchClosureSummary::BirdsPlaced)
+                    .drop_column(BatchClosureSummary::Mortality)
+                    .drop_column(BatchClosureSummary::MortalityPct)
+                    .drop_column(BatchClosureSummary::BirdsSold)
+                    .drop_column(BatchClosureSummary::ClosedAt)
+                    .drop_column(BatchClosureSummary::ClosedBy)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BatchClosureSummary {
+    Table,
+    ChickCost,
+    FeedCost,
+    MedicineCost,
+    FarmerCommission,
+    TotalCost,
+    BirdsPlaced,
+    Mortality,
+    MortalityPct,
+    BirdsSold,
+    ClosedAt,
+    ClosedBy,
+}
+
+#[derive(DeriveIden)]
+enum FarmerCommissionHistory {
+    Table,
+    BatchId,
+}
@@ -0,0 +1,116 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250810_161418_iteration1::Batches;
+
+/// Migration for the batch lifecycle: `batch_status` grows from open/closed to
+/// planned -> open -> selling -> closed -> settled, and every status change is
+/// recorded in `batch_status_changes`.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+        ALTER TABLE batches ALTER COLUMN status DROP DEFAULT;
+        ALTER TYPE batch_status RENAME TO batch_status_old;
+        CREATE TYPE batch_status AS ENUM ('planned', 'open', 'selling', 'closed', 'settled');
+        ALTER TABLE batches
+            ALTER COLUMN status TYPE batch_status USING status::text::batch_status;
+        ALTER TABLE batches ALTER COLUMN status SET DEFAULT 'open';
+        DROP TYPE batch_status_old;
+        "#,
+            )
+            .await?;
+
+        // Open batches that already have sales are selling
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+        UPDATE batches SET status = 'selling'
+        WHERE status = 'open'
+          AND EXISTS (
+            SELECT 1 FROM batch_sales s
+            WHERE s.batch_id = batches.batch_id AND s.voided_at IS NULL
+          );
+        "#,
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BatchStatusChanges::Table)
+                    .if_not_exists()
+                    .col(pk_auto(BatchStatusChanges::ChangeId))
+                    .col(integer(BatchStatusChanges::BatchId))
+                    .col(string_len(BatchStatusChanges::FromStatus, 16))
+                    .col(string_len(BatchStatusChanges::ToStatus, 16))
+                    .col(text_null(BatchStatusChanges::Reason))
+                    .col(integer_null(BatchStatusChanges::ChangedBy))
+                    .col(
+                        timestamp_with_time_zone(BatchStatusChanges::ChangedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_batch_status_changes_batch")
+                            .from(BatchStatusChanges::Table, BatchStatusChanges::BatchId)
+                            .to(Batches::Table, Batches::BatchId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BatchStatusChanges::Table).to_owned())
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+        ALTER TABLE batches ALTER COLUMN status DROP DEFAULT;
+        ALTER TYPE batch_status RENAME TO batch_status_old;
+        CREATE TYPE batch_status AS ENUM ('open', 'closed');
+        ALTER TABLE batches
+            ALTER COLUMN status TYPE batch_status USING (
+                CASE status::text
+                    WHEN 'planned' THEN 'open'
+                    WHEN 'selling' THEN 'open'
+                    WHEN 'settled' THEN 'closed'
+                    ELSE status::text
+                END
+            )::batch_status;
+        ALTER TABLE batches ALTER COLUMN status SET DEFAULT 'open';
+        DROP TYPE batch_status_old;
+        "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum BatchStatusChanges {
+    Table,
+    ChangeId,
+    BatchId,
+    FromStatus,
+    ToStatus,
+    Reason,
+    ChangedBy,
+    ChangedAt,
+}
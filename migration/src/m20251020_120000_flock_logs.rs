@@ -0,0 +1,153 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250810_161418_iteration1::Batches;
+
+/// Migration for the daily flock log: one log per batch per day with feed
+/// consumed, sample weights and water intake, plus the breed standard table
+/// growth curves are compared against.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Batches::Table)
+                    .add_column(string_len_null(Breed::Breed, 100))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BreedStandards::Table)
+                    .if_not_exists()
+                    .col(pk_auto(BreedStandards::StandardId))
+                    .col(string_len(BreedStandards::Breed, 100))
+                    .col(integer(BreedStandards::AgeDays))
+                    .col(decimal_len(BreedStandards::BodyWeightKg, 8, 3))
+                    .col(decimal_len(BreedStandards::CumulativeFeedKg, 8, 3))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_unique_breed_standards_breed_age")
+                    .table(BreedStandards::Table)
+                    .col(BreedStandards::Breed)
+                    .col(BreedStandards::AgeDays)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FlockLogs::Table)
+                    .if_not_exists()
+                    .col(pk_auto(FlockLogs::LogId))
+                    .col(integer(FlockLogs::BatchId))
+                    .col(date(FlockLogs::LogDate))
+                    .col(decimal_len(FlockLogs::FeedConsumedKg, 10, 3).default(0))
+                    .col(integer_null(FlockLogs::SampleSize))
+                    .col(decimal_len_null(FlockLogs::SampleAvgWeightKg, 8, 3))
+                    .col(decimal_len_null(FlockLogs::WaterLitres, 10, 2))
+                    .col(ColumnDef::new(FlockLogs::Notes).text().null())
+                    .col(integer_null(FlockLogs::RecordedBy))
+                    .col(
+                        timestamp_with_time_zone(FlockLogs::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(FlockLogs::CorrectedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(integer_null(FlockLogs::CorrectedBy))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_flock_logs_batch")
+                            .from(FlockLogs::Table, FlockLogs::BatchId)
+                            .to(Batches::Table, Batches::BatchId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_unique_flock_logs_batch_date")
+                    .table(FlockLogs::Table)
+                    .col(FlockLogs::BatchId)
+                    .col(FlockLogs::LogDate)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FlockLogs::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(BreedStandards::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Batches::Table)
+                    .drop_column(Breed::Breed)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Breed {
+    Breed,
+}
+
+#[derive(DeriveIden)]
+pub enum BreedStandards {
+    Table,
+    StandardId,
+    Breed,
+    AgeDays,
+    BodyWeightKg,
+    CumulativeFeedKg,
+}
+
+#[derive(DeriveIden)]
+pub enum FlockLogs {
+    Table,
+    LogId,
+    BatchId,
+    LogDate,
+    FeedConsumedKg,
+    SampleSize,
+    SampleAvgWeightKg,
+    WaterLitres,
+    Notes,
+    RecordedBy,
+    CreatedAt,
+    CorrectedAt,
+    CorrectedBy,
+}
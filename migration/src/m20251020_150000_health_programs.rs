@@ -0,0 +1,181 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250810_161418_iteration1::{Batches, Items};
+
+/// Migration for vaccination and medication programs: template steps by day
+/// of age, copied onto each new batch as dated health tasks.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(HealthPrograms::Table)
+                    .if_not_exists()
+                    .col(pk_auto(HealthPrograms::ProgramId))
+                    .col(string_len(HealthPrograms::Name, 100).unique_key())
+                    .col(boolean(HealthPrograms::IsDefault).default(false))
+                    .col(
+                        timestamp_with_time_zone(HealthPrograms::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(HealthProgramSteps::Table)
+                    .if_not_exists()
+                    .col(pk_auto(HealthProgramSteps::StepId))
+                    .col(integer(HealthProgramSteps::ProgramId))
+                    .col(integer(HealthProgramSteps::DayOfAge))
+                    .col(string_len(HealthProgramSteps::TaskName, 100))
+                    .col(string_len_null(HealthProgramSteps::ItemCode, 100))
+                    .col(decimal_len_null(HealthProgramSteps::DosePerBird, 10, 4))
+                    .col(ColumnDef::new(HealthProgramSteps::Notes).text().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_health_program_steps_program")
+                            .from(HealthProgramSteps::Table, HealthProgramSteps::ProgramId)
+                            .to(HealthPrograms::Table, HealthPrograms::ProgramId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_health_program_steps_item")
+                            .from(HealthProgramSteps::Table, HealthProgramSteps::ItemCode)
+                            .to(Items::Table, Items::ItemCode)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BatchHealthTasks::Table)
+                    .if_not_exists()
+                    .col(pk_auto(BatchHealthTasks::TaskId))
+                    .col(integer(BatchHealthTasks::BatchId))
+                    .col(integer_null(BatchHealthTasks::StepId))
+                    .col(string_len(BatchHealthTasks::TaskName, 100))
+                    .col(string_len_null(BatchHealthTasks::ItemCode, 100))
+                    .col(decimal_len_null(BatchHealthTasks::DosePerBird, 10, 4))
+                    .col(date(BatchHealthTasks::DueDate))
+                    .col(
+                        ColumnDef::new(BatchHealthTasks::CompletedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(integer_null(BatchHealthTasks::CompletedBy))
+                    .col(integer_null(BatchHealthTasks::RequirementId))
+                    .col(ColumnDef::new(BatchHealthTasks::Notes).text().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_batch_health_tasks_batch")
+                            .from(BatchHealthTasks::Table, BatchHealthTasks::BatchId)
+                            .to(Batches::Table, Batches::BatchId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_batch_health_tasks_step")
+                            .from(BatchHealthTasks::Table, BatchHealthTasks::StepId)
+                            .to(HealthProgramSteps::Table, HealthProgramSteps::StepId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_batch_health_tasks_requirement")
+                            .from(BatchHealthTasks::Table, BatchHealthTasks::RequirementId)
+                            .to(BatchRequirements::Table, BatchRequirements::RequirementId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_batch_health_tasks_due")
+                    .table(BatchHealthTasks::Table)
+                    .col(BatchHealthTasks::DueDate)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BatchHealthTasks::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(HealthProgramSteps::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(HealthPrograms::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BatchRequirements {
+    Table,
+    RequirementId,
+}
+
+#[derive(DeriveIden)]
+pub enum HealthPrograms {
+    Table,
+    ProgramId,
+    Name,
+    IsDefault,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum HealthProgramSteps {
+    Table,
+    StepId,
+    ProgramId,
+    DayOfAge,
+    TaskName,
+    ItemCode,
+    DosePerBird,
+    Notes,
+}
+
+#[derive(DeriveIden)]
+pub enum BatchHealthTasks {
+    Table,
+    TaskId,
+    BatchId,
+    StepId,
+    TaskName,
+    ItemCode,
+    DosePerBird,
+    DueDate,
+    CompletedAt,
+    CompletedBy,
+    RequirementId,
+    Notes,
+}
@@ -0,0 +1,171 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250810_161418_iteration1::{Batches, Farmers};
+
+/// Migration for grower settlement: versioned rate tables and the settlement
+/// record each approved payment is booked from.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SettlementRateTables::Table)
+                    .if_not_exists()
+                    .col(pk_auto(SettlementRateTables::RateTableId))
+                    .col(integer(SettlementRateTables::Version).unique_key())
+                    .col(date(SettlementRateTables::EffectiveFrom))
+                    .col(decimal_len(SettlementRateTables::BaseRatePerKg, 10, 2))
+                    .col(decimal_len(SettlementRateTables::StandardCostPerKg, 10, 2))
+                    .col(decimal_len(SettlementRateTables::IncentiveSharePct, 5, 2))
+                    .col(decimal_len(
+                        SettlementRateTables::MortalityAllowancePct,
+                        5,
+                        2,
+                    ))
+                    .col(decimal_len(
+                        SettlementRateTables::MortalityPenaltyPerBird,
+                        10,
+                        2,
+                    ))
+                    .col(ColumnDef::new(SettlementRateTables::Notes).text().null())
+                    .col(integer_null(SettlementRateTables::CreatedBy))
+                    .col(
+                        timestamp_with_time_zone(SettlementRateTables::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FarmerSettlements::Table)
+                    .if_not_exists()
+                    .col(pk_auto(FarmerSettlements::SettlementId))
+                    .col(integer(FarmerSettlements::BatchId).unique_key())
+                    .col(integer(FarmerSettlements::FarmerId))
+                    .col(integer(FarmerSettlements::RateTableId))
+                    .col(integer(FarmerSettlements::BirdsPlaced))
+                    .col(integer(FarmerSettlements::Mortality))
+                    .col(decimal_len(FarmerSettlements::MortalityPct, 6, 2))
+                    .col(decimal_len(FarmerSettlements::LiveWeightKg, 12, 3))
+                    .col(decimal_len(FarmerSettlements::ProductionCost, 14, 2))
+                    .col(decimal_len_null(FarmerSettlements::CostPerKg, 10, 2))
+                    .col(decimal_len(FarmerSettlements::BaseAmount, 14, 2))
+                    .col(decimal_len(FarmerSettlements::CostIncentive, 14, 2))
+                    .col(decimal_len(FarmerSettlements::MortalityPenalty, 14, 2))
+                    .col(decimal_len(FarmerSettlements::GrossAmount, 14, 2))
+                    .col(decimal_len(FarmerSettlements::AdvancesDeducted, 14, 2))
+                    .col(decimal_len(FarmerSettlements::NetAmount, 14, 2))
+                    .col(integer(FarmerSettlements::CommissionId))
+                    .col(integer_null(FarmerSettlements::ApprovedBy))
+                    .col(
+                        timestamp_with_time_zone(FarmerSettlements::ApprovedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_farmer_settlements_batch")
+                            .from(FarmerSettlements::Table, FarmerSettlements::BatchId)
+                            .to(Batches::Table, Batches::BatchId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_farmer_settlements_farmer")
+                            .from(FarmerSettlements::Table, FarmerSettlements::FarmerId)
+                            .to(Farmers::Table, Farmers::FarmerId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_farmer_settlements_rate_table")
+                            .from(FarmerSettlements::Table, FarmerSettlements::RateTableId)
+                            .to(
+                                SettlementRateTables::Table,
+                                SettlementRateTables::RateTableId,
+                            )
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_farmer_settlements_commission")
+                            .from(FarmerSettlements::Table, FarmerSettlements::CommissionId)
+                            .to(FarmerCommissionHistory::Table, FarmerCommissionHistory::Id)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FarmerSettlements::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(SettlementRateTables::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum FarmerCommissionHistory {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+pub enum SettlementRateTables {
+    Table,
+    RateTableId,
+    Version,
+    EffectiveFrom,
+    BaseRatePerKg,
+    StandardCostPerKg,
+    IncentiveSharePct,
+    MortalityAllowancePct,
+    MortalityPenaltyPerBird,
+    Notes,
+    CreatedBy,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum FarmerSettlements {
+    Table,
+    SettlementId,
+    BatchId,
+    FarmerId,
+    RateTableId,
+    BirdsPlaced,
+    Mortality,
+    MortalityPct,
+    LiveWeightKg,
+    ProductionCost,
+    CostPerKg,
+    BaseAmount,
+    CostIncentive,
+    MortalityPenalty,
+    GrossAmount,
+    AdvancesDeducted,
+    NetAmount,
+    CommissionId,
+    ApprovedBy,
+    ApprovedAt,
+}
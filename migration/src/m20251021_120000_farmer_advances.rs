@@ -0,0 +1,175 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250810_161418_iteration1::{Batches, Farmers};
+use crate::m20251019_090000_supplier_payables::recreate_posting_event_type;
+
+/// Migration for farmer advances and deductions: money the grower owes back,
+/// carried on a farmer-advances account and recovered from settlements.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(FarmerAdvanceType::Table)
+                    .values([FarmerAdvanceType::Advance, FarmerAdvanceType::Deduction])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FarmerAdvances::Table)
+                    .if_not_exists()
+                    .col(pk_auto(FarmerAdvances::AdvanceId))
+                    .col(integer(FarmerAdvances::FarmerId))
+                    .col(integer_null(FarmerAdvances::BatchId))
+                    .col(
+                        ColumnDef::new(FarmerAdvances::AdvanceType)
+                            .custom(FarmerAdvanceType::Table)
+                            .not_null(),
+                    )
+                    .col(decimal_len(FarmerAdvances::Amount, 12, 2))
+                    .col(date(FarmerAdvances::AdvanceDate))
+                    .col(ColumnDef::new(FarmerAdvances::Description).text().null())
+                    .col(integer_null(FarmerAdvances::CreatedBy))
+                    .col(
+                        timestamp_with_time_zone(FarmerAdvances::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(FarmerAdvances::VoidedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(FarmerAdvances::VoidReason).text().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_farmer_advances_farmer")
+                            .from(FarmerAdvances::Table, FarmerAdvances::FarmerId)
+                            .to(Farmers::Table, Farmers::FarmerId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_farmer_advances_batch")
+                            .from(FarmerAdvances::Table, FarmerAdvances::BatchId)
+                            .to(Batches::Table, Batches::BatchId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_farmer_advances_farmer")
+                    .table(FarmerAdvances::Table)
+                    .col(FarmerAdvances::FarmerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Advances are paid out of cash and deductions are recovered as
+        // income; both are owed by the farmer on the farmer-advances account
+        let conn = manager.get_connection();
+        recreate_posting_event_type(
+            conn,
+            &[
+                "purchase",
+                "allocation",
+                "batch_sale",
+                "farmer_commission",
+                "supplier_payment",
+                "trader_receipt",
+                "farmer_advance",
+                "farmer_deduction",
+            ],
+        )
+        .await?;
+
+        conn.execute_unprepared(
+            r#"
+        INSERT INTO ledger_accounts (name, account_type) VALUES
+            ('farmer-advances', 'asset'),
+            ('farmer-recoveries', 'revenue')
+        ON CONFLICT (name) DO NOTHING;
+
+        INSERT INTO posting_rules (event, item_category, debit_account_id, credit_account_id, description)
+        SELECT r.event::posting_event, NULL, d.account_id, c.account_id, r.description
+        FROM (VALUES
+            ('farmer_advance', 'farmer-advances', 'cash', 'Cash advance to farmer'),
+            ('farmer_deduction', 'farmer-advances', 'farmer-recoveries', 'Deduction charged to farmer')
+        ) AS r(event, debit_name, credit_name, description)
+        JOIN ledger_accounts d ON d.name = r.debit_name
+        JOIN ledger_accounts c ON c.name = r.credit_name
+        ON CONFLICT DO NOTHING;
+        "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        conn.execute_unprepared(
+            "DELETE FROM posting_rules WHERE event IN ('farmer_advance', 'farmer_deduction');",
+        )
+        .await?;
+        recreate_posting_event_type(
+            conn,
+            &[
+                "purchase",
+                "allocation",
+                "batch_sale",
+                "farmer_commission",
+                "supplier_payment",
+                "trader_receipt",
+            ],
+        )
+        .await?;
+
+        manager
+            .drop_table(Table::drop().table(FarmerAdvances::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(FarmerAdvanceType::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum FarmerAdvanceType {
+    Table,
+    Advance,
+    Deduction,
+}
+
+#[derive(DeriveIden)]
+pub enum FarmerAdvances {
+    Table,
+    AdvanceId,
+    FarmerId,
+    BatchId,
+    AdvanceType,
+    Amount,
+    AdvanceDate,
+    Description,
+    CreatedBy,
+    CreatedAt,
+    VoidedAt,
+    VoidReason,
+}
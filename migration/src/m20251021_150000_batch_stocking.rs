@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+/// Migration for stocking a batch from several chick items: the requirements
+/// `create_batch` raises for its placements are flagged so reversing the
+/// stocking finds all of them.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BatchRequirements::Table)
+                    .add_column(boolean(BatchRequirements::IsStocking).default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Until now a batch was stocked from its first requirement only
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+        UPDATE batch_requirements r
+        SET is_stocking = TRUE
+        WHERE r.requirement_id = (
+            SELECT MIN(f.requirement_id) FROM batch_requirements f WHERE f.batch_id = r.batch_id
+        );
+        "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BatchRequirements::Table)
+                    .drop_column(BatchRequirements::IsStocking)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BatchRequirements {
+    Table,
+    IsStocking,
+}
@@ -0,0 +1,243 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250810_161418_iteration1::Items;
+use crate::m20251019_090000_supplier_payables::recreate_posting_event_type;
+
+/// Migration for stock adjustments and write-offs: damaged or expired stock,
+/// spillage and count corrections, each consuming or creating receipt lots
+/// and posting the loss or gain to the ledger.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(AdjustmentReason::Table)
+                    .values([
+                        AdjustmentReason::Damaged,
+                        AdjustmentReason::Expired,
+                        AdjustmentReason::Spillage,
+                        AdjustmentReason::CountCorrection,
+                        AdjustmentReason::Other,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(StockAdjustments::Table)
+                    .if_not_exists()
+                    .col(pk_auto(StockAdjustments::AdjustmentId))
+                    .col(string_len(StockAdjustments::ItemCode, 100))
+                    .col(decimal_len(StockAdjustments::QtyChange, 12, 2))
+                    .col(
+                        ColumnDef::new(StockAdjustments::Reason)
+                            .custom(AdjustmentReason::Table)
+                            .not_null(),
+                    )
+                    .col(decimal_len(StockAdjustments::Value, 12, 2))
+                    .col(integer_null(StockAdjustments::LotId))
+                    .col(date(StockAdjustments::AdjustmentDate))
+                    .col(ColumnDef::new(StockAdjustments::Notes).text().null())
+                    .col(integer_null(StockAdjustments::CreatedBy))
+                    .col(
+                        timestamp_with_time_zone(StockAdjustments::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_stock_adjustments_item")
+                            .from(StockAdjustments::Table, StockAdjustments::ItemCode)
+                            .to(Items::Table, Items::ItemCode)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_stock_adjustments_lot")
+                            .from(StockAdjustments::Table, StockAdjustments::LotId)
+                            .to(StockReceipts::Table, StockReceipts::LotId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Lots consumed by a negative adjustment, mirroring batch_allocation_lines
+        manager
+            .create_table(
+                Table::create()
+                    .table(StockAdjustmentLines::Table)
+                    .if_not_exists()
+                    .col(pk_auto(StockAdjustmentLines::AdjustmentLineId))
+                    .col(integer(StockAdjustmentLines::AdjustmentId))
+                    .col(integer(StockAdjustmentLines::LotId))
+                    .col(decimal_len(StockAdjustmentLines::Qty, 12, 2))
+                    .col(decimal_len(StockAdjustmentLines::UnitCost, 12, 2))
+                    .col(decimal_len(StockAdjustmentLines::LineValue, 12, 2))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_adjustment_lines_adjustment")
+                            .from(
+                                StockAdjustmentLines::Table,
+                                StockAdjustmentLines::AdjustmentId,
+                            )
+                            .to(StockAdjustments::Table, StockAdjustments::AdjustmentId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_adjustment_lines_lot")
+                            .from(StockAdjustmentLines::Table, StockAdjustmentLines::LotId)
+                            .to(StockReceipts::Table, StockReceipts::LotId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_stock_adjustments_item")
+                    .table(StockAdjustments::Table)
+                    .col(StockAdjustments::ItemCode)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Write-offs are expensed against the category's inventory account;
+        // surplus found on a count is recognised as a gain
+        let conn = manager.get_connection();
+        recreate_posting_event_type(
+            conn,
+            &[
+                "purchase",
+                "allocation",
+                "batch_sale",
+                "farmer_commission",
+                "supplier_payment",
+                "trader_receipt",
+                "farmer_advance",
+                "farmer_deduction",
+                "stock_loss",
+                "stock_gain",
+            ],
+        )
+        .await?;
+
+        conn.execute_unprepared(
+            r#"
+        INSERT INTO ledger_accounts (name, account_type) VALUES
+            ('stock-loss', 'expense'),
+            ('stock-gain', 'revenue')
+        ON CONFLICT (name) DO NOTHING;
+
+        INSERT INTO posting_rules (event, item_category, debit_account_id, credit_account_id, description)
+        SELECT r.event::posting_event, r.item_category::item_category, d.account_id, c.account_id, r.description
+        FROM (VALUES
+            ('stock_loss', 'medicine', 'stock-loss', 'inventory-medicine', 'Medicine written off'),
+            ('stock_loss', 'feed', 'stock-loss', 'inventory-feed', 'Feed written off'),
+            ('stock_loss', 'chicks', 'stock-loss', 'inventory-chicks', 'Chicks written off'),
+            ('stock_gain', 'medicine', 'inventory-medicine', 'stock-gain', 'Medicine found on count'),
+            ('stock_gain', 'feed', 'inventory-feed', 'stock-gain', 'Feed found on count'),
+            ('stock_gain', 'chicks', 'inventory-chicks', 'stock-gain', 'Chicks found on count')
+        ) AS r(event, item_category, debit_name, credit_name, description)
+        JOIN ledger_accounts d ON d.name = r.debit_name
+        JOIN ledger_accounts c ON c.name = r.credit_name
+        ON CONFLICT DO NOTHING;
+        "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        conn.execute_unprepared(
+            "DELETE FROM posting_rules WHERE event IN ('stock_loss', 'stock_gain');",
+        )
+        .await?;
+        recreate_posting_event_type(
+            conn,
+            &[
+                "purchase",
+                "allocation",
+                "batch_sale",
+                "farmer_commission",
+                "supplier_payment",
+                "trader_receipt",
+                "farmer_advance",
+                "farmer_deduction",
+            ],
+        )
+        .await?;
+
+        manager
+            .drop_table(Table::drop().table(StockAdjustmentLines::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(StockAdjustments::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(AdjustmentReason::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum StockReceipts {
+    Table,
+    LotId,
+}
+
+#[derive(DeriveIden)]
+pub enum AdjustmentReason {
+    Table,
+    Damaged,
+    Expired,
+    Spillage,
+    CountCorrection,
+    Other,
+}
+
+#[derive(DeriveIden)]
+pub enum StockAdjustments {
+    Table,
+    AdjustmentId,
+    ItemCode,
+    QtyChange,
+    Reason,
+    Value,
+    LotId,
+    AdjustmentDate,
+    Notes,
+    CreatedBy,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum StockAdjustmentLines {
+    Table,
+    AdjustmentLineId,
+    AdjustmentId,
+    LotId,
+    Qty,
+    UnitCost,
+    LineValue,
+}
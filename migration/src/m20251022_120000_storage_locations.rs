@@ -0,0 +1,385 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250810_161418_iteration1::{BatchAllocations, Farmers, Items};
+
+/// Migration for multi-location inventory: warehouses and farms hold stock,
+/// lots remember where they sit, and transfers move quantity between them.
+/// The existing `inventory` row stays the item's total across locations.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(LocationType::Table)
+                    .values([LocationType::Warehouse, LocationType::Farm])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(StorageLocations::Table)
+                    .if_not_exists()
+                    .col(pk_auto(StorageLocations::LocationId))
+                    .col(string_len(StorageLocations::Name, 100).unique_key())
+                    .col(
+                        ColumnDef::new(StorageLocations::LocationType)
+                            .custom(LocationType::Table)
+                            .not_null(),
+                    )
+                    .col(integer_null(StorageLocations::FarmerId).unique_key())
+                    .col(boolean(StorageLocations::IsDefault).default(false))
+                    .col(
+                        timestamp_with_time_zone(StorageLocations::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_storage_locations_farmer")
+                            .from(StorageLocations::Table, StorageLocations::FarmerId)
+                            .to(Farmers::Table, Farmers::FarmerId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(InventoryLocations::Table)
+                    .if_not_exists()
+                    .col(string_len(InventoryLocations::ItemCode, 100))
+                    .col(integer(InventoryLocations::LocationId))
+                    .col(decimal_len(InventoryLocations::CurrentQty, 12, 2).default(0))
+                    .col(
+                        timestamp_with_time_zone(InventoryLocations::LastUpdated)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(InventoryLocations::ItemCode)
+                            .col(InventoryLocations::LocationId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_inventory_locations_item")
+                            .from(InventoryLocations::Table, InventoryLocations::ItemCode)
+                            .to(Items::Table, Items::ItemCode)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_inventory_locations_location")
+                            .from(InventoryLocations::Table, InventoryLocations::LocationId)
+                            .to(StorageLocations::Table, StorageLocations::LocationId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(StockTransfers::Table)
+                    .if_not_exists()
+                    .col(pk_auto(StockTransfers::TransferId))
+                    .col(string_len(StockTransfers::ItemCode, 100))
+                    .col(integer(StockTransfers::FromLocationId))
+                    .col(integer(StockTransfers::ToLocationId))
+                    .col(decimal_len(StockTransfers::Quantity, 12, 2))
+                    .col(decimal_len(StockTransfers::Value, 12, 2))
+                    .col(date(StockTransfers::TransferDate))
+                    .col(integer_null(StockTransfers::AllocationId))
+                    .col(ColumnDef::new(StockTransfers::Notes).text().null())
+                    .col(integer_null(StockTransfers::CreatedBy))
+                    .col(
+                        timestamp_with_time_zone(StockTransfers::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_stock_transfers_item")
+                            .from(StockTransfers::Table, StockTransfers::ItemCode)
+                            .to(Items::Table, Items::ItemCode)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_stock_transfers_from")
+                            .from(StockTransfers::Table, StockTransfers::FromLocationId)
+                            .to(StorageLocations::Table, StorageLocations::LocationId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_stock_transfers_to")
+                            .from(StockTransfers::Table, StockTransfers::ToLocationId)
+                            .to(StorageLocations::Table, StorageLocations::LocationId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_stock_transfers_allocation")
+                            .from(StockTransfers::Table, StockTransfers::AllocationId)
+                            .to(BatchAllocations::Table, BatchAllocations::AllocationId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StockReceipts::Table)
+                    .add_column(integer_null(StockReceipts::LocationId))
+                    .add_column(integer_null(StockReceipts::SourceLotId))
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_stock_receipts_location")
+                            .from_tbl(StockReceipts::Table)
+                            .from_col(StockReceipts::LocationId)
+                            .to_tbl(StorageLocations::Table)
+                            .to_col(StorageLocations::LocationId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_stock_receipts_source_lot")
+                            .from_tbl(StockReceipts::Table)
+                            .from_col(StockReceipts::SourceLotId)
+                            .to_tbl(StockReceipts::Table)
+                            .to_col(StockReceipts::LotId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(InventoryMovements::Table)
+                    .add_column(integer_null(InventoryMovements::LocationId))
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_inventory_movements_location")
+                            .from_tbl(InventoryMovements::Table)
+                            .from_col(InventoryMovements::LocationId)
+                            .to_tbl(StorageLocations::Table)
+                            .to_col(StorageLocations::LocationId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StockAdjustments::Table)
+                    .add_column(integer_null(StockAdjustments::LocationId))
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_stock_adjustments_location")
+                            .from_tbl(StockAdjustments::Table)
+                            .from_col(StockAdjustments::LocationId)
+                            .to_tbl(StorageLocations::Table)
+                            .to_col(StorageLocations::LocationId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Everything on hand so far sat in the central godown; every farmer
+        // gets a farm location for stock sent to their batches
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+        INSERT INTO storage_locations (name, location_type, is_default)
+        VALUES ('Central godown', 'warehouse', TRUE);
+
+        INSERT INTO storage_locations (name, location_type, farmer_id)
+        SELECT f.name || ' farm #' || f.farmer_id, 'farm', f.farmer_id
+        FROM farmers f;
+
+        UPDATE stock_receipts
+        SET location_id = (SELECT location_id FROM storage_locations WHERE is_default);
+        ALTER TABLE stock_receipts ALTER COLUMN location_id SET NOT NULL;
+
+        UPDATE stock_adjustments
+        SET location_id = (SELECT location_id FROM storage_locations WHERE is_default);
+
+        UPDATE inventory_movements
+        SET location_id = (SELECT location_id FROM storage_locations WHERE is_default);
+
+        INSERT INTO inventory_locations (item_code, location_id, current_qty, last_updated)
+        SELECT i.item_code, l.location_id, i.current_qty, i.last_updated
+        FROM inventory i
+        CROSS JOIN storage_locations l
+        WHERE l.is_default;
+        "#,
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_stock_receipts_item_location")
+                    .table(StockReceipts::Table)
+                    .col(StockReceipts::ItemCode)
+                    .col(StockReceipts::LocationId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StockAdjustments::Table)
+                    .drop_foreign_key(Alias::new("fk_stock_adjustments_location"))
+                    .drop_column(StockAdjustments::LocationId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(InventoryMovements::Table)
+                    .drop_foreign_key(Alias::new("fk_inventory_movements_location"))
+                    .drop_column(InventoryMovements::LocationId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_stock_receipts_item_location")
+                    .table(StockReceipts::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StockReceipts::Table)
+                    .drop_foreign_key(Alias::new("fk_stock_receipts_source_lot"))
+                    .drop_foreign_key(Alias::new("fk_stock_receipts_location"))
+                    .drop_column(StockReceipts::SourceLotId)
+                    .drop_column(StockReceipts::LocationId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(StockTransfers::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(InventoryLocations::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(StorageLocations::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(LocationType::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum StockReceipts {
+    Table,
+    LotId,
+    ItemCode,
+    LocationId,
+    SourceLotId,
+}
+
+#[derive(DeriveIden)]
+enum StockAdjustments {
+    Table,
+    LocationId,
+}
+
+#[derive(DeriveIden)]
+enum InventoryMovements {
+    Table,
+    LocationId,
+}
+
+#[derive(DeriveIden)]
+pub enum LocationType {
+    Table,
+    Warehouse,
+    Farm,
+}
+
+#[derive(DeriveIden)]
+pub enum StorageLocations {
+    Table,
+    LocationId,
+    Name,
+    LocationType,
+    FarmerId,
+    IsDefault,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum InventoryLocations {
+    Table,
+    ItemCode,
+    LocationId,
+    CurrentQty,
+    LastUpdated,
+}
+
+#[derive(DeriveIden)]
+pub enum StockTransfers {
+    Table,
+    TransferId,
+    ItemCode,
+    FromLocationId,
+    ToLocationId,
+    Quantity,
+    Value,
+    TransferDate,
+    AllocationId,
+    Notes,
+    CreatedBy,
+    CreatedAt,
+}
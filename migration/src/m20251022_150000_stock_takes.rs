@@ -0,0 +1,193 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250810_161418_iteration1::Items;
+
+/// Migration for stock-takes: an admin opens a count at a location,
+/// supervisors enter what they find, and approval posts the variances as
+/// count-correction adjustments.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(StockTakeStatus::Table)
+                    .values([
+                        StockTakeStatus::Open,
+                        StockTakeStatus::Approved,
+                        StockTakeStatus::Cancelled,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(StockTakes::Table)
+                    .if_not_exists()
+                    .col(pk_auto(StockTakes::StockTakeId))
+                    .col(integer(StockTakes::LocationId))
+                    .col(
+                        ColumnDef::new(StockTakes::Status)
+                            .custom(StockTakeStatus::Table)
+                            .not_null()
+                            .default("open"),
+                    )
+                    .col(date(StockTakes::CountDate))
+                    .col(ColumnDef::new(StockTakes::Notes).text().null())
+                    .col(integer_null(StockTakes::OpenedBy))
+                    .col(
+                        timestamp_with_time_zone(StockTakes::OpenedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(integer_null(StockTakes::ApprovedBy))
+                    .col(
+                        ColumnDef::new(StockTakes::ApprovedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_stock_takes_location")
+                            .from(StockTakes::Table, StockTakes::LocationId)
+                            .to(StorageLocations::Table, StorageLocations::LocationId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(StockTakeLines::Table)
+                    .if_not_exists()
+                    .col(pk_auto(StockTakeLines::LineId))
+                    .col(integer(StockTakeLines::StockTakeId))
+                    .col(string_len(StockTakeLines::ItemCode, 100))
+                    .col(decimal_len(StockTakeLines::BookQty, 12, 2))
+                    .col(decimal_len(StockTakeLines::LotQty, 12, 2))
+                    .col(decimal_len_null(StockTakeLines::CountedQty, 12, 2))
+                    .col(integer_null(StockTakeLines::CountedBy))
+                    .col(
+                        ColumnDef::new(StockTakeLines::CountedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(decimal_len_null(StockTakeLines::Variance, 12, 2))
+                    .col(integer_null(StockTakeLines::AdjustmentId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_stock_take_lines_stock_take")
+                            .from(StockTakeLines::Table, StockTakeLines::StockTakeId)
+                            .to(StockTakes::Table, StockTakes::StockTakeId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_stock_take_lines_item")
+                            .from(StockTakeLines::Table, StockTakeLines::ItemCode)
+                            .to(Items::Table, Items::ItemCode)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_stock_take_lines_adjustment")
+                            .from(StockTakeLines::Table, StockTakeLines::AdjustmentId)
+                            .to(StockAdjustments::Table, StockAdjustments::AdjustmentId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_stock_take_lines_item")
+                    .table(StockTakeLines::Table)
+                    .col(StockTakeLines::StockTakeId)
+                    .col(StockTakeLines::ItemCode)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(StockTakeLines::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(StockTakes::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(StockTakeStatus::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum StorageLocations {
+    Table,
+    LocationId,
+}
+
+#[derive(DeriveIden)]
+enum StockAdjustments {
+    Table,
+    AdjustmentId,
+}
+
+#[derive(DeriveIden)]
+pub enum StockTakeStatus {
+    Table,
+    Open,
+    Approved,
+    Cancelled,
+}
+
+#[derive(DeriveIden)]
+pub enum StockTakes {
+    Table,
+    StockTakeId,
+    LocationId,
+    Status,
+    CountDate,
+    Notes,
+    OpenedBy,
+    OpenedAt,
+    ApprovedBy,
+    ApprovedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum StockTakeLines {
+    Table,
+    LineId,
+    StockTakeId,
+    ItemCode,
+    BookQty,
+    LotQty,
+    CountedQty,
+    CountedBy,
+    CountedAt,
+    Variance,
+    AdjustmentId,
+}
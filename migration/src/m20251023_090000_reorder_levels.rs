@@ -0,0 +1,131 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+/// Migration for reorder levels: items carry a reorder point, safety stock
+/// and preferred supplier, and a daily job records items that have fallen
+/// below their reorder point.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Items::Table)
+                    .add_column(decimal_len_null(Items::ReorderPoint, 12, 2))
+                    .add_column(decimal_len(Items::SafetyStock, 12, 2).default(0))
+                    .add_column(integer_null(Items::PreferredSupplierId))
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_items_preferred_supplier")
+                            .from_tbl(Items::Table)
+                            .from_col(Items::PreferredSupplierId)
+                            .to_tbl(Suppliers::Table)
+                            .to_col(Suppliers::SupplierId)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(LowStockAlerts::Table)
+                    .if_not_exists()
+                    .col(pk_auto(LowStockAlerts::AlertId))
+                    .col(string_len(LowStockAlerts::ItemCode, 100))
+                    .col(date(LowStockAlerts::AlertDate))
+                    .col(decimal_len(LowStockAlerts::OnHandQty, 12, 2))
+                    .col(decimal_len(LowStockAlerts::CommittedQty, 12, 2))
+                    .col(decimal_len(LowStockAlerts::AvailableQty, 12, 2))
+                    .col(decimal_len(LowStockAlerts::ReorderPoint, 12, 2))
+                    .col(decimal_len(LowStockAlerts::SafetyStock, 12, 2))
+                    .col(decimal_len(LowStockAlerts::SuggestedOrderQty, 12, 2))
+                    .col(integer_null(LowStockAlerts::PreferredSupplierId))
+                    .col(
+                        timestamp_with_time_zone(LowStockAlerts::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_low_stock_alerts_item")
+                            .from(LowStockAlerts::Table, LowStockAlerts::ItemCode)
+                            .to(Items::Table, Items::ItemCode)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // One alert per item per day, however often the job runs
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_low_stock_alerts_item_date")
+                    .table(LowStockAlerts::Table)
+                    .col(LowStockAlerts::ItemCode)
+                    .col(LowStockAlerts::AlertDate)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LowStockAlerts::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Items::Table)
+                    .drop_foreign_key(Alias::new("fk_items_preferred_supplier"))
+                    .drop_column(Items::PreferredSupplierId)
+                    .drop_column(Items::SafetyStock)
+                    .drop_column(Items::ReorderPoint)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Suppliers {
+    Table,
+    SupplierId,
+}
+
+#[derive(DeriveIden)]
+enum Items {
+    Table,
+    ItemCode,
+    ReorderPoint,
+    SafetyStock,
+    PreferredSupplierId,
+}
+
+#[derive(DeriveIden)]
+pub enum LowStockAlerts {
+    Table,
+    AlertId,
+    ItemCode,
+    AlertDate,
+    OnHandQty,
+    CommittedQty,
+    AvailableQty,
+    ReorderPoint,
+    SafetyStock,
+    SuggestedOrderQty,
+    PreferredSupplierId,
+    CreatedAt,
+}
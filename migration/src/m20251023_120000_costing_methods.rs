@@ -0,0 +1,96 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+/// Migration for selectable costing: each item category draws its lots
+/// either first-in first-out or at moving weighted average cost.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(CostingMethod::Table)
+                    .values([CostingMethod::Fifo, CostingMethod::WeightedAverage])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(CategoryCostingMethods::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CategoryCostingMethods::ItemCategory)
+                            .custom(ItemCategory::Table)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CategoryCostingMethods::CostingMethod)
+                            .custom(CostingMethod::Table)
+                            .not_null()
+                            .default("fifo"),
+                    )
+                    .col(
+                        timestamp_with_time_zone(CategoryCostingMethods::UpdatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Everything has been costed FIFO so far
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+        INSERT INTO category_costing_methods (item_category, costing_method)
+        SELECT unnest(enum_range(NULL::item_category)), 'fifo';
+        "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(CategoryCostingMethods::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(CostingMethod::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ItemCategory {
+    Table,
+}
+
+#[derive(DeriveIden)]
+pub enum CostingMethod {
+    Table,
+    Fifo,
+    WeightedAverage,
+}
+
+#[derive(DeriveIden)]
+pub enum CategoryCostingMethods {
+    Table,
+    ItemCategory,
+    CostingMethod,
+    UpdatedAt,
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+/// Migration for lot expiry: receipts record the manufacturer's lot number
+/// and expiry date so medicines can be issued first-expiry-first-out and
+/// expired stock kept back.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StockReceipts::Table)
+                    .add_column(string_len_null(StockReceipts::ManufacturerLotNo, 100))
+                    .add_column(date_null(StockReceipts::ExpiryDate))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_stock_receipts_expiry_date")
+                    .table(StockReceipts::Table)
+                    .col(StockReceipts::ExpiryDate)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_stock_receipts_expiry_date")
+                    .table(StockReceipts::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StockReceipts::Table)
+                    .drop_column(StockReceipts::ExpiryDate)
+                    .drop_column(StockReceipts::ManufacturerLotNo)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum StockReceipts {
+    Table,
+    ManufacturerLotNo,
+    ExpiryDate,
+}
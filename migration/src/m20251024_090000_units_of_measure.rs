@@ -0,0 +1,111 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250810_161418_iteration1::Items;
+
+/// Migration for units of measure: `items.unit` is the base unit stock is
+/// kept in, and each item can list other units (a 50 kg bag, a box of 100
+/// chicks) with how many base units one of them holds. Unit costs get four
+/// places so converting a bag price to a per-kg cost does not lose money.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ItemUnitConversions::Table)
+                    .if_not_exists()
+                    .col(pk_auto(ItemUnitConversions::ConversionId))
+                    .col(string_len(ItemUnitConversions::ItemCode, 100))
+                    .col(string_len(ItemUnitConversions::Unit, 30))
+                    .col(decimal_len(ItemUnitConversions::Factor, 14, 4))
+                    .col(
+                        timestamp_with_time_zone(ItemUnitConversions::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .check(Expr::col(ItemUnitConversions::Factor).gt(0))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_item_unit_conversions_item")
+                            .from(ItemUnitConversions::Table, ItemUnitConversions::ItemCode)
+                            .to(Items::Table, Items::ItemCode)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_item_unit_conversions_item_unit")
+                    .table(ItemUnitConversions::Table)
+                    .col(ItemUnitConversions::ItemCode)
+                    .col(ItemUnitConversions::Unit)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        for (table, column) in unit_cost_columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .modify_column(decimal_len(column, 14, 4))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (table, column) in unit_cost_columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .modify_column(decimal_len(column, 12, 2))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_table(Table::drop().table(ItemUnitConversions::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn unit_cost_columns() -> [(Alias, Alias); 4] {
+    [
+        (Alias::new("purchases"), Alias::new("cost_per_unit")),
+        (Alias::new("stock_receipts"), Alias::new("unit_cost")),
+        (
+            Alias::new("batch_allocation_lines"),
+            Alias::new("unit_cost"),
+        ),
+        (
+            Alias::new("stock_adjustment_lines"),
+            Alias::new("unit_cost"),
+        ),
+    ]
+}
+
+#[derive(DeriveIden)]
+pub enum ItemUnitConversions {
+    Table,
+    ConversionId,
+    ItemCode,
+    Unit,
+    Factor,
+    CreatedAt,
+}
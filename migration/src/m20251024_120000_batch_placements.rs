@@ -0,0 +1,132 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+use crate::m20250810_161418_iteration1::{BatchAllocations, Batches, Items};
+
+/// Migration for recording how a batch was stocked: one row per chick item
+/// placed, so `batches.initial_bird_count` stays the total placed.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BatchPlacements::Table)
+                    .if_not_exists()
+                    .col(pk_auto(BatchPlacements::PlacementId))
+                    .col(integer(BatchPlacements::BatchId))
+                    .col(string_len(BatchPlacements::ItemCode, 100))
+                    .col(integer(BatchPlacements::BirdCount))
+                    .col(integer_null(BatchPlacements::AllocationId))
+                    .col(
+                        timestamp_with_time_zone(BatchPlacements::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .check(Expr::col(BatchPlacements::BirdCount).gt(0))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_batch_placements_batch")
+                            .from(BatchPlacements::Table, BatchPlacements::BatchId)
+                            .to(Batches::Table, Batches::BatchId)
+                            .on_update(ForeignKeyAction::NoAction)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_batch_placements_item")
+                            .from(BatchPlacements::Table, BatchPlacements::ItemCode)
+                            .to(Items::Table, Items::ItemCode)
+                            .on_update(ForeignKeyAction::Cascade)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_batch_placements_allocation")
+                            .from(BatchPlacements::Table, BatchPlacements::AllocationId)
+                            .to(BatchAllocations::Table, BatchAllocations::AllocationId)
+                            .on_update(ForeignKeyAction::NoAction)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_batch_placements_batch_item")
+                    .table(BatchPlacements::Table)
+                    .col(BatchPlacements::BatchId)
+                    .col(BatchPlacements::ItemCode)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+
+        // Batches stocked so far: one placement per stocking allocation
+        db.execute_unprepared(
+            r#"
+        INSERT INTO batch_placements (batch_id, item_code, bird_count, allocation_id)
+        SELECT r.batch_id, r.item_code, a.allocated_qty::integer, a.allocation_id
+        FROM batch_requirements r
+        JOIN batch_allocations a ON a.requirement_id = r.requirement_id
+        WHERE r.is_stocking AND a.voided_at IS NULL
+        ON CONFLICT (batch_id, item_code) DO NOTHING;
+        "#,
+        )
+        .await?;
+
+        // Batches stocked from several items kept only the first item in
+        // initial_bird_count and logged the others as additions
+        db.execute_unprepared(
+            r#"
+        DELETE FROM bird_count_history h
+        USING batches b
+        WHERE h.batch_id = b.batch_id
+          AND h.record_date = b.start_date
+          AND h.deaths = 0
+          AND h.notes = h.additions || ' birds placed from ' || (
+              SELECT p.item_code FROM batch_placements p
+              WHERE p.batch_id = b.batch_id AND p.bird_count = h.additions
+              ORDER BY p.placement_id LIMIT 1
+          );
+
+        UPDATE batches b
+        SET initial_bird_count = p.total
+        FROM (
+            SELECT batch_id, SUM(bird_count)::integer AS total, COUNT(*) AS items
+            FROM batch_placements
+            GROUP BY batch_id
+        ) p
+        WHERE p.batch_id = b.batch_id AND p.items > 1;
+        "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BatchPlacements::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BatchPlacements {
+    Table,
+    PlacementId,
+    BatchId,
+    ItemCode,
+    BirdCount,
+    AllocationId,
+    CreatedAt,
+}
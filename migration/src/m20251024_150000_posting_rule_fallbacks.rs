@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+/// Migration for the event-wide fallback posting rules: the unique index on
+/// (event, item_category) treats NULL categories as distinct, so it let an
+/// event have several fallbacks. A partial index allows only one.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Keep the oldest fallback of any event that has several; it is the
+        // one resolve_posting_rule has been finding
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+        DELETE FROM posting_rules r
+        USING posting_rules f
+        WHERE r.item_category IS NULL
+          AND f.item_category IS NULL
+          AND f.event = r.event
+          AND f.rule_id < r.rule_id;
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_unique_posting_rules_event_fallback
+            ON posting_rules (event)
+            WHERE item_category IS NULL;
+        "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_unique_posting_rules_event_fallback")
+                    .table(PostingRules::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PostingRules {
+    Table,
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::*;
+
+/// Migration for logging a batch's stocking in bird_count_history: one
+/// addition per chick item placed, flagged so totals that start from
+/// `batches.initial_bird_count` do not count those birds twice.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BirdCountHistory::Table)
+                    .add_column(boolean(BirdCountHistory::IsStocking).default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Batches stocked so far get their placements in the history
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+        INSERT INTO bird_count_history (batch_id, record_date, deaths, additions, notes, is_stocking)
+        SELECT p.batch_id, b.start_date, 0, p.bird_count,
+               p.bird_count || ' birds placed from ' || p.item_code, TRUE
+        FROM batch_placements p
+        JOIN batches b ON b.batch_id = p.batch_id
+        ORDER BY p.placement_id;
+        "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DELETE FROM bird_count_history WHERE is_stocking;")
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BirdCountHistory::Table)
+                    .drop_column(BirdCountHistory::IsStocking)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BirdCountHistory {
+    Table,
+    IsStocking,
+}
@@ -1,6 +1,51 @@
 use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::Database;
 
 #[async_std::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.get(1).map(String::as_str) == Some("reconcile-balances") {
+        let fix = args.iter().any(|a| a == "--fix");
+        reconcile_balances(fix).await;
+        return;
+    }
+
     cli::run_cli(migration::Migrator).await;
 }
+
+async fn reconcile_balances(fix: bool) {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let db = Database::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let drift = migration::reconcile::reconcile_balances(&db, fix)
+        .await
+        .expect("Failed to reconcile ledger balances");
+
+    if drift.is_empty() {
+        println!("All ledger account balances match their entries");
+        return;
+    }
+
+    for d in &drift {
+        println!(
+            "account {} ({}): stored {} computed {} drift {}",
+            d.account_id,
+            d.name,
+            d.stored_balance,
+            d.computed_balance,
+            d.drift()
+        );
+    }
+
+    if fix {
+        println!("Fixed {} account balance(s)", drift.len());
+    } else {
+        println!(
+            "{} account(s) drifted; rerun with --fix to correct them",
+            drift.len()
+        );
+    }
+}
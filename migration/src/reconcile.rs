@@ -0,0 +1,85 @@
+//! Recompute `ledger_accounts.current_balance` from `ledger_entries`.
+//!
+//! Shared by the `reconcile-balances` CLI subcommand and the admin endpoint.
+
+use sea_orm_migration::sea_orm::{
+    prelude::Decimal, ConnectionTrait, DbErr, QueryResult, Statement, TransactionTrait,
+};
+
+/// Normal-side balance of every account: debit-normal for asset/expense,
+/// credit-normal for liability/equity/revenue.
+const COMPUTED_BALANCES: &str = r#"
+    SELECT a.account_id,
+           a.name,
+           a.current_balance AS stored_balance,
+           CASE WHEN a.account_type IN ('asset', 'expense')
+                THEN COALESCE(SUM(e.debit), 0) - COALESCE(SUM(e.credit), 0)
+                ELSE COALESCE(SUM(e.credit), 0) - COALESCE(SUM(e.debit), 0)
+           END AS computed_balance
+    FROM ledger_accounts a
+    LEFT JOIN ledger_entries e ON e.account_id = a.account_id
+    GROUP BY a.account_id, a.name, a.current_balance, a.account_type
+"#;
+
+#[derive(Debug, Clone)]
+pub struct BalanceDrift {
+    pub account_id: i32,
+    pub name: String,
+    pub stored_balance: Decimal,
+    pub computed_balance: Decimal,
+}
+
+impl BalanceDrift {
+    /// Stored minus computed; positive means the stored balance is overstated.
+    pub fn drift(&self) -> Decimal {
+        self.stored_balance - self.computed_balance
+    }
+
+    fn from_row(row: &QueryResult) -> Result<Self, DbErr> {
+        Ok(Self {
+            account_id: row.try_get("", "account_id")?,
+            name: row.try_get("", "name")?,
+            stored_balance: row.try_get("", "stored_balance")?,
+            computed_balance: row.try_get("", "computed_balance")?,
+        })
+    }
+}
+
+/// Accounts whose stored balance differs from the one computed from entries.
+pub async fn find_balance_drift<C: ConnectionTrait>(conn: &C) -> Result<Vec<BalanceDrift>, DbErr> {
+    let sql = format!(
+        "SELECT * FROM ({}) c WHERE c.stored_balance <> c.computed_balance ORDER BY c.account_id",
+        COMPUTED_BALANCES
+    );
+    let rows = conn
+        .query_all(Statement::from_string(conn.get_database_backend(), sql))
+        .await?;
+
+    rows.iter().map(BalanceDrift::from_row).collect()
+}
+
+/// Report drift and, when `fix` is set, overwrite the stored balances with
+/// the computed ones. Both happen in one transaction.
+pub async fn reconcile_balances<C: TransactionTrait>(
+    db: &C,
+    fix: bool,
+) -> Result<Vec<BalanceDrift>, DbErr> {
+    let txn = db.begin().await?;
+
+    let drift = find_balance_drift(&txn).await?;
+
+    if fix && !drift.is_empty() {
+        let sql = format!(
+            "UPDATE ledger_accounts a SET current_balance = c.computed_balance \
+             FROM ({}) c \
+             WHERE a.account_id = c.account_id AND a.current_balance <> c.computed_balance",
+            COMPUTED_BALANCES
+        );
+        txn.execute(Statement::from_string(txn.get_database_backend(), sql))
+            .await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(drift)
+}
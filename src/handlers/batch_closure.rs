@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use entity::sea_orm_active_enums::{BatchStatus, ItemCategory, RequirementStatus};
+use entity::{
+    batch_allocations, batch_closure_summary, batch_requirements, batch_sales, bird_count_history,
+    farmer_commission_history, items,
+};
+use num_traits::ToPrimitive;
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, Set, TransactionTrait,
+};
+use thiserror::Error;
+
+use crate::handlers::batch_lifecycle::{
+    ensure_batch_allows, set_batch_status, BatchOperation, BatchStateError,
+};
+use crate::models::{CloseBatch, ResponseMessage};
+
+#[derive(Debug, Error)]
+pub enum BatchCloseError {
+    #[error(transparent)]
+    Lifecycle(#[from] BatchStateError),
+    #[error("Batch {batch_id} still has pending requirements: {requirement_ids:?}")]
+    PendingRequirements {
+        batch_id: i32,
+        requirement_ids: Vec<i32>,
+    },
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl BatchCloseError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            BatchCloseError::Lifecycle(e) => e.status(),
+            BatchCloseError::PendingRequirements { .. } => StatusCode::CONFLICT,
+            BatchCloseError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+pub async fn close_batch_handler(
+    State(db): State<DatabaseConnection>,
+    Path(batch_id): Path<i32>,
+    Json(payload): Json<CloseBatch>,
+) -> Result<Json<batch_closure_summary::Model>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match close_batch(&txn, batch_id, &payload).await {
+            Ok(summary) => txn
+                .commit()
+                .await
+                .map(|_| summary)
+                .map_err(BatchCloseError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(BatchCloseError::from(e)),
+    };
+
+    result.map(Json).map_err(|e| {
+        eprintln!("Failed to close batch {}: {}", batch_id, e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    })
+}
+
+/// Close a batch and store its cost sheet, computed from the batch's own
+/// allocations, sales, mortality records and commissions.
+pub async fn close_batch<C: ConnectionTrait>(
+    conn: &C,
+    batch_id: i32,
+    payload: &CloseBatch,
+) -> Result<batch_closure_summary::Model, BatchCloseError> {
+    let batch = ensure_batch_allows(conn, batch_id, BatchOperation::Close).await?;
+
+    // 1. Everything requested for the batch must be decided first
+    let requirements = batch_requirements::Entity::find()
+        .filter(batch_requirements::Column::BatchId.eq(batch_id))
+        .all(conn)
+        .await?;
+
+    let pending: Vec<i32> = requirements
+        .iter()
+        .filter(|r| r.status == RequirementStatus::Pending)
+        .map(|r| r.requirement_id)
+        .collect();
+    if !pending.is_empty() {
+        return Err(BatchCloseError::PendingRequirements {
+            batch_id,
+            requirement_ids: pending,
+        });
+    }
+
+    // 2. Allocated stock value by item category
+    let item_codes: Vec<String> = requirements.iter().map(|r| r.item_code.clone()).collect();
+    let categories: HashMap<String, ItemCategory> = items::Entity::find()
+        .filter(items::Column::ItemCode.is_in(item_codes))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|i| (i.item_code, i.item_category))
+        .collect();
+    let requirement_items: HashMap<i32, &String> = requirements
+        .iter()
+        .map(|r| (r.requirement_id, &r.item_code))
+        .collect();
+
+    let allocations = batch_allocations::Entity::find()
+        .filter(
+            batch_allocations::Column::RequirementId
+                .is_in(requirement_items.keys().copied().collect::<Vec<_>>()),
+        )
+        .filter(batch_allocations::Column::VoidedAt.is_null())
+        .all(conn)
+        .await?;
+
+    let mut chick_cost = Decimal::ZERO;
+    let mut feed_cost = Decimal::ZERO;
+    let mut medicine_cost = Decimal::ZERO;
+    for allocation in &allocations {
+        let category = allocation
+            .requirement_id
+            .and_then(|id| requirement_items.get(&id))
+            .and_then(|code| categories.get(*code));
+        match category {
+            Some(ItemCategory::Chicks) => chick_cost += allocation.allocated_value,
+            Some(ItemCategory::Feed) => feed_cost += allocation.allocated_value,
+            Some(ItemCategory::Medicine) => medicine_cost += allocation.allocated_value,
+            Some(ItemCategory::FinishedBirds) | None => {}
+        }
+    }
+
+    // 3. Farmer commission booked against the batch
+    let farmer_commission: Decimal = farmer_commission_history::Entity::find()
+        .filter(farmer_commission_history::Column::BatchId.eq(batch_id))
+        .filter(farmer_commission_history::Column::VoidedAt.is_null())
+        .all(conn)
+        .await?
+        .iter()
+        .map(|c| c.commission_amount)
+        .sum();
+
+    // 4. Revenue and birds sold
+    let sales = batch_sales::Entity::find()
+        .filter(batch_sales::Column::BatchId.eq(batch_id))
+        .filter(batch_sales::Column::VoidedAt.is_null())
+        .all(conn)
+        .await?;
+    let revenue: Decimal = sales.iter().map(|s| s.value).sum();
+    let birds_sold = sales
+        .iter()
+        .map(|s| s.quantity)
+        .sum::<Decimal>()
+        .to_i32()
+        .unwrap_or_default();
+
+    // 5. Mortality
+    let history = bird_count_history::Entity::find()
+        .filter(bird_count_history::Column::BatchId.eq(batch_id))
+        .all(conn)
+        .await?;
+    let mortality: i32 = history.iter().map(|h| h.deaths).sum();
+    // Stocking rows are already in initial_bird_count
+    let additions: i32 = history
+        .iter()
+        .filter(|h| !h.is_stocking)
+        .map(|h| h.additions)
+        .sum();
+    let birds_placed = batch.initial_bird_count + additions;
+    let mortality_pct = if birds_placed > 0 {
+        (Decimal::from(mortality) * Decimal::from(100) / Decimal::from(birds_placed)).round_dp(2)
+    } else {
+        Decimal::ZERO
+    };
+
+    let total_cost = chick_cost + feed_cost + medicine_cost + farmer_commission;
+    let available =
+        (batch.current_bird_count.unwrap_or(birds_placed - mortality) - birds_sold).max(0);
+
+    let summary = batch_closure_summary::ActiveModel {
+        batch_id: Set(batch_id),
+        start_date: Set(batch.start_date),
+        end_date: Set(payload.end_date.unwrap_or_else(|| Utc::now().date_naive())),
+        initial_chicken_count: Set(batch.initial_bird_count),
+        available_chicken_count: Set(available),
+        revenue: Set(revenue),
+        gross_profit: Set(revenue - total_cost),
+        chick_cost: Set(chick_cost),
+        feed_cost: Set(feed_cost),
+        medicine_cost: Set(medicine_cost),
+        farmer_commission: Set(farmer_commission),
+        total_cost: Set(total_cost),
+        birds_placed: Set(birds_placed),
+        mortality: Set(mortality),
+        mortality_pct: Set(mortality_pct),
+        birds_sold: Set(birds_sold),
+        closed_at: Set(Utc::now().into()),
+        closed_by: Set(payload.closed_by),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    set_batch_status(conn, batch, BatchStatus::Closed, payload.closed_by, None).await?;
+
+    Ok(summary)
+}
+
+/// Keep a closed batch's cost sheet in step with commission booked, or voided,
+/// after it was closed. Does nothing for batches without a summary.
+pub async fn add_commission_to_summary<C: ConnectionTrait>(
+    conn: &C,
+    batch_id: i32,
+    amount: Decimal,
+) -> Result<(), DbErr> {
+    if let Some(summary) = batch_closure_summary::Entity::find()
+        .filter(batch_closure_summary::Column::BatchId.eq(batch_id))
+        .one(conn)
+        .await?
+    {
+        let mut active: batch_closure_summary::ActiveModel = summary.clone().into();
+        active.farmer_commission = Set(summary.farmer_commission + amount);
+        active.total_cost = Set(summary.total_cost + amount);
+        active.gross_profit = Set(summary.gross_profit - amount);
+        active.update(conn).await?;
+    }
+
+    Ok(())
+}
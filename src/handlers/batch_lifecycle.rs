@@ -0,0 +1,340 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use entity::sea_orm_active_enums::BatchStatus;
+use entity::{
+    batch_closure_summary, batch_requirements, batch_sales, batch_status_changes, batches,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, Set, TransactionTrait,
+};
+use thiserror::Error;
+
+use crate::models::{ChangeBatchStatus, ReopenBatch, ResponseMessage};
+
+/// Writes whose validity depends on where a batch is in its lifecycle:
+/// planned -> open -> selling -> closed -> settled.
+#[derive(Debug, Clone, Copy)]
+pub enum BatchOperation {
+    RaiseRequirement,
+    ApproveRequirement,
+    AllocateStock,
+    RecordBirdCount,
+    RecordFlockLog,
+    RecordHealthTask,
+    RecordSale,
+    RecordCommission,
+    RecordAdvance,
+    Close,
+    Settle,
+}
+
+impl BatchOperation {
+    fn allowed_in(self) -> &'static [BatchStatus] {
+        match self {
+            BatchOperation::RaiseRequirement
+            | BatchOperation::ApproveRequirement
+            | BatchOperation::AllocateStock => &[
+                BatchStatus::Planned,
+                BatchStatus::Open,
+                BatchStatus::Selling,
+            ],
+            BatchOperation::RecordBirdCount
+            | BatchOperation::RecordFlockLog
+            | BatchOperation::RecordHealthTask
+            | BatchOperation::RecordSale
+            | BatchOperation::Close => &[BatchStatus::Open, BatchStatus::Selling],
+            // Growers are usually paid once the batch is closed
+            BatchOperation::RecordCommission => {
+                &[BatchStatus::Open, BatchStatus::Selling, BatchStatus::Closed]
+            }
+            BatchOperation::RecordAdvance => &[
+                BatchStatus::Planned,
+                BatchStatus::Open,
+                BatchStatus::Selling,
+                BatchStatus::Closed,
+            ],
+            BatchOperation::Settle => &[BatchStatus::Closed],
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            BatchOperation::RaiseRequirement => "raise requirements",
+            BatchOperation::ApproveRequirement => "approve requirements",
+            BatchOperation::AllocateStock => "allocate stock",
+            BatchOperation::RecordBirdCount => "record bird counts",
+            BatchOperation::RecordFlockLog => "record flock logs",
+            BatchOperation::RecordHealthTask => "complete health tasks",
+            BatchOperation::RecordSale => "record sales",
+            BatchOperation::RecordCommission => "record farmer commission",
+            BatchOperation::RecordAdvance => "record farmer advances",
+            BatchOperation::Close => "be closed",
+            BatchOperation::Settle => "be settled",
+        }
+    }
+}
+
+pub fn status_label(status: &BatchStatus) -> &'static str {
+    match status {
+        BatchStatus::Planned => "planned",
+        BatchStatus::Open => "open",
+        BatchStatus::Selling => "selling",
+        BatchStatus::Closed => "closed",
+        BatchStatus::Settled => "settled",
+    }
+}
+
+/// Moves an admin may make directly. Closing goes through `close_batch` so
+/// the cost sheet is always computed, and reopening has its own endpoint.
+fn can_transition(from: &BatchStatus, to: &BatchStatus) -> bool {
+    matches!(
+        (from, to),
+        (BatchStatus::Planned, BatchStatus::Open)
+            | (BatchStatus::Open, BatchStatus::Selling)
+            | (BatchStatus::Closed, BatchStatus::Settled)
+    )
+}
+
+#[derive(Debug, Error)]
+pub enum BatchStateError {
+    #[error("Batch {0} not found")]
+    NotFound(i32),
+    #[error("Requirement {0} not found")]
+    RequirementNotFound(i32),
+    #[error(
+        "Batch {batch_id} is {state}; it cannot {action}",
+        state = status_label(.status),
+        action = .operation.describe()
+    )]
+    NotAllowed {
+        batch_id: i32,
+        status: BatchStatus,
+        operation: BatchOperation,
+    },
+    #[error(
+        "Batch {batch_id} is {state}; it cannot move to {target}",
+        state = status_label(.from),
+        target = status_label(.to)
+    )]
+    InvalidTransition {
+        batch_id: i32,
+        from: BatchStatus,
+        to: BatchStatus,
+    },
+    #[error("A reason is required to reopen a batch")]
+    MissingReason,
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl BatchStateError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            BatchStateError::NotFound(_) | BatchStateError::RequirementNotFound(_) => {
+                StatusCode::NOT_FOUND
+            }
+            BatchStateError::NotAllowed { .. } | BatchStateError::InvalidTransition { .. } => {
+                StatusCode::CONFLICT
+            }
+            BatchStateError::MissingReason => StatusCode::BAD_REQUEST,
+            BatchStateError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for BatchStateError {
+    fn into_response(self) -> Response {
+        eprintln!("Batch lifecycle error: {}", self);
+        (
+            self.status(),
+            Json(ResponseMessage {
+                message: self.to_string(),
+            }),
+        )
+            .into_response()
+    }
+}
+
+/// Error for handlers that otherwise answer with a bare status code, so a
+/// lifecycle refusal still tells the caller what state the batch is in.
+#[derive(Debug)]
+pub enum BatchWriteError {
+    Lifecycle(BatchStateError),
+    Status(StatusCode),
+}
+
+impl From<BatchStateError> for BatchWriteError {
+    fn from(err: BatchStateError) -> Self {
+        BatchWriteError::Lifecycle(err)
+    }
+}
+
+impl From<StatusCode> for BatchWriteError {
+    fn from(status: StatusCode) -> Self {
+        BatchWriteError::Status(status)
+    }
+}
+
+impl IntoResponse for BatchWriteError {
+    fn into_response(self) -> Response {
+        match self {
+            BatchWriteError::Lifecycle(err) => err.into_response(),
+            BatchWriteError::Status(status) => status.into_response(),
+        }
+    }
+}
+
+/// Fetch the batch and check that `operation` is allowed in its current state.
+pub async fn ensure_batch_allows<C: ConnectionTrait>(
+    conn: &C,
+    batch_id: i32,
+    operation: BatchOperation,
+) -> Result<batches::Model, BatchStateError> {
+    let batch = batches::Entity::find_by_id(batch_id)
+        .one(conn)
+        .await?
+        .ok_or(BatchStateError::NotFound(batch_id))?;
+
+    if !operation.allowed_in().contains(&batch.status) {
+        return Err(BatchStateError::NotAllowed {
+            batch_id,
+            status: batch.status,
+            operation,
+        });
+    }
+
+    Ok(batch)
+}
+
+/// Same check, for writes that arrive with a requirement rather than a batch.
+pub async fn ensure_requirement_batch_allows<C: ConnectionTrait>(
+    conn: &C,
+    requirement_id: i32,
+    operation: BatchOperation,
+) -> Result<batches::Model, BatchStateError> {
+    let requirement = batch_requirements::Entity::find_by_id(requirement_id)
+        .one(conn)
+        .await?
+        .ok_or(BatchStateError::RequirementNotFound(requirement_id))?;
+
+    ensure_batch_allows(conn, requirement.batch_id, operation).await
+}
+
+/// Move a batch to `to` and record the change.
+pub async fn set_batch_status<C: ConnectionTrait>(
+    conn: &C,
+    batch: batches::Model,
+    to: BatchStatus,
+    changed_by: Option<i32>,
+    reason: Option<String>,
+) -> Result<batches::Model, DbErr> {
+    batch_status_changes::ActiveModel {
+        batch_id: Set(batch.batch_id),
+        from_status: Set(status_label(&batch.status).to_string()),
+        to_status: Set(status_label(&to).to_string()),
+        reason: Set(reason),
+        changed_by: Set(changed_by),
+        changed_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    let mut active: batches::ActiveModel = batch.into();
+    active.status = Set(to);
+    active.update(conn).await
+}
+
+pub async fn change_batch_status_handler(
+    State(db): State<DatabaseConnection>,
+    Path(batch_id): Path<i32>,
+    Json(payload): Json<ChangeBatchStatus>,
+) -> Result<Json<batches::Model>, BatchStateError> {
+    let txn = db.begin().await?;
+
+    let batch = batches::Entity::find_by_id(batch_id)
+        .one(&txn)
+        .await?
+        .ok_or(BatchStateError::NotFound(batch_id))?;
+
+    if !can_transition(&batch.status, &payload.status) {
+        return Err(BatchStateError::InvalidTransition {
+            batch_id,
+            from: batch.status,
+            to: payload.status,
+        });
+    }
+
+    let batch = set_batch_status(
+        &txn,
+        batch,
+        payload.status,
+        payload.changed_by,
+        payload.reason,
+    )
+    .await?;
+    txn.commit().await?;
+
+    Ok(Json(batch))
+}
+
+/// Reopen a closed batch. The stored cost sheet is dropped, since closing the
+/// batch again recomputes it from whatever is recorded after reopening.
+pub async fn reopen_batch_handler(
+    State(db): State<DatabaseConnection>,
+    Path(batch_id): Path<i32>,
+    Json(payload): Json<ReopenBatch>,
+) -> Result<Json<batches::Model>, BatchStateError> {
+    if payload.reason.trim().is_empty() {
+        return Err(BatchStateError::MissingReason);
+    }
+
+    let txn = db.begin().await?;
+
+    let batch = batches::Entity::find_by_id(batch_id)
+        .one(&txn)
+        .await?
+        .ok_or(BatchStateError::NotFound(batch_id))?;
+
+    let sales = batch_sales::Entity::find()
+        .filter(batch_sales::Column::BatchId.eq(batch_id))
+        .filter(batch_sales::Column::VoidedAt.is_null())
+        .count(&txn)
+        .await?;
+    let target = if sales > 0 {
+        BatchStatus::Selling
+    } else {
+        BatchStatus::Open
+    };
+
+    if batch.status != BatchStatus::Closed {
+        return Err(BatchStateError::InvalidTransition {
+            batch_id,
+            from: batch.status,
+            to: target,
+        });
+    }
+
+    batch_closure_summary::Entity::delete_many()
+        .filter(batch_closure_summary::Column::BatchId.eq(batch_id))
+        .exec(&txn)
+        .await?;
+
+    let batch = set_batch_status(
+        &txn,
+        batch,
+        target,
+        Some(payload.reopened_by),
+        Some(payload.reason),
+    )
+    .await?;
+    txn.commit().await?;
+
+    Ok(Json(batch))
+}
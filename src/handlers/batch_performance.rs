@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use entity::sea_orm_active_enums::ItemCategory;
+use entity::{
+    batch_allocation_lines, batch_allocations, batch_requirements, batch_sales, batches,
+    bird_count_history, farmer_commission_history, farmers, items, production_lines,
+    stock_receipts,
+};
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+
+use crate::handlers::purchases::internal_error;
+use crate::models::{
+    BatchComparisonQuery, BatchComparisonResponse, BatchPerformance, PerformanceGroup,
+    PerformanceMetrics,
+};
+
+/// Raw quantities a batch's metrics are derived from. Kept separately from
+/// `PerformanceMetrics` so farmer and line groups can sum them before any
+/// ratio is taken.
+#[derive(Default, Clone)]
+struct PerformanceTotals {
+    birds_placed: i32,
+    mortality: i32,
+    birds_sold: Decimal,
+    live_weight_kg: Decimal,
+    feed_kg: Decimal,
+    feed_cost: Decimal,
+    total_cost: Decimal,
+}
+
+impl PerformanceTotals {
+    fn add(&mut self, other: &PerformanceTotals) {
+        self.birds_placed += other.birds_placed;
+        self.mortality += other.mortality;
+        self.birds_sold += other.birds_sold;
+        self.live_weight_kg += other.live_weight_kg;
+        self.feed_kg += other.feed_kg;
+        self.feed_cost += other.feed_cost;
+        self.total_cost += other.total_cost;
+    }
+
+    fn metrics(&self) -> PerformanceMetrics {
+        let ratio = |num: Decimal, den: Decimal, dp: u32| {
+            (den > Decimal::ZERO).then(|| (num / den).round_dp(dp))
+        };
+
+        PerformanceMetrics {
+            birds_placed: self.birds_placed,
+            mortality: self.mortality,
+            mortality_pct: ratio(
+                Decimal::from(self.mortality) * Decimal::from(100),
+                Decimal::from(self.birds_placed),
+                2,
+            ),
+            birds_sold: self.birds_sold,
+            live_weight_kg: self.live_weight_kg,
+            avg_weight: ratio(self.live_weight_kg, self.birds_sold, 3),
+            feed_kg: self.feed_kg,
+            feed_cost: self.feed_cost,
+            total_cost: self.total_cost,
+            fcr: ratio(self.feed_kg, self.live_weight_kg, 3),
+            cost_per_kg: ratio(self.total_cost, self.live_weight_kg, 2),
+        }
+    }
+}
+
+/// Compute the performance totals for each batch, keyed by batch id.
+/// Voided allocations, sales and commissions are left out.
+async fn performance_totals<C: ConnectionTrait>(
+    conn: &C,
+    batch_list: &[batches::Model],
+) -> Result<HashMap<i32, PerformanceTotals>, DbErr> {
+    let batch_ids: Vec<i32> = batch_list.iter().map(|b| b.batch_id).collect();
+    let mut totals: HashMap<i32, PerformanceTotals> = batch_list
+        .iter()
+        .map(|b| {
+            (
+                b.batch_id,
+                PerformanceTotals {
+                    birds_placed: b.initial_bird_count,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+
+    // 1. Mortality and later placements
+    for record in bird_count_history::Entity::find()
+        .filter(bird_count_history::Column::BatchId.is_in(batch_ids.clone()))
+        .all(conn)
+        .await?
+    {
+        if let Some(t) = totals.get_mut(&record.batch_id) {
+            // Stocking rows are already in initial_bird_count
+            if !record.is_stocking {
+                t.birds_placed += record.additions;
+            }
+            t.mortality += record.deaths;
+        }
+    }
+
+    // 2. Sales: live weight is the average weight times the birds sold
+    for sale in batch_sales::Entity::find()
+        .filter(batch_sales::Column::BatchId.is_in(batch_ids.clone()))
+        .filter(batch_sales::Column::VoidedAt.is_null())
+        .all(conn)
+        .await?
+    {
+        if let Some(t) = totals.get_mut(&sale.batch_id) {
+            t.birds_sold += sale.quantity;
+            t.live_weight_kg += sale.avg_weight * sale.quantity;
+        }
+    }
+
+    // 3. Stock issued to the batch, lot by lot
+    let requirement_batches: HashMap<i32, i32> = batch_requirements::Entity::find()
+        .filter(batch_requirements::Column::BatchId.is_in(batch_ids.clone()))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|r| (r.requirement_id, r.batch_id))
+        .collect();
+
+    let allocation_batches: HashMap<i32, i32> = batch_allocations::Entity::find()
+        .filter(
+            batch_allocations::Column::RequirementId
+                .is_in(requirement_batches.keys().copied().collect::<Vec<_>>()),
+        )
+        .filter(batch_allocations::Column::VoidedAt.is_null())
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter_map(|a| {
+            a.requirement_id
+                .and_then(|id| requirement_batches.get(&id))
+                .map(|batch_id| (a.allocation_id, *batch_id))
+        })
+        .collect();
+
+    let lines = batch_allocation_lines::Entity::find()
+        .filter(
+            batch_allocation_lines::Column::AllocationId
+                .is_in(allocation_batches.keys().copied().collect::<Vec<_>>()),
+        )
+        .all(conn)
+        .await?;
+
+    let lot_items: HashMap<i32, String> = stock_receipts::Entity::find()
+        .filter(
+            stock_receipts::Column::LotId.is_in(lines.iter().map(|l| l.lot_id).collect::<Vec<_>>()),
+        )
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|lot| (lot.lot_id, lot.item_code))
+        .collect();
+
+    let categories: HashMap<String, ItemCategory> = items::Entity::find()
+        .filter(items::Column::ItemCode.is_in(lot_items.values().cloned().collect::<Vec<_>>()))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|i| (i.item_code, i.item_category))
+        .collect();
+
+    for line in &lines {
+        let Some(t) = allocation_batches
+            .get(&line.allocation_id)
+            .and_then(|batch_id| totals.get_mut(batch_id))
+        else {
+            continue;
+        };
+
+        t.total_cost += line.line_value;
+        let category = lot_items
+            .get(&line.lot_id)
+            .and_then(|code| categories.get(code));
+        if category == Some(&ItemCategory::Feed) {
+            t.feed_kg += line.qty;
+            t.feed_cost += line.line_value;
+        }
+    }
+
+    // 4. Farmer commission
+    for commission in farmer_commission_history::Entity::find()
+        .filter(farmer_commission_history::Column::BatchId.is_in(batch_ids))
+        .filter(farmer_commission_history::Column::VoidedAt.is_null())
+        .all(conn)
+        .await?
+    {
+        if let Some(t) = commission
+            .batch_id
+            .and_then(|batch_id| totals.get_mut(&batch_id))
+        {
+            t.total_cost += commission.commission_amount;
+        }
+    }
+
+    Ok(totals)
+}
+
+/// Feed issued to a batch, in kg, net of voided allocations.
+pub async fn allocated_feed_kg<C: ConnectionTrait>(
+    conn: &C,
+    batch: &batches::Model,
+) -> Result<Decimal, DbErr> {
+    Ok(performance_totals(conn, std::slice::from_ref(batch))
+        .await?
+        .get(&batch.batch_id)
+        .map(|t| t.feed_kg)
+        .unwrap_or_default())
+}
+
+async fn names<C: ConnectionTrait>(
+    conn: &C,
+) -> Result<(HashMap<i32, String>, HashMap<i32, String>), DbErr> {
+    let farmer_names = farmers::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|f| (f.farmer_id, f.name))
+        .collect();
+    let line_names = production_lines::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|l| (l.line_id, l.line_name))
+        .collect();
+
+    Ok((farmer_names, line_names))
+}
+
+fn batch_performance(
+    batch: &batches::Model,
+    totals: &PerformanceTotals,
+    farmer_names: &HashMap<i32, String>,
+    line_names: &HashMap<i32, String>,
+) -> BatchPerformance {
+    BatchPerformance {
+        batch_id: batch.batch_id,
+        farmer_id: batch.farmer_id,
+        farmer_name: farmer_names
+            .get(&batch.farmer_id)
+            .cloned()
+            .unwrap_or_default(),
+        line_id: batch.line_id,
+        line_name: line_names.get(&batch.line_id).cloned().unwrap_or_default(),
+        status: batch.status.clone(),
+        start_date: batch.start_date,
+        metrics: totals.metrics(),
+    }
+}
+
+pub async fn get_batch_performance_handler(
+    State(db): State<DatabaseConnection>,
+    Path(batch_id): Path<i32>,
+) -> Result<Json<BatchPerformance>, StatusCode> {
+    let batch = batches::Entity::find_by_id(batch_id)
+        .one(&db)
+        .await
+        .map_err(internal_error("fetch batch"))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let totals = performance_totals(&db, std::slice::from_ref(&batch))
+        .await
+        .map_err(internal_error("compute batch performance"))?;
+    let (farmer_names, line_names) = names(&db)
+        .await
+        .map_err(internal_error("fetch farmer and line names"))?;
+
+    Ok(Json(batch_performance(
+        &batch,
+        totals
+            .get(&batch_id)
+            .unwrap_or(&PerformanceTotals::default()),
+        &farmer_names,
+        &line_names,
+    )))
+}
+
+/// Compare batches side by side, and summed per farmer and per production line.
+pub async fn get_batch_comparison_handler(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<BatchComparisonQuery>,
+) -> Result<Json<BatchComparisonResponse>, StatusCode> {
+    let mut select = batches::Entity::find().order_by_asc(batches::Column::BatchId);
+    if let Some(from) = query.from {
+        select = select.filter(batches::Column::StartDate.gte(from));
+    }
+    if let Some(to) = query.to {
+        select = select.filter(batches::Column::StartDate.lte(to));
+    }
+    if let Some(farmer_id) = query.farmer_id {
+        select = select.filter(batches::Column::FarmerId.eq(farmer_id));
+    }
+    if let Some(line_id) = query.line_id {
+        select = select.filter(batches::Column::LineId.eq(line_id));
+    }
+
+    let batch_list = select
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch batches"))?;
+    let totals = performance_totals(&db, &batch_list)
+        .await
+        .map_err(internal_error("compute batch performance"))?;
+    let (farmer_names, line_names) = names(&db)
+        .await
+        .map_err(internal_error("fetch farmer and line names"))?;
+
+    let mut per_farmer: HashMap<i32, (usize, PerformanceTotals)> = HashMap::new();
+    let mut per_line: HashMap<i32, (usize, PerformanceTotals)> = HashMap::new();
+    let mut batch_lines = Vec::with_capacity(batch_list.len());
+
+    for batch in &batch_list {
+        let batch_totals = totals.get(&batch.batch_id).cloned().unwrap_or_default();
+
+        let farmer = per_farmer.entry(batch.farmer_id).or_default();
+        farmer.0 += 1;
+        farmer.1.add(&batch_totals);
+
+        let line = per_line.entry(batch.line_id).or_default();
+        line.0 += 1;
+        line.1.add(&batch_totals);
+
+        batch_lines.push(batch_performance(
+            batch,
+            &batch_totals,
+            &farmer_names,
+            &line_names,
+        ));
+    }
+
+    let groups = |grouped: HashMap<i32, (usize, PerformanceTotals)>,
+                  group_names: &HashMap<i32, String>| {
+        let mut groups: Vec<PerformanceGroup> = grouped
+            .into_iter()
+            .map(|(id, (batch_count, totals))| PerformanceGroup {
+                id,
+                name: group_names.get(&id).cloned().unwrap_or_default(),
+                batch_count,
+                metrics: totals.metrics(),
+            })
+            .collect();
+        groups.sort_by_key(|g| g.id);
+        groups
+    };
+
+    Ok(Json(BatchComparisonResponse {
+        batches: batch_lines,
+        by_farmer: groups(per_farmer, &farmer_names),
+        by_line: groups(per_line, &line_names),
+    }))
+}
@@ -5,22 +5,23 @@ use axum::{
     Json,
 };
 use entity::{
-    batch_allocation_lines, batch_allocations, batches, bird_count_history, items, ledger_accounts,
-    ledger_entries,
-    sea_orm_active_enums::{ItemCategory, RequirementStatus},
-    stock_receipts,
+    batch_allocation_lines, batch_allocations, batches, bird_count_history, items, ledger_entries,
+    sea_orm_active_enums::{ItemCategory, PostingEvent, RequirementStatus},
 };
 use entity::{
     batch_requirements, inventory, inventory_movements, sea_orm_active_enums::MovementType,
 };
-use sea_orm::ColumnTrait;
-use sea_orm::{
-    prelude::Decimal, ActiveModelTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
-    Set,
-};
+use sea_orm::{prelude::Decimal, ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
 use sea_orm::{DatabaseTransaction, IntoActiveModel, TransactionTrait};
 use uuid::Uuid;
 
+use crate::handlers::batch_lifecycle::{ensure_requirement_batch_allows, BatchOperation};
+use crate::handlers::costing::draw_lots;
+use crate::handlers::locations::{farm_location, issue_to_farm, resolve_location, TransferRequest};
+use crate::handlers::periods::ensure_period_open;
+use crate::handlers::posting_rules::resolve_posting_rule;
+use crate::handlers::purchases::update_account_balance;
+use crate::handlers::units::requirement_base_quantity;
 use crate::models::{ApprovePayload, ResponseMessage};
 
 pub async fn decline_batch_requirement_handler(
@@ -66,11 +67,54 @@ pub async fn decline_batch_requirement_handler(
 
 pub async fn approve_batch_requirement_handler(
     State(db): State<DatabaseConnection>,
-    Json(payload): Json<ApprovePayload>,
+    Json(mut payload): Json<ApprovePayload>,
 ) -> impl IntoResponse {
     // Start transaction
     match db.begin().await {
         Ok(txn) => {
+            if let Err(e) = ensure_period_open(&txn, chrono::Utc::now().date_naive()).await {
+                eprintln!("Posting refused: {}", e);
+                return (
+                    e.status(),
+                    Json(ResponseMessage {
+                        message: e.to_string(),
+                    }),
+                )
+                    .into_response();
+            }
+
+            if let Err(e) = ensure_requirement_batch_allows(
+                &txn,
+                payload.requirement_id,
+                BatchOperation::ApproveRequirement,
+            )
+            .await
+            {
+                return e.into_response();
+            }
+
+            // Allocations are recorded in the item's base unit
+            match requirement_base_quantity(
+                &txn,
+                payload.requirement_id,
+                payload.unit.as_deref(),
+                payload.allocated_qty,
+            )
+            .await
+            {
+                Ok(quantity) => payload.allocated_qty = quantity,
+                Err(e) => {
+                    eprintln!("Unit conversion refused: {}", e);
+                    return (
+                        e.status(),
+                        Json(ResponseMessage {
+                            message: e.to_string(),
+                        }),
+                    )
+                        .into_response();
+                }
+            }
+
             let result = approve_and_allocate(payload.requirement_id, payload, &txn).await;
 
             match result {
@@ -109,6 +153,19 @@ async fn approve_and_allocate(
         .map_err(|e| format!("DB fetch error: {}", e))?
         .ok_or_else(|| format!("Requirement {} not found", requirement_id))?;
 
+    // Stock leaves the source location for the farm the batch is raised on
+    let source = resolve_location(txn, payload.from_location_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let batch = batches::Entity::find_by_id(requirement.batch_id)
+        .one(txn)
+        .await
+        .map_err(|e| format!("Failed to fetch batch {}: {}", requirement.batch_id, e))?
+        .ok_or_else(|| format!("Batch {} not found", requirement.batch_id))?;
+    let farm = farm_location(txn, batch.farmer_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
     // 2. Update requirement status -> Accept
     let mut active_model = requirement.clone().into_active_model();
     active_model.status = Set(RequirementStatus::Accept);
@@ -123,8 +180,10 @@ async fn approve_and_allocate(
         requirement_id: Set(Some(payload.requirement_id)),
         allocated_qty: Set(payload.allocated_qty),
         allocation_date: Set(payload.allocation_date),
-        allocated_value: Set(Decimal::ZERO), // to be updated after FIFO allocation
+        allocated_value: Set(Decimal::ZERO), // to be updated once the lots are drawn
         allocated_by: Set(payload.allocated_by),
+
+        ..Default::default()
     };
 
     let allocation_model = allocation
@@ -162,13 +221,14 @@ async fn approve_and_allocate(
         ));
     }
 
-    // 5. Insert inventory movement (OUT)
+    // 5. Insert inventory movement (OUT, issued at the farm)
     let movement = inventory_movements::ActiveModel {
         movement_id: Default::default(),
         item_code: Set(requirement.item_code.clone()),
         movement_type: Set(MovementType::Allocation),
         qty_change: Set(-payload.allocated_qty),
         reference_id: Set(Some(allocation_model.allocation_id)),
+        location_id: Set(Some(farm.location_id)),
         ..Default::default()
     };
 
@@ -177,54 +237,46 @@ async fn approve_and_allocate(
         .await
         .map_err(|e| format!("Failed to insert inventory movement: {}", e))?;
 
-    // -----------------------------------------------------------
-    // 6. FIFO allocation from stock_receipts -> batch_allocation_lines
-    // -----------------------------------------------------------
-    let mut qty_to_allocate = payload.allocated_qty;
-    let mut total_value = Decimal::ZERO;
-
-    // fetch lots in FIFO order
-    let receipts = stock_receipts::Entity::find()
-        .filter(stock_receipts::Column::ItemCode.eq(requirement.item_code.clone()))
-        .filter(stock_receipts::Column::RemainingQty.gt(Decimal::ZERO))
-        .order_by_asc(stock_receipts::Column::ReceivedDate)
-        .order_by_asc(stock_receipts::Column::LotId)
-        .all(txn)
+    let item = items::Entity::find_by_id(requirement.item_code.clone())
+        .one(txn)
         .await
-        .map_err(|e| format!("Failed to fetch stock receipts: {}", e))?;
-
-    for r in receipts {
-        if qty_to_allocate <= Decimal::ZERO {
-            break;
-        }
-
-        let take = std::cmp::min(r.remaining_qty, qty_to_allocate);
-        let line_value = take * r.unit_cost;
+        .map_err(|e| {
+            tracing::error!("Failed to fetch item {}: {}", requirement.item_code, e);
+            format!("Failed to fetch item: {}", e)
+        })?
+        .ok_or_else(|| {
+            tracing::error!("Item {} not found", requirement.item_code);
+            format!("Item {} not found", requirement.item_code)
+        })?;
 
-        // insert allocation line
-        let line = batch_allocation_lines::ActiveModel {
+    // -----------------------------------------------------------
+    // 6. Draw the source lots down -> batch_allocation_lines
+    // -----------------------------------------------------------
+    let issue = draw_lots(
+        txn,
+        &requirement.item_code,
+        &item.item_category,
+        source.location_id,
+        payload.allocated_qty,
+        Some(payload.allocation_date),
+    )
+    .await
+    .map_err(|e| format!("Failed to draw stock lots: {}", e))?;
+
+    for draw in &issue.draws {
+        batch_allocation_lines::ActiveModel {
             allocation_line_id: Default::default(),
             allocation_id: Set(allocation_model.allocation_id),
-            lot_id: Set(r.lot_id),
-            qty: Set(take),
-            unit_cost: Set(r.unit_cost),
-            line_value: Set(line_value),
-        };
-        line.insert(txn)
-            .await
-            .map_err(|e| format!("Failed to insert allocation line: {}", e))?;
-
-        // update lot remaining qty
-        let mut r_active: stock_receipts::ActiveModel = r.into();
-        r_active.remaining_qty = Set(r_active.remaining_qty.take().unwrap() - take);
-        r_active
-            .update(txn)
-            .await
-            .map_err(|e| format!("Failed to update stock_receipt: {}", e))?;
-
-        total_value += line_value;
-        qty_to_allocate -= take;
+            lot_id: Set(draw.lot_id),
+            qty: Set(draw.qty),
+            unit_cost: Set(draw.unit_cost),
+            line_value: Set(draw.value),
+        }
+        .insert(txn)
+        .await
+        .map_err(|e| format!("Failed to insert allocation line: {}", e))?;
     }
+    let total_value = issue.total_value;
 
     // update allocation with monetary worth
     let mut alloc_update: batch_allocations::ActiveModel = allocation_model.clone().into();
@@ -234,32 +286,54 @@ async fn approve_and_allocate(
         .await
         .map_err(|e| format!("Failed to update allocation value: {}", e))?;
 
-    if qty_to_allocate > Decimal::ZERO {
+    if issue.shortage > Decimal::ZERO {
         // not enough stock: business decision → error, negative stock, or backorder
-        return Err(format!(
+        let mut message = format!(
             "Partial allocation: shortage of {} units for item {}",
-            qty_to_allocate, requirement.item_code
-        ));
+            issue.shortage, requirement.item_code
+        );
+        if issue.expired_qty > Decimal::ZERO {
+            message.push_str(&format!(
+                " ({} units are in expired lots and cannot be issued)",
+                issue.expired_qty
+            ));
+        }
+        return Err(message);
     }
 
-    let item = items::Entity::find_by_id(requirement.item_code.clone())
-        .one(txn)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch item {}: {}", requirement.item_code, e);
-            format!("Failed to fetch item: {}", e)
-        })?
-        .ok_or_else(|| {
-            tracing::error!("Item {} not found", requirement.item_code);
-            format!("Item {} not found", requirement.item_code)
-        })?;
-
-    let (asset_account_id, expense_account_id) = match item.item_category {
-        ItemCategory::Medicine => (102_i32, 107_i32), // inventory-medicine -> farm-expense
-        ItemCategory::Feed => (103_i32, 107_i32),     // inventory-feed -> farm-expense
-        ItemCategory::Chicks => (104_i32, 107_i32),   // inventory-chicks -> farm-expense
-        ItemCategory::FinishedBirds => (105_i32, 107_i32), // this is wrong
-    };
+    // 7. Move the stock to the farm and issue it to the batch there
+    issue_to_farm(
+        txn,
+        TransferRequest {
+            item_code: &requirement.item_code,
+            from_location_id: source.location_id,
+            to_location_id: farm.location_id,
+            quantity: payload.allocated_qty,
+            value: total_value,
+            transfer_date: payload.allocation_date,
+            allocation_id: Some(allocation_model.allocation_id),
+            notes: Some(format!(
+                "Sent for allocation #{}",
+                allocation_model.allocation_id
+            )),
+            created_by: Some(payload.allocated_by),
+        },
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // inventory-<category> -> farm-expense, as configured in posting_rules
+    let rule = resolve_posting_rule(
+        txn,
+        PostingEvent::Allocation,
+        Some(item.item_category.clone()),
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to resolve allocation posting rule: {}", e);
+        e.to_string()
+    })?;
+    let (asset_account_id, expense_account_id) = (rule.credit_account_id, rule.debit_account_id);
 
     if let ItemCategory::Chicks = item.item_category {
         // Convert allocated_qty (Decimal) to i32 for bird_count_history.additions
@@ -282,6 +356,7 @@ async fn approve_and_allocate(
                 additions_i32, payload.allocation_date, allocation_model.allocation_id
             )),
             created_at: Set(chrono::Utc::now().into()),
+            is_stocking: Set(false),
         };
 
         bird_history
@@ -290,19 +365,13 @@ async fn approve_and_allocate(
             .map_err(|e| format!("Failed to insert bird_count_history: {}", e))?;
 
         // Update batches.current_bird_count
-        if let Some(batch) = batches::Entity::find_by_id(requirement.batch_id)
-            .one(txn)
+        let current = batch.current_bird_count.unwrap_or(0);
+        let mut batch_active: batches::ActiveModel = batch.into();
+        batch_active.current_bird_count = Set(Some(current + additions_i32));
+        batch_active
+            .update(txn)
             .await
-            .map_err(|e| format!("Failed to fetch batch {}: {}", requirement.batch_id, e))?
-        {
-            let current = batch.current_bird_count.unwrap_or(0);
-            let mut batch_active: batches::ActiveModel = batch.into();
-            batch_active.current_bird_count = Set(Some(current + additions_i32));
-            batch_active
-                .update(txn)
-                .await
-                .map_err(|e| format!("Failed to update batch bird count: {}", e))?;
-        }
+            .map_err(|e| format!("Failed to update batch bird count: {}", e))?;
     }
 
     let txn_group_id = Uuid::new_v4();
@@ -353,26 +422,9 @@ async fn approve_and_allocate(
         format!("Failed to insert debit entry: {}", e)
     })?;
 
-    if let Some(asset_account) = ledger_accounts::Entity::find_by_id(asset_account_id)
-        .one(txn)
+    update_account_balance(txn, asset_account_id, Some(total_value), false)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to fetch asset account {}: {}", asset_account_id, e);
-            format!("Failed to fetch asset account: {}", e)
-        })?
-    {
-        let mut asset_active: ledger_accounts::ActiveModel = asset_account.into();
-        let current_balance = asset_active.current_balance.take().unwrap_or_default();
-        tracing::debug!(
-            "Asset account {} current_balance={} total_value={}",
-            asset_account_id,
-            current_balance,
-            total_value
-        );
-
-        asset_active.current_balance = Set(current_balance - total_value);
-
-        asset_active.update(txn).await.map_err(|e| {
             tracing::error!(
                 "Failed to update asset account {} balance: {}",
                 asset_account_id,
@@ -380,37 +432,9 @@ async fn approve_and_allocate(
             );
             format!("Failed to update asset account balance: {}", e)
         })?;
-        tracing::info!(
-            "Updated asset account {} balance -> {}",
-            asset_account_id,
-            current_balance - total_value
-        );
-    }
-
-    if let Some(expense_account) = ledger_accounts::Entity::find_by_id(expense_account_id)
-        .one(txn)
+    update_account_balance(txn, expense_account_id, Some(total_value), true)
         .await
         .map_err(|e| {
-            tracing::error!(
-                "Failed to fetch expense account {}: {}",
-                expense_account_id,
-                e
-            );
-            format!("Failed to fetch expense account: {}", e)
-        })?
-    {
-        let mut expense_active: ledger_accounts::ActiveModel = expense_account.into();
-        let current_balance = expense_active.current_balance.take().unwrap_or_default();
-        tracing::debug!(
-            "Expense account {} current_balance={} total_value={}",
-            expense_account_id,
-            current_balance,
-            total_value
-        );
-
-        expense_active.current_balance = Set(current_balance + total_value);
-
-        expense_active.update(txn).await.map_err(|e| {
             tracing::error!(
                 "Failed to update expense account {} balance: {}",
                 expense_account_id,
@@ -418,12 +442,6 @@ async fn approve_and_allocate(
             );
             format!("Failed to update expense account balance: {}", e)
         })?;
-        tracing::info!(
-            "Updated expense account {} balance -> {}",
-            expense_account_id,
-            current_balance + total_value
-        );
-    }
 
     Ok(format!(
         "Requirement {} approved, allocation created, inventory updated, and movement logged",
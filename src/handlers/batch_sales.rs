@@ -1,11 +1,20 @@
+use crate::handlers::batch_lifecycle::{
+    ensure_batch_allows, set_batch_status, BatchOperation, BatchWriteError,
+};
+use crate::handlers::periods::{ensure_period_open, period_status};
+use crate::handlers::posting_rules::resolve_posting_rule;
 use crate::handlers::purchases::internal_error;
 use crate::handlers::purchases::update_account_balance;
+use crate::handlers::trader_receivables::trader_outstanding;
 use crate::models::CreateBatchSale;
 use axum::{extract::State, Json};
 use chrono::Utc;
 use entity::batch_closure_summary;
 use entity::batch_sales;
+use entity::items;
 use entity::ledger_entries;
+use entity::sea_orm_active_enums::{BatchStatus, PostingEvent};
+use entity::traders;
 use num_traits::ToPrimitive;
 use reqwest::StatusCode;
 use sea_orm::prelude::Decimal;
@@ -18,18 +27,26 @@ use sea_orm::QueryFilter;
 use sea_orm::TransactionTrait;
 use uuid::Uuid;
 
-const CASH_ACCOUNT_ID: i32 = 101;
-const REVENUE_ACCOUNT_ID: i32 = 108;
-
 pub async fn create_batch_sale(
     State(db): State<DatabaseConnection>,
     Json(payload): Json<CreateBatchSale>,
-) -> Result<Json<batch_sales::Model>, StatusCode> {
+) -> Result<Json<batch_sales::Model>, BatchWriteError> {
     let txn = db.begin().await.map_err(|err| {
         eprintln!("Failed to start transaction: {:?}", err);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    ensure_period_open(&txn, Utc::now().date_naive())
+        .await
+        .map_err(period_status)?;
+
+    let batch = ensure_batch_allows(&txn, payload.batch_id, BatchOperation::RecordSale).await?;
+
+    let on_credit = payload.on_credit.unwrap_or(false);
+    if on_credit {
+        ensure_within_credit_limit(&txn, payload.trader_id, payload.value).await?;
+    }
+
     let new_sale = batch_sales::ActiveModel {
         item_code: Set(payload.item_code),
         batch_id: Set(payload.batch_id),
@@ -38,6 +55,13 @@ pub async fn create_batch_sale(
         rate: Set(payload.rate),
         quantity: Set(payload.quantity),
         value: Set(payload.value),
+        on_credit: Set(on_credit),
+        amount_due: Set(if on_credit {
+            payload.value
+        } else {
+            Decimal::ZERO
+        }),
+        amount_received: Set(Decimal::ZERO),
         ..Default::default()
     };
 
@@ -51,7 +75,7 @@ pub async fn create_batch_sale(
     {
         eprintln!("Failed to insert ledger entries for sale: {:?}", err_status);
         txn.rollback().await.ok();
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
     }
 
     if let Err(err) =
@@ -59,7 +83,20 @@ pub async fn create_batch_sale(
     {
         eprintln!("Failed to update batch financials: {:?}", err);
         txn.rollback().await.ok();
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+    }
+
+    // The first sale moves the batch into selling
+    if batch.status == BatchStatus::Open {
+        set_batch_status(
+            &txn,
+            batch,
+            BatchStatus::Selling,
+            Some(payload.created_by),
+            None,
+        )
+        .await
+        .map_err(internal_error("move batch to selling"))?;
     }
 
     txn.commit().await.map_err(|err| {
@@ -70,6 +107,34 @@ pub async fn create_batch_sale(
     Ok(Json(inserted_sale))
 }
 
+/// Refuse a credit sale that would take the trader past their credit limit.
+async fn ensure_within_credit_limit(
+    txn: &sea_orm::DatabaseTransaction,
+    trader_id: i32,
+    sale_value: Decimal,
+) -> Result<(), StatusCode> {
+    let trader = traders::Entity::find_by_id(trader_id)
+        .one(txn)
+        .await
+        .map_err(internal_error("fetch trader"))?
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    if let Some(limit) = trader.credit_limit {
+        let outstanding = trader_outstanding(txn, trader_id)
+            .await
+            .map_err(internal_error("fetch trader outstanding"))?;
+        if outstanding + sale_value > limit {
+            eprintln!(
+                "Credit sale refused: trader {} owes {} against a limit of {}",
+                trader_id, outstanding, limit
+            );
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+
+    Ok(())
+}
+
 async fn update_batch_financials(
     txn: &sea_orm::DatabaseTransaction,
     batch_id: i32,
@@ -104,15 +169,35 @@ pub async fn insert_batch_sales_ledger_entries<C: TransactionTrait + sea_orm::Co
     sale: &batch_sales::Model,
     created_by: i32,
 ) -> Result<(), StatusCode> {
+    let item = items::Entity::find_by_id(sale.item_code.clone())
+        .one(txn)
+        .await
+        .map_err(internal_error("fetch item for batch sale"))?
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let rule = resolve_posting_rule(txn, PostingEvent::BatchSale, Some(item.item_category))
+        .await
+        .map_err(internal_error("resolve batch sale posting rule"))?;
+    let revenue_account_id = rule.credit_account_id;
+
+    // Credit sales are owed by the trader: book them to the receivables account
+    let cash_account_id = if sale.on_credit {
+        resolve_posting_rule(txn, PostingEvent::TraderReceipt, None)
+            .await
+            .map_err(internal_error("resolve trader receipt posting rule"))?
+            .credit_account_id
+    } else {
+        rule.debit_account_id
+    };
+
     let txn_group_id = Uuid::new_v4();
     let sale_value: Decimal = sale.value;
 
     // txn_date: use naive date (match your ledger_entries txn_date type)
     let txn_date = Utc::now().date_naive();
 
-    // --- Debit: Cash account (Asset) ---
+    // --- Debit: Cash or receivables account (Asset) ---
     let debit_entry = ledger_entries::ActiveModel {
-        account_id: Set(CASH_ACCOUNT_ID),
+        account_id: Set(cash_account_id),
         debit: Set(Some(sale_value)),
         credit: Set(None),
         txn_date: Set(txn_date),
@@ -132,7 +217,7 @@ pub async fn insert_batch_sales_ledger_entries<C: TransactionTrait + sea_orm::Co
 
     // --- Credit: Revenue account (Revenue) ---
     let credit_entry = ledger_entries::ActiveModel {
-        account_id: Set(REVENUE_ACCOUNT_ID),
+        account_id: Set(revenue_account_id),
         debit: Set(None),
         credit: Set(Some(sale_value)),
         txn_date: Set(txn_date),
@@ -155,10 +240,10 @@ pub async fn insert_batch_sales_ledger_entries<C: TransactionTrait + sea_orm::Co
 
     // --- Update account balances ---
     // Cash account increases (Asset): pass is_debit = true
-    update_account_balance(txn, CASH_ACCOUNT_ID, Some(sale_value), true).await?;
+    update_account_balance(txn, cash_account_id, Some(sale_value), true).await?;
 
     // Revenue account increases (Revenue): pass is_debit = false (credit increases revenue)
-    update_account_balance(txn, REVENUE_ACCOUNT_ID, Some(sale_value), false).await?;
+    update_account_balance(txn, revenue_account_id, Some(sale_value), false).await?;
 
     Ok(())
 }
@@ -1,90 +1,207 @@
-use crate::models::CreateBatch;
+use std::collections::HashSet;
+
+use crate::handlers::costing::draw_lots;
+use crate::handlers::health_programs::{apply_health_program, HealthProgramError};
+use crate::handlers::journal::{post_journal, JournalError};
+use crate::handlers::locations::{
+    farm_location, issue_to_farm, resolve_location, LocationError, TransferRequest,
+};
+use crate::handlers::periods::{ensure_period_open, PeriodError};
+use crate::handlers::posting_rules::{resolve_posting_rule, PostingRuleError};
+use crate::models::{
+    ChickStocking, CreateBatch, CreateJournalVoucher, JournalLine, ResponseMessage,
+};
 use axum::{extract::State, http::StatusCode, Json};
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 use entity::batch_allocation_lines;
 use entity::batch_allocations;
+use entity::batch_placements;
 use entity::batch_requirements;
 use entity::batches;
+use entity::bird_count_history;
 use entity::inventory;
 use entity::inventory_movements;
 use entity::items;
-use entity::ledger_accounts;
-use entity::ledger_entries;
+use entity::sea_orm_active_enums::BatchStatus;
 use entity::sea_orm_active_enums::ItemCategory;
 use entity::sea_orm_active_enums::MovementType;
+use entity::sea_orm_active_enums::PostingEvent;
 use entity::sea_orm_active_enums::RequirementStatus;
-use entity::stock_receipts;
-use num_traits::cast::ToPrimitive;
 use sea_orm::prelude::Decimal;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
-    QueryFilter, QueryOrder, Set, TransactionTrait,
+    ActiveModelTrait, DatabaseConnection, DatabaseTransaction, DbErr, EntityTrait, Set,
+    TransactionTrait,
 };
-use uuid::Uuid;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum BatchCreateError {
+    #[error("Give the chicks to stock in either chick_items or chick_item_code, not both")]
+    AmbiguousItems,
+    #[error("At least one chick item is required")]
+    NoChickItems,
+    #[error("Several chick items need a quantity each; send them as chick_items")]
+    MissingQuantities,
+    #[error("Chick item {0} is listed more than once")]
+    DuplicateItem(String),
+    #[error("Quantity for chick item {0} must be positive")]
+    InvalidQuantity(String),
+    #[error("initial_bird_count is {expected} but the chick items add up to {total}")]
+    CountMismatch { expected: i32, total: i32 },
+    #[error("The chick items add up to more birds than a batch can hold")]
+    CountOverflow,
+    #[error("Item {0} not found")]
+    ItemNotFound(String),
+    #[error("Item {0} is not a chick item")]
+    NotChickItem(String),
+    #[error("Insufficient stock for {item_code}. Required: {required}, Available: {available}")]
+    InsufficientStock {
+        item_code: String,
+        required: i32,
+        available: Decimal,
+    },
+    #[error(transparent)]
+    Period(#[from] PeriodError),
+    #[error(transparent)]
+    PostingRule(#[from] PostingRuleError),
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+    #[error(transparent)]
+    HealthProgram(#[from] HealthProgramError),
+    #[error(transparent)]
+    Location(#[from] LocationError),
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl BatchCreateError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            BatchCreateError::Period(e) => e.status(),
+            BatchCreateError::Journal(e) => e.status(),
+            BatchCreateError::HealthProgram(e) => e.status(),
+            BatchCreateError::Location(e) => e.status(),
+            BatchCreateError::PostingRule(_) | BatchCreateError::Db(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
 
 pub async fn create_batch(
     State(db): State<DatabaseConnection>,
     Json(payload): Json<CreateBatch>,
-) -> Result<Json<batches::Model>, StatusCode> {
+) -> Result<Json<batches::Model>, (StatusCode, Json<ResponseMessage>)> {
     // Use transaction for data consistency
-    let txn = db
-        .begin()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    match create_batch_with_transaction(&txn, payload).await {
-        Ok(batch) => {
-            txn.commit()
+    let result = match db.begin().await {
+        Ok(txn) => match create_batch_with_transaction(&txn, payload).await {
+            Ok(batch) => txn
+                .commit()
                 .await
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-            Ok(Json(batch))
+                .map(|_| batch)
+                .map_err(BatchCreateError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(BatchCreateError::from(e)),
+    };
+
+    result.map(Json).map_err(|e| {
+        eprintln!("Failed to create batch: {}", e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    })
+}
+
+/// The chick items a batch is stocked from, each with its quantity. A single
+/// `chick_item_code` supplies the whole `initial_bird_count`.
+fn stocking_lines(payload: &CreateBatch) -> Result<Vec<ChickStocking>, BatchCreateError> {
+    let lines: Vec<ChickStocking> = match (
+        payload.chick_items.is_empty(),
+        payload.chick_item_code.as_slice(),
+    ) {
+        (false, []) => payload.chick_items.clone(),
+        (false, _) => return Err(BatchCreateError::AmbiguousItems),
+        (true, []) => return Err(BatchCreateError::NoChickItems),
+        (true, [item_code]) => vec![ChickStocking {
+            item_code: item_code.clone(),
+            quantity: payload.initial_bird_count,
+        }],
+        (true, _) => return Err(BatchCreateError::MissingQuantities),
+    };
+
+    let mut seen = HashSet::new();
+    for line in &lines {
+        if !seen.insert(line.item_code.as_str()) {
+            return Err(BatchCreateError::DuplicateItem(line.item_code.clone()));
         }
-        Err(err) => {
-            let _ = txn.rollback().await;
-            eprintln!("Failed to create batch: {}", err);
-            Err(StatusCode::BAD_REQUEST)
+        if line.quantity <= 0 {
+            return Err(BatchCreateError::InvalidQuantity(line.item_code.clone()));
         }
     }
+
+    let total = lines
+        .iter()
+        .try_fold(0i32, |total, line| total.checked_add(line.quantity))
+        .ok_or(BatchCreateError::CountOverflow)?;
+    if total != payload.initial_bird_count {
+        return Err(BatchCreateError::CountMismatch {
+            expected: payload.initial_bird_count,
+            total,
+        });
+    }
+
+    Ok(lines)
 }
 
 async fn create_batch_with_transaction(
     txn: &DatabaseTransaction,
     payload: CreateBatch,
-) -> Result<batches::Model, String> {
-    // 1. Validate chick item exists and is actually a chick
-    let item = items::Entity::find_by_id(&payload.chick_item_code[0])
-        .one(txn)
-        .await
-        .map_err(|e| format!("Failed to fetch item {}: {}", payload.chick_item_code[0], e))?
-        .ok_or_else(|| format!("Item {} not found", payload.chick_item_code[0]))?;
-
-    if item.item_category != ItemCategory::Chicks {
-        return Err(format!(
-            "Item {} is not a chick item",
-            payload.chick_item_code[0]
-        ));
-    }
+) -> Result<batches::Model, BatchCreateError> {
+    let today = Utc::now().date_naive();
+    ensure_period_open(txn, today).await?;
+
+    // Chicks come from the source location and are placed on the farmer's farm
+    let source = resolve_location(txn, payload.location_id).await?;
+    let farm = farm_location(txn, payload.farmer_id).await?;
+
+    // 1. Validate every chick item and check its stock up front
+    let lines = stocking_lines(&payload)?;
+    let mut stock = Vec::with_capacity(lines.len());
+    for line in &lines {
+        let item = items::Entity::find_by_id(&line.item_code)
+            .one(txn)
+            .await?
+            .ok_or_else(|| BatchCreateError::ItemNotFound(line.item_code.clone()))?;
+
+        if item.item_category != ItemCategory::Chicks {
+            return Err(BatchCreateError::NotChickItem(line.item_code.clone()));
+        }
 
-    // 2. Check total inventory availability
-    let inventory = inventory::Entity::find_by_id(&payload.chick_item_code[0])
-        .one(txn)
-        .await
-        .map_err(|e| {
-            format!(
-                "Failed to fetch inventory for {}: {}",
-                payload.chick_item_code[0], e
-            )
-        })?
-        .ok_or_else(|| format!("No inventory found for item {}", payload.chick_item_code[0]))?;
-
-    if inventory.current_qty.to_i32().unwrap_or(0) < payload.initial_bird_count {
-        return Err(format!(
-            "Insufficient stock for {}. Required: {}, Available: {}",
-            payload.chick_item_code[0], payload.initial_bird_count, inventory.current_qty
-        ));
+        let inventory = inventory::Entity::find_by_id(&line.item_code)
+            .one(txn)
+            .await?;
+        let available = inventory
+            .as_ref()
+            .map(|inv| inv.current_qty)
+            .unwrap_or_default();
+        if available < Decimal::from(line.quantity) {
+            return Err(BatchCreateError::InsufficientStock {
+                item_code: line.item_code.clone(),
+                required: line.quantity,
+                available,
+            });
+        }
+
+        stock.push(inventory);
     }
 
-    // 3. Create the batch
+    // 2. Create the batch with the total placed; each item's birds are
+    //    logged below
     let new_batch = batches::ActiveModel {
         line_id: Set(payload.line_id),
         supervisor_id: Set(payload.supervisor_id),
@@ -93,230 +210,333 @@ async fn create_batch_with_transaction(
         end_date: Set(payload.end_date),
         initial_bird_count: Set(payload.initial_bird_count),
         current_bird_count: Set(Some(payload.initial_bird_count)),
+        breed: Set(payload.breed.clone()),
+        // Batches dated ahead are planned until an admin opens them
+        status: Set(if payload.start_date > today {
+            BatchStatus::Planned
+        } else {
+            BatchStatus::Open
+        }),
         ..Default::default()
     };
 
-    let batch_model = new_batch
+    let batch_model = new_batch.insert(txn).await?;
+
+    // For chicks: inventory-chicks -> farm-expense, as configured in posting_rules
+    let rule =
+        resolve_posting_rule(txn, PostingEvent::Allocation, Some(ItemCategory::Chicks)).await?;
+    let mut journal_lines = Vec::with_capacity(lines.len() * 2);
+
+    for (line, inventory) in lines.iter().zip(stock) {
+        let quantity = Decimal::from(line.quantity);
+
+        // 3. One accepted stocking requirement and allocation per item
+        let requirement_model = batch_requirements::ActiveModel {
+            requirement_id: Default::default(),
+            batch_id: Set(batch_model.batch_id),
+            line_id: Set(payload.line_id),
+            supervisor_id: Set(payload.supervisor_id),
+            item_code: Set(line.item_code.clone()),
+            quantity: Set(quantity),
+            status: Set(RequirementStatus::Accept),
+            request_date: Set(today),
+            is_stocking: Set(true),
+        }
         .insert(txn)
-        .await
-        .map_err(|e| format!("Failed to create batch: {}", e))?;
+        .await?;
 
-    let requirement = batch_requirements::ActiveModel {
-        requirement_id: Default::default(),
-        batch_id: Set(batch_model.batch_id),
-        line_id: Set(payload.line_id),
-        supervisor_id: Set(payload.supervisor_id),
-        item_code: Set(payload.chick_item_code[0].clone()),
-        quantity: Set(Decimal::from(payload.initial_bird_count)),
-        status: Set(RequirementStatus::Accept),
-        request_date: Set(Utc::now().date_naive()),
-    };
+        let allocation_model = batch_allocations::ActiveModel {
+            allocation_id: Default::default(),
+            requirement_id: Set(Some(requirement_model.requirement_id)),
+            allocated_qty: Set(quantity),
+            allocation_date: Set(today),
+            allocated_value: Set(Decimal::ZERO), // Will be updated once the lots are drawn
+            allocated_by: Set(payload.created_by),
 
-    let requirement_model = requirement
+            ..Default::default()
+        }
         .insert(txn)
-        .await
-        .map_err(|e| format!("Failed to create batch requirement: {}", e))?;
-
-    // 4. Create allocation record for the batch
-    let allocation = batch_allocations::ActiveModel {
-        allocation_id: Default::default(),
-        requirement_id: Set(Some(requirement_model.requirement_id)),
-        allocated_qty: Set(payload.initial_bird_count.into()),
-        allocation_date: Set(Utc::now().date_naive()),
-        allocated_value: Set(Decimal::ZERO), // Will be updated after FIFO allocation
-        allocated_by: Set(payload.created_by),
-    };
+        .await?;
+
+        // 4. Update inventory (deduct allocated qty)
+        if let Some(inventory) = inventory {
+            let mut active_inv: inventory::ActiveModel = inventory.into();
+            let current = active_inv.current_qty.take().unwrap_or_default();
+            active_inv.current_qty = Set(current - quantity);
+            active_inv.last_updated = Set(Utc::now().into());
+            active_inv.update(txn).await?;
+        }
 
-    let allocation_model = allocation
+        // 5. Insert inventory movement (OUT)
+        inventory_movements::ActiveModel {
+            movement_id: Default::default(),
+            item_code: Set(line.item_code.clone()),
+            movement_type: Set(MovementType::Allocation),
+            qty_change: Set(-quantity),
+            reference_id: Set(Some(batch_model.batch_id)),
+            movement_date: Set(Utc::now().into()),
+            location_id: Set(Some(farm.location_id)),
+        }
         .insert(txn)
-        .await
-        .map_err(|e| format!("Failed to create allocation: {}", e))?;
-
-    // 5. Update inventory (deduct allocated qty)
-    let mut active_inv: inventory::ActiveModel = inventory.into();
-    let current = active_inv.current_qty.take().unwrap_or_default();
-    active_inv.current_qty = Set(current - Decimal::from(payload.initial_bird_count));
-    active_inv.last_updated = Set(Utc::now().into());
-
-    active_inv
-        .update(txn)
-        .await
-        .map_err(|e| format!("Failed to update inventory: {}", e))?;
-
-    // 6. Insert inventory movement (OUT)
-    let movement = inventory_movements::ActiveModel {
-        movement_id: Default::default(),
-        item_code: Set(payload.chick_item_code[0].clone()),
-        movement_type: Set(MovementType::Allocation),
-        qty_change: Set(Decimal::from(payload.initial_bird_count) * Decimal::from(-1)),
-        reference_id: Set(Some(batch_model.batch_id)),
-        movement_date: Set(Utc::now().into()),
-    };
+        .await?;
+
+        // 6. Draw the lots down -> batch_allocation_lines
+        let (total_value, shortage) = allocate_from_lots(
+            txn,
+            allocation_model.allocation_id,
+            &line.item_code,
+            source.location_id,
+            quantity,
+            today,
+        )
+        .await?;
+        let allocation_id = allocation_model.allocation_id;
+
+        // Update allocation with total monetary worth
+        let mut alloc_update: batch_allocations::ActiveModel = allocation_model.into();
+        alloc_update.allocated_value = Set(total_value);
+        alloc_update.update(txn).await?;
+
+        // Inventory and lots disagree (like in approve_and_allocate)
+        if shortage > Decimal::ZERO {
+            return Err(BatchCreateError::InsufficientStock {
+                item_code: line.item_code.clone(),
+                required: line.quantity,
+                available: quantity - shortage,
+            });
+        }
 
-    movement
+        issue_to_farm(
+            txn,
+            TransferRequest {
+                item_code: &line.item_code,
+                from_location_id: source.location_id,
+                to_location_id: farm.location_id,
+                quantity,
+                value: total_value,
+                transfer_date: today,
+                allocation_id: Some(allocation_id),
+                notes: Some(format!("Chicks placed in batch {}", batch_model.batch_id)),
+                created_by: Some(payload.created_by),
+            },
+        )
+        .await?;
+
+        // 7. Record the birds placed from this item. The history row is an
+        //    addition already counted in initial_bird_count.
+        bird_count_history::ActiveModel {
+            batch_id: Set(batch_model.batch_id),
+            record_date: Set(payload.start_date),
+            deaths: Set(0),
+            additions: Set(line.quantity),
+            notes: Set(format!(
+                "{} birds placed from {}",
+                line.quantity, line.item_code
+            )),
+            created_at: Set(Utc::now().into()),
+            is_stocking: Set(true),
+            ..Default::default()
+        }
         .insert(txn)
-        .await
-        .map_err(|e| format!("Failed to insert inventory movement: {}", e))?;
-
-    // 7. FIFO allocation from stock_receipts -> batch_allocation_lines
-    let mut qty_to_allocate = payload.initial_bird_count;
-    let mut total_value = Decimal::ZERO;
-
-    // Fetch lots in FIFO order (oldest first)
-    let receipts = stock_receipts::Entity::find()
-        .filter(stock_receipts::Column::ItemCode.eq(&payload.chick_item_code[0]))
-        .filter(stock_receipts::Column::RemainingQty.gt(Decimal::ZERO))
-        .order_by_asc(stock_receipts::Column::ReceivedDate)
-        .order_by_asc(stock_receipts::Column::LotId)
-        .all(txn)
-        .await
-        .map_err(|e| format!("Failed to fetch stock receipts: {}", e))?;
-
-    for receipt in receipts {
-        if qty_to_allocate <= 0 {
-            break;
+        .await?;
+
+        batch_placements::ActiveModel {
+            batch_id: Set(batch_model.batch_id),
+            item_code: Set(line.item_code.clone()),
+            bird_count: Set(line.quantity),
+            allocation_id: Set(Some(allocation_id)),
+            created_at: Set(Utc::now().into()),
+            ..Default::default()
         }
+        .insert(txn)
+        .await?;
+
+        journal_lines.push(JournalLine {
+            account_id: rule.debit_account_id,
+            debit: Some(total_value),
+            credit: None,
+            narration: Some(format!(
+                "Chick expense for batch {} - Item: {}",
+                batch_model.batch_id, line.item_code
+            )),
+        });
+        journal_lines.push(JournalLine {
+            account_id: rule.credit_account_id,
+            debit: None,
+            credit: Some(total_value),
+            narration: Some(format!(
+                "Chick allocation for batch {} - Item: {}",
+                batch_model.batch_id, line.item_code
+            )),
+        });
+    }
+
+    // 8. Post the stocking as one transaction against the batch. Lots at zero
+    //    cost leave nothing to post.
+    journal_lines.retain(|l| l.debit.or(l.credit).unwrap_or_default() > Decimal::ZERO);
+    if !journal_lines.is_empty() {
+        post_journal(
+            txn,
+            &CreateJournalVoucher {
+                txn_date: today,
+                narration: None,
+                reference_table: Some("batches".into()),
+                reference_id: Some(batch_model.batch_id),
+                created_by: Some(payload.created_by),
+                lines: journal_lines,
+            },
+        )
+        .await?;
+    }
 
-        let take: Decimal = std::cmp::min(receipt.remaining_qty, Decimal::from(qty_to_allocate));
-        let line_value = take * receipt.unit_cost;
+    // 9. Schedule the batch's vaccinations and medication
+    apply_health_program(txn, &batch_model, payload.health_program_id).await?;
 
-        // Insert allocation line
-        let line = batch_allocation_lines::ActiveModel {
+    Ok(batch_model)
+}
+
+/// Take `quantity` of an item from its lots at a location by the category's
+/// costing method, writing one allocation line per lot. Lots expired by
+/// `issued_on` are left alone. Returns the value taken and any quantity the
+/// lots could not cover.
+async fn allocate_from_lots(
+    txn: &DatabaseTransaction,
+    allocation_id: i32,
+    item_code: &str,
+    location_id: i32,
+    quantity: Decimal,
+    issued_on: NaiveDate,
+) -> Result<(Decimal, Decimal), DbErr> {
+    let issue = draw_lots(
+        txn,
+        item_code,
+        &ItemCategory::Chicks,
+        location_id,
+        quantity,
+        Some(issued_on),
+    )
+    .await?;
+
+    for draw in &issue.draws {
+        batch_allocation_lines::ActiveModel {
             allocation_line_id: Default::default(),
-            allocation_id: Set(allocation_model.allocation_id),
-            lot_id: Set(receipt.lot_id),
-            qty: Set(take),
-            unit_cost: Set(receipt.unit_cost),
-            line_value: Set(line_value),
-        };
-        line.insert(txn)
-            .await
-            .map_err(|e| format!("Failed to insert allocation line: {}", e))?;
-
-        // Update lot remaining qty
-        let mut receipt_active: stock_receipts::ActiveModel = receipt.into();
-        receipt_active.remaining_qty = Set(receipt_active.remaining_qty.take().unwrap() - take);
-        receipt_active
-            .update(txn)
-            .await
-            .map_err(|e| format!("Failed to update stock receipt: {}", e))?;
-
-        total_value += line_value;
-        qty_to_allocate -= take.to_i32().unwrap_or(0);
+            allocation_id: Set(allocation_id),
+            lot_id: Set(draw.lot_id),
+            qty: Set(draw.qty),
+            unit_cost: Set(draw.unit_cost),
+            line_value: Set(draw.value),
+        }
+        .insert(txn)
+        .await?;
     }
 
-    // Update allocation with total monetary worth
-    let mut alloc_update: batch_allocations::ActiveModel = allocation_model.clone().into();
-    alloc_update.allocated_value = Set(total_value);
-    alloc_update
-        .update(txn)
-        .await
-        .map_err(|e| format!("Failed to update allocation value: {}", e))?;
-
-    // Check if we have shortage (like in approve_and_allocate)
-    if qty_to_allocate > 0 {
-        return Err(format!(
-            "Partial allocation: shortage of {} units for item {}",
-            qty_to_allocate, payload.chick_item_code[0]
-        ));
+    Ok((issue.total_value, issue.shortage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(
+        initial_bird_count: i32,
+        chick_item_code: &[&str],
+        chick_items: &[(&str, i32)],
+    ) -> CreateBatch {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        CreateBatch {
+            line_id: 1,
+            supervisor_id: 1,
+            farmer_id: 1,
+            start_date: date,
+            end_date: date,
+            initial_bird_count,
+            chick_item_code: chick_item_code.iter().map(|c| c.to_string()).collect(),
+            chick_items: chick_items
+                .iter()
+                .map(|&(item_code, quantity)| ChickStocking {
+                    item_code: item_code.to_string(),
+                    quantity,
+                })
+                .collect(),
+            created_by: 1,
+            breed: None,
+            health_program_id: None,
+            location_id: None,
+        }
     }
 
-    // 8. Create ledger entries
-    let txn_group_id = Uuid::new_v4();
-
-    // For chicks: inventory-chicks (104) -> farm-expense (107)
-    let asset_account_id = 104i32; // inventory-chicks
-    let expense_account_id = 107i32; // farm-expense
-
-    // Credit entry (reduce asset)
-    let credit_entry = ledger_entries::ActiveModel {
-        entry_id: Default::default(),
-        account_id: Set(asset_account_id),
-        debit: Set(None),
-        credit: Set(Some(total_value)),
-        txn_date: Set(Utc::now().date_naive()),
-        reference_table: Set(Some("batches".into())),
-        reference_id: Set(Some(batch_model.batch_id)),
-        narration: Set(Some(format!(
-            "Chick allocation for batch {} - Item: {}",
-            batch_model.batch_id, payload.chick_item_code[0]
-        ))),
-        txn_group_id: Set(txn_group_id),
-        created_by: Set(Some(payload.created_by)),
-        created_at: Set(Utc::now().into()),
-        ..Default::default()
-    };
+    fn split(lines: &[ChickStocking]) -> Vec<(&str, i32)> {
+        lines
+            .iter()
+            .map(|l| (l.item_code.as_str(), l.quantity))
+            .collect()
+    }
 
-    credit_entry
-        .insert(txn)
-        .await
-        .map_err(|e| format!("Failed to insert credit entry: {}", e))?;
-
-    // Debit entry (increase expense)
-    let debit_entry = ledger_entries::ActiveModel {
-        entry_id: Default::default(),
-        account_id: Set(expense_account_id),
-        debit: Set(Some(total_value)),
-        credit: Set(None),
-        txn_date: Set(Utc::now().date_naive()),
-        reference_table: Set(Some("batches".into())),
-        reference_id: Set(Some(batch_model.batch_id)),
-        narration: Set(Some(format!(
-            "Chick expense for batch {} - Item: {}",
-            batch_model.batch_id, payload.chick_item_code[0]
-        ))),
-        txn_group_id: Set(txn_group_id),
-        created_by: Set(Some(payload.created_by)),
-        created_at: Set(Utc::now().into()),
-        ..Default::default()
-    };
+    #[test]
+    fn single_item_code_takes_the_whole_count() {
+        let lines = stocking_lines(&payload(1000, &["CHICK-A"], &[])).unwrap();
+        assert_eq!(split(&lines), vec![("CHICK-A", 1000)]);
+    }
 
-    debit_entry
-        .insert(txn)
-        .await
-        .map_err(|e| format!("Failed to insert debit entry: {}", e))?;
-
-    // 9. Update ledger account balances
-    // Update asset account (decrease balance)
-    if let Some(asset_account) = ledger_accounts::Entity::find_by_id(asset_account_id)
-        .one(txn)
-        .await
-        .map_err(|e| format!("Failed to fetch asset account {}: {}", asset_account_id, e))?
-    {
-        let mut asset_active: ledger_accounts::ActiveModel = asset_account.into();
-        let current_balance = asset_active.current_balance.take().unwrap_or_default();
-        asset_active.current_balance = Set(current_balance - total_value);
-
-        asset_active.update(txn).await.map_err(|e| {
-            format!(
-                "Failed to update asset account {} balance: {}",
-                asset_account_id, e
-            )
-        })?;
+    #[test]
+    fn several_items_adding_up_to_the_count() {
+        let lines =
+            stocking_lines(&payload(1000, &[], &[("CHICK-A", 600), ("CHICK-B", 400)])).unwrap();
+        assert_eq!(split(&lines), vec![("CHICK-A", 600), ("CHICK-B", 400)]);
     }
 
-    // Update expense account (increase balance)
-    if let Some(expense_account) = ledger_accounts::Entity::find_by_id(expense_account_id)
-        .one(txn)
-        .await
-        .map_err(|e| {
-            format!(
-                "Failed to fetch expense account {}: {}",
-                expense_account_id, e
-            )
-        })?
-    {
-        let mut expense_active: ledger_accounts::ActiveModel = expense_account.into();
-        let current_balance = expense_active.current_balance.take().unwrap_or_default();
-        expense_active.current_balance = Set(current_balance + total_value);
-
-        expense_active.update(txn).await.map_err(|e| {
-            format!(
-                "Failed to update expense account {} balance: {}",
-                expense_account_id, e
-            )
-        })?;
+    #[test]
+    fn items_must_be_given_one_way() {
+        assert!(matches!(
+            stocking_lines(&payload(100, &["CHICK-A"], &[("CHICK-A", 100)])),
+            Err(BatchCreateError::AmbiguousItems)
+        ));
+        assert!(matches!(
+            stocking_lines(&payload(100, &[], &[])),
+            Err(BatchCreateError::NoChickItems)
+        ));
+        assert!(matches!(
+            stocking_lines(&payload(100, &["CHICK-A", "CHICK-B"], &[])),
+            Err(BatchCreateError::MissingQuantities)
+        ));
     }
 
-    Ok(batch_model)
+    #[test]
+    fn duplicate_and_non_positive_items_are_refused() {
+        assert!(matches!(
+            stocking_lines(&payload(100, &[], &[("CHICK-A", 50), ("CHICK-A", 50)])),
+            Err(BatchCreateError::DuplicateItem(code)) if code == "CHICK-A"
+        ));
+        assert!(matches!(
+            stocking_lines(&payload(100, &[], &[("CHICK-A", 100), ("CHICK-B", 0)])),
+            Err(BatchCreateError::InvalidQuantity(code)) if code == "CHICK-B"
+        ));
+        assert!(matches!(
+            stocking_lines(&payload(0, &["CHICK-A"], &[])),
+            Err(BatchCreateError::InvalidQuantity(code)) if code == "CHICK-A"
+        ));
+    }
+
+    #[test]
+    fn item_total_past_the_integer_range_is_refused() {
+        assert!(matches!(
+            stocking_lines(&payload(
+                i32::MAX,
+                &[],
+                &[("CHICK-A", i32::MAX), ("CHICK-B", 1)]
+            )),
+            Err(BatchCreateError::CountOverflow)
+        ));
+    }
+
+    #[test]
+    fn items_must_add_up_to_the_count() {
+        assert!(matches!(
+            stocking_lines(&payload(1000, &[], &[("CHICK-A", 600), ("CHICK-B", 300)])),
+            Err(BatchCreateError::CountMismatch {
+                expected: 1000,
+                total: 900
+            })
+        ));
+    }
 }
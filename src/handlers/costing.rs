@@ -0,0 +1,390 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{NaiveDate, Utc};
+use entity::category_costing_methods;
+use entity::sea_orm_active_enums::{CostingMethod, ItemCategory};
+use entity::stock_receipts;
+use sea_orm::prelude::Decimal;
+use sea_orm::sea_query::{NullOrdering, OnConflict};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, Order,
+    QueryFilter, QueryOrder, Set,
+};
+
+use crate::handlers::purchases::internal_error;
+use crate::models::UpdateCostingMethod;
+
+/// What a lot holds, as far as costing is concerned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LotBalance {
+    pub lot_id: i32,
+    pub remaining_qty: Decimal,
+    pub unit_cost: Decimal,
+}
+
+/// Quantity taken from one lot, valued at the lot's cost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LotDraw {
+    pub lot_id: i32,
+    pub qty: Decimal,
+    pub unit_cost: Decimal,
+    pub value: Decimal,
+}
+
+/// The lots an issue draws on, its cost, and whatever the lots could not
+/// cover.
+#[derive(Debug, Default, PartialEq)]
+pub struct CostedIssue {
+    pub draws: Vec<LotDraw>,
+    pub total_value: Decimal,
+    pub shortage: Decimal,
+    /// Held in lots past their expiry, which were left alone
+    pub expired_qty: Decimal,
+}
+
+impl CostedIssue {
+    fn from_takes<'a>(
+        takes: impl IntoIterator<Item = (&'a LotBalance, Decimal)>,
+        shortage: Decimal,
+    ) -> Self {
+        let draws: Vec<LotDraw> = takes
+            .into_iter()
+            .filter(|(_, qty)| *qty > Decimal::ZERO)
+            .map(|(lot, qty)| LotDraw {
+                lot_id: lot.lot_id,
+                qty,
+                unit_cost: lot.unit_cost,
+                value: qty * lot.unit_cost,
+            })
+            .collect();
+        CostedIssue {
+            total_value: draws.iter().map(|d| d.value).sum(),
+            draws,
+            shortage,
+            ..Default::default()
+        }
+    }
+}
+
+/// Decides which lots an issue draws down and what it costs. `lots` come
+/// in the order they should be used; nothing is written, the caller applies
+/// the draws.
+pub trait CostingStrategy: Send + Sync {
+    fn draw(&self, lots: &[LotBalance], quantity: Decimal) -> CostedIssue;
+}
+
+/// First in, first out: empty the lots in the order given, each at its own
+/// cost.
+pub struct Fifo;
+
+impl CostingStrategy for Fifo {
+    fn draw(&self, lots: &[LotBalance], quantity: Decimal) -> CostedIssue {
+        let mut left = quantity;
+        let mut takes = Vec::new();
+        for lot in lots {
+            if left <= Decimal::ZERO {
+                break;
+            }
+            if lot.remaining_qty <= Decimal::ZERO {
+                continue;
+            }
+            let take = std::cmp::min(lot.remaining_qty, left);
+            takes.push((lot, take));
+            left -= take;
+        }
+
+        CostedIssue::from_takes(takes, left.max(Decimal::ZERO))
+    }
+}
+
+/// Moving weighted average: the issue is spread over the lots in proportion
+/// to what each holds, so it costs the average of the stock on hand and the
+/// lots left behind keep that same average for the next issue.
+pub struct WeightedAverage;
+
+impl CostingStrategy for WeightedAverage {
+    fn draw(&self, lots: &[LotBalance], quantity: Decimal) -> CostedIssue {
+        let held: Vec<&LotBalance> = lots
+            .iter()
+            .filter(|lot| lot.remaining_qty > Decimal::ZERO)
+            .collect();
+        let available: Decimal = held.iter().map(|lot| lot.remaining_qty).sum();
+        if quantity >= available {
+            let takes = held.into_iter().map(|lot| (lot, lot.remaining_qty));
+            return CostedIssue::from_takes(takes, quantity - available);
+        }
+
+        // Shares are rounded down to the quantity columns' two places; the
+        // odd hundredths left over come out of the oldest lots with room
+        let mut takes: Vec<(&LotBalance, Decimal)> = held
+            .into_iter()
+            .map(|lot| {
+                let share = (lot.remaining_qty * quantity * Decimal::ONE_HUNDRED / available)
+                    .floor()
+                    / Decimal::ONE_HUNDRED;
+                (lot, share.min(lot.remaining_qty))
+            })
+            .collect();
+        let mut left = quantity - takes.iter().map(|(_, take)| *take).sum::<Decimal>();
+        for (lot, take) in takes.iter_mut() {
+            if left <= Decimal::ZERO {
+                break;
+            }
+            let extra = std::cmp::min(lot.remaining_qty - *take, left);
+            *take += extra;
+            left -= extra;
+        }
+
+        CostedIssue::from_takes(takes, Decimal::ZERO)
+    }
+}
+
+pub fn costing_strategy(method: &CostingMethod) -> &'static dyn CostingStrategy {
+    match method {
+        CostingMethod::Fifo => &Fifo,
+        CostingMethod::WeightedAverage => &WeightedAverage,
+    }
+}
+
+/// The costing method configured for a category; FIFO when none is set.
+pub async fn costing_method<C: ConnectionTrait>(
+    conn: &C,
+    item_category: &ItemCategory,
+) -> Result<CostingMethod, DbErr> {
+    Ok(
+        category_costing_methods::Entity::find_by_id(item_category.clone())
+            .one(conn)
+            .await?
+            .map(|m| m.costing_method)
+            .unwrap_or(CostingMethod::Fifo),
+    )
+}
+
+/// An item's lots holding stock at a location, in the order they are issued:
+/// medicines first-expiry-first-out, everything else oldest receipt first.
+/// With `usable_on` set, lots expired before that date come back separately
+/// as the second list.
+pub async fn issue_order_lots<C: ConnectionTrait>(
+    conn: &C,
+    item_code: &str,
+    item_category: &ItemCategory,
+    location_id: i32,
+    usable_on: Option<NaiveDate>,
+) -> Result<(Vec<stock_receipts::Model>, Vec<stock_receipts::Model>), DbErr> {
+    let mut query = stock_receipts::Entity::find()
+        .filter(stock_receipts::Column::ItemCode.eq(item_code))
+        .filter(stock_receipts::Column::LocationId.eq(location_id))
+        .filter(stock_receipts::Column::RemainingQty.gt(Decimal::ZERO));
+    if *item_category == ItemCategory::Medicine {
+        query = query.order_by_with_nulls(
+            stock_receipts::Column::ExpiryDate,
+            Order::Asc,
+            NullOrdering::Last,
+        );
+    }
+    Ok(query
+        .order_by_asc(stock_receipts::Column::ReceivedDate)
+        .order_by_asc(stock_receipts::Column::LotId)
+        .all(conn)
+        .await?
+        .into_iter()
+        .partition(|r| match (usable_on, r.expiry_date) {
+            (Some(date), Some(expiry)) => expiry >= date,
+            _ => true,
+        }))
+}
+
+/// Draw `quantity` of an item from its lots at a location using the
+/// category's costing method, bringing each lot's `remaining_qty` down.
+/// Lots are taken in [`issue_order_lots`] order; with `usable_on` set,
+/// expired lots are kept back and counted in `expired_qty`. A shortage is
+/// reported, not refused; callers decide what it means.
+pub async fn draw_lots<C: ConnectionTrait>(
+    conn: &C,
+    item_code: &str,
+    item_category: &ItemCategory,
+    location_id: i32,
+    quantity: Decimal,
+    usable_on: Option<NaiveDate>,
+) -> Result<CostedIssue, DbErr> {
+    let (receipts, expired) =
+        issue_order_lots(conn, item_code, item_category, location_id, usable_on).await?;
+
+    let balances: Vec<LotBalance> = receipts
+        .iter()
+        .map(|r| LotBalance {
+            lot_id: r.lot_id,
+            remaining_qty: r.remaining_qty,
+            unit_cost: r.unit_cost,
+        })
+        .collect();
+    let method = costing_method(conn, item_category).await?;
+    let mut issue = costing_strategy(&method).draw(&balances, quantity);
+    issue.expired_qty = expired.iter().map(|r| r.remaining_qty).sum();
+
+    for draw in &issue.draws {
+        if let Some(receipt) = receipts.iter().find(|r| r.lot_id == draw.lot_id) {
+            let remaining = receipt.remaining_qty - draw.qty;
+            let mut active: stock_receipts::ActiveModel = receipt.clone().into();
+            active.remaining_qty = Set(remaining);
+            active.update(conn).await?;
+        }
+    }
+
+    Ok(issue)
+}
+
+pub async fn get_costing_methods_handler(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<Vec<category_costing_methods::Model>>, StatusCode> {
+    category_costing_methods::Entity::find()
+        .order_by_asc(category_costing_methods::Column::ItemCategory)
+        .all(&db)
+        .await
+        .map(Json)
+        .map_err(internal_error("fetch costing methods"))
+}
+
+/// Switching method applies to issues from now on; what lots already hold
+/// is not revalued.
+pub async fn update_costing_method(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<UpdateCostingMethod>,
+) -> Result<Json<category_costing_methods::Model>, StatusCode> {
+    category_costing_methods::Entity::insert(category_costing_methods::ActiveModel {
+        item_category: Set(payload.item_category.clone()),
+        costing_method: Set(payload.costing_method),
+        updated_at: Set(Utc::now().into()),
+    })
+    .on_conflict(
+        OnConflict::column(category_costing_methods::Column::ItemCategory)
+            .update_columns([
+                category_costing_methods::Column::CostingMethod,
+                category_costing_methods::Column::UpdatedAt,
+            ])
+            .to_owned(),
+    )
+    .exec_without_returning(&db)
+    .await
+    .map_err(internal_error("update costing method"))?;
+
+    category_costing_methods::Entity::find_by_id(payload.item_category)
+        .one(&db)
+        .await
+        .map_err(internal_error("fetch costing method"))?
+        .map(Json)
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lot(lot_id: i32, remaining_qty: i64, unit_cost: i64) -> LotBalance {
+        LotBalance {
+            lot_id,
+            remaining_qty: Decimal::from(remaining_qty),
+            unit_cost: Decimal::from(unit_cost),
+        }
+    }
+
+    fn taken(issue: &CostedIssue) -> Vec<(i32, Decimal)> {
+        issue.draws.iter().map(|d| (d.lot_id, d.qty)).collect()
+    }
+
+    #[test]
+    fn fifo_takes_part_of_a_lot() {
+        let lots = [lot(1, 10, 5), lot(2, 10, 7)];
+        let issue = Fifo.draw(&lots, Decimal::from(14));
+
+        assert_eq!(
+            taken(&issue),
+            vec![(1, Decimal::from(10)), (2, Decimal::from(4))]
+        );
+        assert_eq!(issue.total_value, Decimal::from(10 * 5 + 4 * 7));
+        assert_eq!(issue.shortage, Decimal::ZERO);
+    }
+
+    #[test]
+    fn fifo_empties_lots_exactly() {
+        let lots = [lot(1, 10, 5), lot(2, 10, 7)];
+        let issue = Fifo.draw(&lots, Decimal::from(20));
+
+        assert_eq!(
+            taken(&issue),
+            vec![(1, Decimal::from(10)), (2, Decimal::from(10))]
+        );
+        assert_eq!(issue.total_value, Decimal::from(120));
+        assert_eq!(issue.shortage, Decimal::ZERO);
+    }
+
+    #[test]
+    fn fifo_reports_shortage() {
+        let lots = [lot(1, 10, 5), lot(2, 0, 9)];
+        let issue = Fifo.draw(&lots, Decimal::from(25));
+
+        assert_eq!(taken(&issue), vec![(1, Decimal::from(10))]);
+        assert_eq!(issue.total_value, Decimal::from(50));
+        assert_eq!(issue.shortage, Decimal::from(15));
+    }
+
+    #[test]
+    fn weighted_average_spreads_over_lots() {
+        let lots = [lot(1, 30, 4), lot(2, 10, 8)];
+        let issue = WeightedAverage.draw(&lots, Decimal::from(20));
+
+        assert_eq!(
+            taken(&issue),
+            vec![(1, Decimal::from(15)), (2, Decimal::from(5))]
+        );
+        // Average cost on hand is (30 * 4 + 10 * 8) / 40 = 5
+        assert_eq!(issue.total_value, Decimal::from(20 * 5));
+        assert_eq!(issue.shortage, Decimal::ZERO);
+    }
+
+    #[test]
+    fn weighted_average_rounding_still_takes_the_full_quantity() {
+        let lots = [lot(1, 1, 3), lot(2, 1, 3), lot(3, 1, 3)];
+        let issue = WeightedAverage.draw(&lots, Decimal::from(1));
+
+        let qty: Decimal = issue.draws.iter().map(|d| d.qty).sum();
+        assert_eq!(qty, Decimal::ONE);
+        assert_eq!(issue.draws[0].qty, Decimal::new(34, 2));
+        assert_eq!(issue.total_value, Decimal::from(3));
+        assert_eq!(issue.shortage, Decimal::ZERO);
+    }
+
+    #[test]
+    fn weighted_average_empties_lots_exactly() {
+        let lots = [lot(1, 30, 4), lot(2, 10, 8)];
+        let issue = WeightedAverage.draw(&lots, Decimal::from(40));
+
+        assert_eq!(
+            taken(&issue),
+            vec![(1, Decimal::from(30)), (2, Decimal::from(10))]
+        );
+        assert_eq!(issue.total_value, Decimal::from(200));
+        assert_eq!(issue.shortage, Decimal::ZERO);
+    }
+
+    #[test]
+    fn weighted_average_reports_shortage() {
+        let lots = [lot(1, 30, 4), lot(2, 10, 8)];
+        let issue = WeightedAverage.draw(&lots, Decimal::from(50));
+
+        assert_eq!(
+            taken(&issue),
+            vec![(1, Decimal::from(30)), (2, Decimal::from(10))]
+        );
+        assert_eq!(issue.shortage, Decimal::from(10));
+    }
+
+    #[test]
+    fn nothing_on_hand_is_all_shortage() {
+        for method in [CostingMethod::Fifo, CostingMethod::WeightedAverage] {
+            let issue = costing_strategy(&method).draw(&[], Decimal::from(5));
+            assert!(issue.draws.is_empty());
+            assert_eq!(issue.total_value, Decimal::ZERO);
+            assert_eq!(issue.shortage, Decimal::from(5));
+        }
+    }
+}
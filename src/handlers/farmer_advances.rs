@@ -0,0 +1,397 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Datelike, NaiveDate, Utc};
+use entity::sea_orm_active_enums::{FarmerAdvanceType, PostingEvent};
+use entity::{farmer_advances, farmer_commission_history, farmer_settlements, farmers};
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, Set, TransactionTrait,
+};
+use thiserror::Error;
+
+use crate::handlers::batch_lifecycle::{ensure_batch_allows, BatchOperation, BatchStateError};
+use crate::handlers::journal::{post_journal, JournalError};
+use crate::handlers::periods::{ensure_period_open, PeriodError};
+use crate::handlers::posting_rules::{resolve_posting_rule, PostingRuleError};
+use crate::handlers::purchases::internal_error;
+use crate::handlers::reports::{csv_response, csv_row};
+use crate::models::{
+    CreateFarmerAdvance, CreateJournalVoucher, FarmerStatementLine, FarmerStatementResponse,
+    JournalLine, PeriodQuery, ReportFormat, ResponseMessage,
+};
+
+#[derive(Debug, Error)]
+pub enum FarmerAdvanceError {
+    #[error("Farmer {0} not found")]
+    FarmerNotFound(i32),
+    #[error("Batch {batch_id} does not belong to farmer {farmer_id}")]
+    WrongFarmer { batch_id: i32, farmer_id: i32 },
+    #[error("Amount must be positive")]
+    InvalidAmount,
+    #[error(transparent)]
+    Lifecycle(#[from] BatchStateError),
+    #[error(transparent)]
+    Period(#[from] PeriodError),
+    #[error(transparent)]
+    PostingRule(#[from] PostingRuleError),
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl FarmerAdvanceError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            FarmerAdvanceError::FarmerNotFound(_) => StatusCode::NOT_FOUND,
+            FarmerAdvanceError::WrongFarmer { .. } | FarmerAdvanceError::InvalidAmount => {
+                StatusCode::BAD_REQUEST
+            }
+            FarmerAdvanceError::Lifecycle(e) => e.status(),
+            FarmerAdvanceError::Period(e) => e.status(),
+            FarmerAdvanceError::Journal(e) => e.status(),
+            FarmerAdvanceError::PostingRule(_) | FarmerAdvanceError::Db(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+/// What the farmer still owes back: advances and deductions not yet recovered
+/// from a settlement.
+pub async fn outstanding_advances<C: ConnectionTrait>(
+    conn: &C,
+    farmer_id: i32,
+) -> Result<Decimal, DbErr> {
+    let advanced: Decimal = farmer_advances::Entity::find()
+        .filter(farmer_advances::Column::FarmerId.eq(farmer_id))
+        .filter(farmer_advances::Column::VoidedAt.is_null())
+        .all(conn)
+        .await?
+        .iter()
+        .map(|a| a.amount)
+        .sum();
+    let recovered: Decimal = farmer_settlements::Entity::find()
+        .filter(farmer_settlements::Column::FarmerId.eq(farmer_id))
+        .all(conn)
+        .await?
+        .iter()
+        .map(|s| s.advances_deducted)
+        .sum();
+
+    Ok(advanced - recovered)
+}
+
+pub async fn create_farmer_advance(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateFarmerAdvance>,
+) -> Result<Json<farmer_advances::Model>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match record_farmer_advance(&txn, payload).await {
+            Ok(advance) => txn
+                .commit()
+                .await
+                .map(|_| advance)
+                .map_err(FarmerAdvanceError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(FarmerAdvanceError::from(e)),
+    };
+
+    result.map(Json).map_err(|e| {
+        eprintln!("Failed to record farmer advance: {}", e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    })
+}
+
+/// Record an advance or deduction and debit it to the farmer-advances
+/// account: advances against cash, deductions against recoveries income.
+async fn record_farmer_advance<C: ConnectionTrait>(
+    conn: &C,
+    payload: CreateFarmerAdvance,
+) -> Result<farmer_advances::Model, FarmerAdvanceError> {
+    if payload.amount <= Decimal::ZERO {
+        return Err(FarmerAdvanceError::InvalidAmount);
+    }
+
+    let farmer = farmers::Entity::find_by_id(payload.farmer_id)
+        .one(conn)
+        .await?
+        .ok_or(FarmerAdvanceError::FarmerNotFound(payload.farmer_id))?;
+
+    if let Some(batch_id) = payload.batch_id {
+        let batch = ensure_batch_allows(conn, batch_id, BatchOperation::RecordAdvance).await?;
+        if batch.farmer_id != farmer.farmer_id {
+            return Err(FarmerAdvanceError::WrongFarmer {
+                batch_id,
+                farmer_id: farmer.farmer_id,
+            });
+        }
+    }
+
+    let advance_date = payload
+        .advance_date
+        .unwrap_or_else(|| Utc::now().date_naive());
+    ensure_period_open(conn, advance_date).await?;
+
+    let (event, narration) = match payload.advance_type {
+        FarmerAdvanceType::Advance => (
+            PostingEvent::FarmerAdvance,
+            format!("Cash advance to farmer {}", farmer.name),
+        ),
+        FarmerAdvanceType::Deduction => (
+            PostingEvent::FarmerDeduction,
+            format!("Deduction charged to farmer {}", farmer.name),
+        ),
+    };
+    let rule = resolve_posting_rule(conn, event, None).await?;
+
+    let advance = farmer_advances::ActiveModel {
+        farmer_id: Set(farmer.farmer_id),
+        batch_id: Set(payload.batch_id),
+        advance_type: Set(payload.advance_type),
+        amount: Set(payload.amount),
+        advance_date: Set(advance_date),
+        description: Set(payload.description),
+        created_by: Set(payload.created_by),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    post_journal(
+        conn,
+        &CreateJournalVoucher {
+            txn_date: advance_date,
+            narration: Some(narration),
+            reference_table: Some("farmer_advances".to_string()),
+            reference_id: Some(advance.advance_id),
+            created_by: payload.created_by,
+            lines: vec![
+                JournalLine {
+                    account_id: rule.debit_account_id,
+                    debit: Some(payload.amount),
+                    credit: None,
+                    narration: None,
+                },
+                JournalLine {
+                    account_id: rule.credit_account_id,
+                    debit: None,
+                    credit: Some(payload.amount),
+                    narration: None,
+                },
+            ],
+        },
+    )
+    .await?;
+
+    Ok(advance)
+}
+
+/// Running balance of what a farmer owes back. Advances, deductions and cash
+/// paid out are debits; commission and settlements earned are credits.
+pub async fn get_farmer_statement_handler(
+    State(db): State<DatabaseConnection>,
+    Path(farmer_id): Path<i32>,
+    Query(query): Query<PeriodQuery>,
+) -> Result<Response, StatusCode> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = query.from.unwrap_or_else(|| to.with_day(1).unwrap_or(to));
+    if from > to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let farmer = farmers::Entity::find_by_id(farmer_id)
+        .one(&db)
+        .await
+        .map_err(internal_error("fetch farmer"))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let advances = farmer_advances::Entity::find()
+        .filter(farmer_advances::Column::FarmerId.eq(farmer_id))
+        .filter(farmer_advances::Column::VoidedAt.is_null())
+        .filter(farmer_advances::Column::AdvanceDate.lte(to))
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch farmer advances"))?;
+
+    let commissions = farmer_commission_history::Entity::find()
+        .filter(farmer_commission_history::Column::FarmerId.eq(farmer_id))
+        .filter(farmer_commission_history::Column::VoidedAt.is_null())
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch farmer commissions"))?;
+
+    let settlements = farmer_settlements::Entity::find()
+        .filter(farmer_settlements::Column::FarmerId.eq(farmer_id))
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch farmer settlements"))?;
+
+    // (date, kind, id, description, debit, credit)
+    let mut movements: Vec<(NaiveDate, &str, i32, String, Decimal, Decimal)> = Vec::new();
+    for advance in &advances {
+        let (kind, default_description) = match advance.advance_type {
+            FarmerAdvanceType::Advance => ("advance", "Cash advance"),
+            FarmerAdvanceType::Deduction => ("deduction", "Deduction"),
+        };
+        movements.push((
+            advance.advance_date,
+            kind,
+            advance.advance_id,
+            advance
+                .description
+                .clone()
+                .unwrap_or_else(|| default_description.to_string()),
+            advance.amount,
+            Decimal::ZERO,
+        ));
+    }
+    for commission in &commissions {
+        let date = commission.created_at.date_naive();
+        let settlement = settlements
+            .iter()
+            .find(|s| s.commission_id == commission.id);
+
+        // Commission is paid in cash as it is booked; a settlement pays out
+        // net of the advances it recovered
+        match settlement {
+            Some(s) => {
+                movements.push((
+                    date,
+                    "settlement",
+                    s.settlement_id,
+                    format!("Settlement for batch {}", s.batch_id),
+                    Decimal::ZERO,
+                    s.gross_amount,
+                ));
+                movements.push((
+                    date,
+                    "payment",
+                    commission.id,
+                    format!(
+                        "Settlement paid, {} of advances recovered",
+                        s.advances_deducted
+                    ),
+                    s.net_amount,
+                    Decimal::ZERO,
+                ));
+            }
+            None => {
+                movements.push((
+                    date,
+                    "commission",
+                    commission.id,
+                    commission
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| "Farmer commission".to_string()),
+                    Decimal::ZERO,
+                    commission.commission_amount,
+                ));
+                movements.push((
+                    date,
+                    "payment",
+                    commission.id,
+                    "Commission paid".to_string(),
+                    commission.commission_amount,
+                    Decimal::ZERO,
+                ));
+            }
+        }
+    }
+    movements.retain(|m| m.0 <= to);
+    movements.sort_by_key(|m| (m.0, m.1 == "payment", m.2));
+
+    let opening_balance: Decimal = movements
+        .iter()
+        .filter(|m| m.0 < from)
+        .map(|m| m.4 - m.5)
+        .sum();
+
+    let mut balance = opening_balance;
+    let lines: Vec<FarmerStatementLine> = movements
+        .into_iter()
+        .filter(|m| m.0 >= from)
+        .map(|(date, kind, reference_id, description, debit, credit)| {
+            balance += debit - credit;
+            FarmerStatementLine {
+                date,
+                kind: kind.to_string(),
+                reference_id,
+                description,
+                debit,
+                credit,
+                balance,
+            }
+        })
+        .collect();
+
+    let statement = FarmerStatementResponse {
+        farmer_id,
+        farmer_name: farmer.name,
+        from,
+        to,
+        opening_balance,
+        lines,
+        closing_balance: balance,
+    };
+
+    if query.format == ReportFormat::Csv {
+        let mut rows = vec![csv_row([
+            "date",
+            "kind",
+            "reference_id",
+            "description",
+            "debit",
+            "credit",
+            "balance",
+        ])];
+        rows.push(csv_row([
+            from.to_string(),
+            "opening".to_string(),
+            String::new(),
+            "Opening balance".to_string(),
+            String::new(),
+            String::new(),
+            statement.opening_balance.to_string(),
+        ]));
+        for line in &statement.lines {
+            rows.push(csv_row([
+                line.date.to_string(),
+                line.kind.clone(),
+                line.reference_id.to_string(),
+                line.description.clone(),
+                line.debit.to_string(),
+                line.credit.to_string(),
+                line.balance.to_string(),
+            ]));
+        }
+        rows.push(csv_row([
+            to.to_string(),
+            "closing".to_string(),
+            String::new(),
+            "Closing balance".to_string(),
+            String::new(),
+            String::new(),
+            statement.closing_balance.to_string(),
+        ]));
+        return Ok(csv_response(
+            &format!("farmer_{}_statement_{}_{}.csv", farmer_id, from, to),
+            rows,
+        ));
+    }
+
+    Ok(Json(statement).into_response())
+}
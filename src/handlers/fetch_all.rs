@@ -76,6 +76,9 @@ pub async fn get_purchases_handler(State(db): State<DatabaseConnection>) -> impl
                     quantity: p.quantity,
                     purchase_date: p.purchase_date,
                     supplier: p.supplier,
+                    supplier_id: p.supplier_id,
+                    amount_due: p.amount_due,
+                    amount_paid: p.amount_paid,
                     created_by: p.created_by,
                 })
                 .collect();
@@ -128,6 +131,7 @@ pub async fn get_batches_handler(State(db): State<DatabaseConnection>) -> impl I
                         current_bird_count: batch.current_bird_count,
                         status: batch.status,
                         created_at: batch.created_at,
+                        breed: batch.breed,
                     })
                 })
                 .collect();
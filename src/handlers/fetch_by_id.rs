@@ -9,6 +9,10 @@ use sea_orm::DatabaseConnection;
 use sea_orm::EntityTrait;
 use sea_orm::QueryFilter;
 use sea_orm::QueryOrder;
+use uuid::Uuid;
+
+use crate::handlers::journal::load_journal;
+use crate::models::JournalResponse;
 
 pub async fn get_farmer_commission_history_by_id_handler(
     State(db): State<DatabaseConnection>,
@@ -30,3 +34,17 @@ pub async fn get_farmer_commission_history_by_id_handler(
         }
     }
 }
+
+pub async fn get_journal_handler(
+    State(db): State<DatabaseConnection>,
+    Path(txn_group_id): Path<Uuid>,
+) -> Result<Json<JournalResponse>, StatusCode> {
+    match load_journal(&db, txn_group_id).await {
+        Ok(Some(journal)) => Ok(Json(journal)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            eprintln!("Failed to fetch journal {}: {}", txn_group_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
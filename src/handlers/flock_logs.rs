@@ -0,0 +1,531 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use entity::{batches, bird_count_history, breed_standards, flock_logs};
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use thiserror::Error;
+
+use crate::handlers::batch_lifecycle::{ensure_batch_allows, BatchOperation, BatchStateError};
+use crate::handlers::batch_performance::allocated_feed_kg;
+use crate::models::{
+    CorrectFlockLog, CreateFlockLog, FlockLogResponse, GrowthCurvePoint, GrowthCurveResponse,
+    ResponseMessage, SetBreedStandards,
+};
+
+#[derive(Debug, Error)]
+pub enum FlockLogError {
+    #[error(transparent)]
+    Lifecycle(#[from] BatchStateError),
+    #[error("Batch {0} not found")]
+    BatchNotFound(i32),
+    #[error("Flock log {0} not found")]
+    LogNotFound(i32),
+    #[error("Batch {batch_id} already has a log for {log_date}; correct it instead")]
+    LogExists { batch_id: i32, log_date: NaiveDate },
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Feed consumed ({consumed} kg) exceeds the {balance} kg left on the farm")]
+    FeedExceedsBalance { consumed: Decimal, balance: Decimal },
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl FlockLogError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            FlockLogError::Lifecycle(e) => e.status(),
+            FlockLogError::BatchNotFound(_) | FlockLogError::LogNotFound(_) => {
+                StatusCode::NOT_FOUND
+            }
+            FlockLogError::LogExists { .. } => StatusCode::CONFLICT,
+            FlockLogError::Invalid(_) => StatusCode::BAD_REQUEST,
+            FlockLogError::FeedExceedsBalance { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            FlockLogError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+fn error_response(
+    action: &'static str,
+) -> impl FnOnce(FlockLogError) -> (StatusCode, Json<ResponseMessage>) {
+    move |e| {
+        eprintln!("Failed to {}: {}", action, e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    }
+}
+
+/// Reject negative readings and a sample weight without a sample size.
+fn validate_readings(
+    feed_consumed_kg: Decimal,
+    sample_size: Option<i32>,
+    sample_avg_weight_kg: Option<Decimal>,
+    water_litres: Option<Decimal>,
+) -> Result<(), FlockLogError> {
+    if feed_consumed_kg < Decimal::ZERO
+        || sample_avg_weight_kg.is_some_and(|w| w <= Decimal::ZERO)
+        || water_litres.is_some_and(|w| w < Decimal::ZERO)
+    {
+        return Err(FlockLogError::Invalid(
+            "Feed, weight and water readings can't be negative".to_string(),
+        ));
+    }
+    if sample_avg_weight_kg.is_some() && sample_size.is_none_or(|n| n <= 0) {
+        return Err(FlockLogError::Invalid(
+            "A sample weight needs the number of birds weighed".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Feed still on the farm: allocated to the batch minus what the logs record
+/// as eaten, leaving out `except_log` so a correction can replace its value.
+async fn feed_balance<C: ConnectionTrait>(
+    conn: &C,
+    batch: &batches::Model,
+    except_log: Option<i32>,
+) -> Result<(Decimal, Decimal), DbErr> {
+    let allocated = allocated_feed_kg(conn, batch).await?;
+
+    let mut query =
+        flock_logs::Entity::find().filter(flock_logs::Column::BatchId.eq(batch.batch_id));
+    if let Some(log_id) = except_log {
+        query = query.filter(flock_logs::Column::LogId.ne(log_id));
+    }
+    let consumed: Decimal = query
+        .all(conn)
+        .await?
+        .iter()
+        .map(|l| l.feed_consumed_kg)
+        .sum();
+
+    Ok((allocated, consumed))
+}
+
+pub async fn create_flock_log(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateFlockLog>,
+) -> Result<Json<flock_logs::Model>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match insert_flock_log(&txn, payload).await {
+            Ok(log) => txn.commit().await.map(|_| log).map_err(FlockLogError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(FlockLogError::from(e)),
+    };
+
+    result.map(Json).map_err(error_response("record flock log"))
+}
+
+async fn insert_flock_log<C: ConnectionTrait>(
+    conn: &C,
+    payload: CreateFlockLog,
+) -> Result<flock_logs::Model, FlockLogError> {
+    let batch = ensure_batch_allows(conn, payload.batch_id, BatchOperation::RecordFlockLog).await?;
+
+    let feed_consumed_kg = payload.feed_consumed_kg.unwrap_or_default();
+    validate_readings(
+        feed_consumed_kg,
+        payload.sample_size,
+        payload.sample_avg_weight_kg,
+        payload.water_litres,
+    )?;
+    if payload.log_date < batch.start_date {
+        return Err(FlockLogError::Invalid(format!(
+            "Log date {} is before the batch started on {}",
+            payload.log_date, batch.start_date
+        )));
+    }
+
+    let existing = flock_logs::Entity::find()
+        .filter(flock_logs::Column::BatchId.eq(payload.batch_id))
+        .filter(flock_logs::Column::LogDate.eq(payload.log_date))
+        .one(conn)
+        .await?;
+    if existing.is_some() {
+        return Err(FlockLogError::LogExists {
+            batch_id: payload.batch_id,
+            log_date: payload.log_date,
+        });
+    }
+
+    let (allocated, consumed) = feed_balance(conn, &batch, None).await?;
+    if feed_consumed_kg > allocated - consumed {
+        return Err(FlockLogError::FeedExceedsBalance {
+            consumed: feed_consumed_kg,
+            balance: allocated - consumed,
+        });
+    }
+
+    let log = flock_logs::ActiveModel {
+        batch_id: Set(payload.batch_id),
+        log_date: Set(payload.log_date),
+        feed_consumed_kg: Set(feed_consumed_kg),
+        sample_size: Set(payload.sample_size),
+        sample_avg_weight_kg: Set(payload.sample_avg_weight_kg),
+        water_litres: Set(payload.water_litres),
+        notes: Set(payload.notes),
+        recorded_by: Set(payload.recorded_by),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    Ok(log)
+}
+
+pub async fn correct_flock_log(
+    State(db): State<DatabaseConnection>,
+    Path(log_id): Path<i32>,
+    Json(payload): Json<CorrectFlockLog>,
+) -> Result<Json<flock_logs::Model>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match update_flock_log(&txn, log_id, payload).await {
+            Ok(log) => txn.commit().await.map(|_| log).map_err(FlockLogError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(FlockLogError::from(e)),
+    };
+
+    result
+        .map(Json)
+        .map_err(error_response("correct flock log"))
+}
+
+async fn update_flock_log<C: ConnectionTrait>(
+    conn: &C,
+    log_id: i32,
+    payload: CorrectFlockLog,
+) -> Result<flock_logs::Model, FlockLogError> {
+    let log = flock_logs::Entity::find_by_id(log_id)
+        .one(conn)
+        .await?
+        .ok_or(FlockLogError::LogNotFound(log_id))?;
+    let batch = ensure_batch_allows(conn, log.batch_id, BatchOperation::RecordFlockLog).await?;
+
+    let feed_consumed_kg = payload.feed_consumed_kg.unwrap_or(log.feed_consumed_kg);
+    let sample_size = payload.sample_size.or(log.sample_size);
+    let sample_avg_weight_kg = payload.sample_avg_weight_kg.or(log.sample_avg_weight_kg);
+    let water_litres = payload.water_litres.or(log.water_litres);
+    validate_readings(
+        feed_consumed_kg,
+        sample_size,
+        sample_avg_weight_kg,
+        water_litres,
+    )?;
+
+    let (allocated, consumed) = feed_balance(conn, &batch, Some(log_id)).await?;
+    if feed_consumed_kg > allocated - consumed {
+        return Err(FlockLogError::FeedExceedsBalance {
+            consumed: feed_consumed_kg,
+            balance: allocated - consumed,
+        });
+    }
+
+    let notes = payload.notes.or_else(|| log.notes.clone());
+    let mut active: flock_logs::ActiveModel = log.into();
+    active.feed_consumed_kg = Set(feed_consumed_kg);
+    active.sample_size = Set(sample_size);
+    active.sample_avg_weight_kg = Set(sample_avg_weight_kg);
+    active.water_litres = Set(water_litres);
+    active.notes = Set(notes);
+    active.corrected_at = Set(Some(Utc::now().into()));
+    active.corrected_by = Set(Some(payload.corrected_by));
+
+    Ok(active.update(conn).await?)
+}
+
+async fn find_batch<C: ConnectionTrait>(
+    conn: &C,
+    batch_id: i32,
+) -> Result<batches::Model, FlockLogError> {
+    batches::Entity::find_by_id(batch_id)
+        .one(conn)
+        .await?
+        .ok_or(FlockLogError::BatchNotFound(batch_id))
+}
+
+/// A batch's logs, oldest first, with the feed still on the farm.
+pub async fn get_flock_logs_handler(
+    State(db): State<DatabaseConnection>,
+    Path(batch_id): Path<i32>,
+) -> Result<Json<FlockLogResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let result: Result<FlockLogResponse, FlockLogError> = async {
+        let batch = find_batch(&db, batch_id).await?;
+        let (allocated, consumed) = feed_balance(&db, &batch, None).await?;
+        let logs = flock_logs::Entity::find()
+            .filter(flock_logs::Column::BatchId.eq(batch_id))
+            .order_by_asc(flock_logs::Column::LogDate)
+            .all(&db)
+            .await?;
+
+        Ok(FlockLogResponse {
+            batch_id,
+            feed_allocated_kg: allocated,
+            feed_consumed_kg: consumed,
+            feed_balance_kg: allocated - consumed,
+            logs,
+        })
+    }
+    .await;
+
+    result.map(Json).map_err(error_response("fetch flock logs"))
+}
+
+/// Standard value at `age_days`, interpolated linearly between the nearest
+/// rows of a table sorted by age. Ages outside the table have no standard.
+fn standard_at(
+    standards: &[breed_standards::Model],
+    age_days: i64,
+    value: impl Fn(&breed_standards::Model) -> Decimal,
+) -> Option<Decimal> {
+    let upper = standards
+        .iter()
+        .position(|s| i64::from(s.age_days) >= age_days)?;
+    let hi = &standards[upper];
+    if i64::from(hi.age_days) == age_days {
+        return Some(value(hi));
+    }
+    let lo = &standards[upper.checked_sub(1)?];
+
+    let span = Decimal::from(hi.age_days - lo.age_days);
+    let offset = Decimal::from(age_days - i64::from(lo.age_days));
+    Some((value(lo) + (value(hi) - value(lo)) * offset / span).round_dp(3))
+}
+
+/// Sampled weights and feed eaten per bird, day by day, against the batch's
+/// breed standard.
+pub async fn get_growth_curve_handler(
+    State(db): State<DatabaseConnection>,
+    Path(batch_id): Path<i32>,
+) -> Result<Json<GrowthCurveResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let result: Result<GrowthCurveResponse, FlockLogError> = async {
+        let batch = find_batch(&db, batch_id).await?;
+
+        let standards = match &batch.breed {
+            Some(breed) => {
+                breed_standards::Entity::find()
+                    .filter(breed_standards::Column::Breed.eq(breed.clone()))
+                    .order_by_asc(breed_standards::Column::AgeDays)
+                    .all(&db)
+                    .await?
+            }
+            None => Vec::new(),
+        };
+
+        let logs = flock_logs::Entity::find()
+            .filter(flock_logs::Column::BatchId.eq(batch_id))
+            .order_by_asc(flock_logs::Column::LogDate)
+            .all(&db)
+            .await?;
+        let counts = bird_count_history::Entity::find()
+            .filter(bird_count_history::Column::BatchId.eq(batch_id))
+            .all(&db)
+            .await?;
+
+        let mut cumulative_feed = Decimal::ZERO;
+        let points = logs
+            .iter()
+            .map(|log| {
+                cumulative_feed += log.feed_consumed_kg;
+                let age_days = (log.log_date - batch.start_date).num_days();
+                let live_birds = batch.initial_bird_count
+                    + counts
+                        .iter()
+                        .filter(|c| c.record_date <= log.log_date && !c.is_stocking)
+                        .map(|c| c.additions - c.deaths)
+                        .sum::<i32>();
+
+                let standard_weight_kg = standard_at(&standards, age_days, |s| s.body_weight_kg);
+                let weight_variance_pct = log
+                    .sample_avg_weight_kg
+                    .zip(standard_weight_kg)
+                    .filter(|(_, standard)| *standard > Decimal::ZERO)
+                    .map(|(actual, standard)| {
+                        ((actual - standard) * Decimal::from(100) / standard).round_dp(2)
+                    });
+
+                GrowthCurvePoint {
+                    log_date: log.log_date,
+                    age_days,
+                    live_birds,
+                    sample_avg_weight_kg: log.sample_avg_weight_kg,
+                    standard_weight_kg,
+                    weight_variance_pct,
+                    cumulative_feed_per_bird_kg: (live_birds > 0)
+                        .then(|| (cumulative_feed / Decimal::from(live_birds)).round_dp(3)),
+                    standard_cumulative_feed_kg: standard_at(&standards, age_days, |s| {
+                        s.cumulative_feed_kg
+                    }),
+                }
+            })
+            .collect();
+
+        Ok(GrowthCurveResponse {
+            batch_id,
+            breed: batch.breed,
+            points,
+        })
+    }
+    .await;
+
+    result
+        .map(Json)
+        .map_err(error_response("build growth curve"))
+}
+
+pub async fn get_breed_standards_handler(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<Vec<breed_standards::Model>>, (StatusCode, Json<ResponseMessage>)> {
+    breed_standards::Entity::find()
+        .order_by_asc(breed_standards::Column::Breed)
+        .order_by_asc(breed_standards::Column::AgeDays)
+        .all(&db)
+        .await
+        .map(Json)
+        .map_err(FlockLogError::from)
+        .map_err(error_response("fetch breed standards"))
+}
+
+pub async fn set_breed_standards(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<SetBreedStandards>,
+) -> Result<Json<Vec<breed_standards::Model>>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match replace_breed_standards(&txn, payload).await {
+            Ok(rows) => txn
+                .commit()
+                .await
+                .map(|_| rows)
+                .map_err(FlockLogError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(FlockLogError::from(e)),
+    };
+
+    result
+        .map(Json)
+        .map_err(error_response("set breed standards"))
+}
+
+async fn replace_breed_standards<C: ConnectionTrait>(
+    conn: &C,
+    payload: SetBreedStandards,
+) -> Result<Vec<breed_standards::Model>, FlockLogError> {
+    let breed = payload.breed.trim().to_string();
+    if breed.is_empty() || payload.rows.is_empty() {
+        return Err(FlockLogError::Invalid(
+            "A breed name and at least one standard row are required".to_string(),
+        ));
+    }
+    if payload.rows.iter().any(|r| {
+        r.age_days < 0 || r.body_weight_kg < Decimal::ZERO || r.cumulative_feed_kg < Decimal::ZERO
+    }) {
+        return Err(FlockLogError::Invalid(
+            "Standard ages, weights and feed can't be negative".to_string(),
+        ));
+    }
+    let mut ages: Vec<i32> = payload.rows.iter().map(|r| r.age_days).collect();
+    ages.sort_unstable();
+    if ages.windows(2).any(|w| w[0] == w[1]) {
+        return Err(FlockLogError::Invalid(
+            "Each age may appear only once in a breed's standard".to_string(),
+        ));
+    }
+
+    breed_standards::Entity::delete_many()
+        .filter(breed_standards::Column::Breed.eq(breed.clone()))
+        .exec(conn)
+        .await?;
+
+    let mut rows = Vec::with_capacity(payload.rows.len());
+    for row in payload.rows {
+        rows.push(
+            breed_standards::ActiveModel {
+                breed: Set(breed.clone()),
+                age_days: Set(row.age_days),
+                body_weight_kg: Set(row.body_weight_kg),
+                cumulative_feed_kg: Set(row.cumulative_feed_kg),
+                ..Default::default()
+            }
+            .insert(conn)
+            .await?,
+        );
+    }
+    rows.sort_by_key(|r| r.age_days);
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Weights given in grams, stored in kg
+    fn standard(age_days: i32, weight_g: i64, feed_g: i64) -> breed_standards::Model {
+        breed_standards::Model {
+            standard_id: age_days,
+            breed: "test".to_string(),
+            age_days,
+            body_weight_kg: Decimal::new(weight_g, 3),
+            cumulative_feed_kg: Decimal::new(feed_g, 3),
+        }
+    }
+
+    fn table() -> Vec<breed_standards::Model> {
+        vec![
+            standard(0, 42, 0),
+            standard(7, 185, 167),
+            standard(14, 465, 580),
+        ]
+    }
+
+    #[test]
+    fn exact_age_uses_the_row() {
+        let standards = table();
+        assert_eq!(
+            standard_at(&standards, 7, |s| s.body_weight_kg),
+            Some(Decimal::new(185, 3))
+        );
+        assert_eq!(
+            standard_at(&standards, 14, |s| s.cumulative_feed_kg),
+            Some(Decimal::new(580, 3))
+        );
+    }
+
+    #[test]
+    fn ages_between_rows_are_interpolated() {
+        let standards = table();
+        // 185 + (465 - 185) * 3 / 7 = 305 g
+        assert_eq!(
+            standard_at(&standards, 10, |s| s.body_weight_kg),
+            Some(Decimal::new(305, 3))
+        );
+        // 167 + (580 - 167) * 1 / 7 = 226 g, to three places
+        assert_eq!(
+            standard_at(&standards, 8, |s| s.cumulative_feed_kg),
+            Some(Decimal::new(226, 3))
+        );
+    }
+
+    #[test]
+    fn ages_outside_the_table_have_no_standard() {
+        let standards = table();
+        assert_eq!(standard_at(&standards, 15, |s| s.body_weight_kg), None);
+        assert_eq!(standard_at(&standards, -1, |s| s.body_weight_kg), None);
+        assert_eq!(standard_at(&[], 3, |s| s.body_weight_kg), None);
+    }
+}
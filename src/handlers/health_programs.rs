@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Days, Utc};
+use entity::sea_orm_active_enums::{BatchStatus, ItemCategory, RequirementStatus};
+use entity::{
+    batch_health_tasks, batch_requirements, batches, farmers, health_program_steps,
+    health_programs, items,
+};
+use sea_orm::prelude::{Decimal, Expr};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use thiserror::Error;
+
+use crate::handlers::batch_lifecycle::{ensure_batch_allows, BatchOperation, BatchStateError};
+use crate::models::{
+    CompleteHealthTask, CompleteHealthTaskResponse, CreateHealthProgram, HealthProgramResponse,
+    HealthTaskLine, HealthTaskQuery, ResponseMessage, SupervisorHealthTasks,
+};
+
+#[derive(Debug, Error)]
+pub enum HealthProgramError {
+    #[error("Health program {0} not found")]
+    ProgramNotFound(i32),
+    #[error("Health task {0} not found")]
+    TaskNotFound(i32),
+    #[error("Health task {0} is already completed")]
+    AlreadyCompleted(i32),
+    #[error("Health task {0} has no medicine item to request")]
+    NoMedicine(i32),
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Lifecycle(#[from] BatchStateError),
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl HealthProgramError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            HealthProgramError::ProgramNotFound(_) | HealthProgramError::TaskNotFound(_) => {
+                StatusCode::NOT_FOUND
+            }
+            HealthProgramError::AlreadyCompleted(_) => StatusCode::CONFLICT,
+            HealthProgramError::NoMedicine(_) | HealthProgramError::Invalid(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            HealthProgramError::Lifecycle(e) => e.status(),
+            HealthProgramError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+fn error_response(
+    action: &'static str,
+) -> impl FnOnce(HealthProgramError) -> (StatusCode, Json<ResponseMessage>) {
+    move |e| {
+        eprintln!("Failed to {}: {}", action, e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    }
+}
+
+/// Copy a program's steps onto a new batch as dated tasks. Without an
+/// explicit program the default one is used; with neither, nothing is
+/// scheduled.
+pub async fn apply_health_program<C: ConnectionTrait>(
+    conn: &C,
+    batch: &batches::Model,
+    program_id: Option<i32>,
+) -> Result<Vec<batch_health_tasks::Model>, HealthProgramError> {
+    let program = match program_id {
+        Some(id) => Some(
+            health_programs::Entity::find_by_id(id)
+                .one(conn)
+                .await?
+                .ok_or(HealthProgramError::ProgramNotFound(id))?,
+        ),
+        None => {
+            health_programs::Entity::find()
+                .filter(health_programs::Column::IsDefault.eq(true))
+                .one(conn)
+                .await?
+        }
+    };
+    let Some(program) = program else {
+        return Ok(Vec::new());
+    };
+
+    let steps = health_program_steps::Entity::find()
+        .filter(health_program_steps::Column::ProgramId.eq(program.program_id))
+        .order_by_asc(health_program_steps::Column::DayOfAge)
+        .all(conn)
+        .await?;
+
+    let mut tasks = Vec::with_capacity(steps.len());
+    for step in steps {
+        let due_date = u64::try_from(step.day_of_age)
+            .ok()
+            .and_then(|days| batch.start_date.checked_add_days(Days::new(days)))
+            .ok_or_else(|| {
+                HealthProgramError::Invalid(format!(
+                    "Step '{}' falls due past the last supported date",
+                    step.task_name
+                ))
+            })?;
+        tasks.push(
+            batch_health_tasks::ActiveModel {
+                batch_id: Set(batch.batch_id),
+                step_id: Set(Some(step.step_id)),
+                task_name: Set(step.task_name),
+                item_code: Set(step.item_code),
+                dose_per_bird: Set(step.dose_per_bird),
+                due_date: Set(due_date),
+                notes: Set(step.notes),
+                ..Default::default()
+            }
+            .insert(conn)
+            .await?,
+        );
+    }
+
+    Ok(tasks)
+}
+
+pub async fn get_health_programs_handler(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<Vec<HealthProgramResponse>>, (StatusCode, Json<ResponseMessage>)> {
+    let result: Result<Vec<HealthProgramResponse>, HealthProgramError> = async {
+        let programs = health_programs::Entity::find()
+            .order_by_asc(health_programs::Column::ProgramId)
+            .all(&db)
+            .await?;
+
+        let mut steps: HashMap<i32, Vec<health_program_steps::Model>> = HashMap::new();
+        for step in health_program_steps::Entity::find()
+            .order_by_asc(health_program_steps::Column::DayOfAge)
+            .all(&db)
+            .await?
+        {
+            steps.entry(step.program_id).or_default().push(step);
+        }
+
+        Ok(programs
+            .into_iter()
+            .map(|program| HealthProgramResponse {
+                steps: steps.remove(&program.program_id).unwrap_or_default(),
+                program,
+            })
+            .collect())
+    }
+    .await;
+
+    result
+        .map(Json)
+        .map_err(error_response("fetch health programs"))
+}
+
+pub async fn create_health_program(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateHealthProgram>,
+) -> Result<Json<HealthProgramResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match insert_health_program(&txn, payload).await {
+            Ok(resp) => txn
+                .commit()
+                .await
+                .map(|_| resp)
+                .map_err(HealthProgramError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(HealthProgramError::from(e)),
+    };
+
+    result
+        .map(Json)
+        .map_err(error_response("create health program"))
+}
+
+async fn insert_health_program<C: ConnectionTrait>(
+    conn: &C,
+    payload: CreateHealthProgram,
+) -> Result<HealthProgramResponse, HealthProgramError> {
+    let name = payload.name.trim().to_string();
+    if name.is_empty() || payload.steps.is_empty() {
+        return Err(HealthProgramError::Invalid(
+            "A program needs a name and at least one step".to_string(),
+        ));
+    }
+
+    // 1. Steps must fall within the batch and name medicine items
+    for step in &payload.steps {
+        if step.day_of_age < 0 || step.dose_per_bird.is_some_and(|d| d <= Decimal::ZERO) {
+            return Err(HealthProgramError::Invalid(format!(
+                "Step '{}' needs a day of age of 0 or more and a positive dose",
+                step.task_name
+            )));
+        }
+        if let Some(item_code) = &step.item_code {
+            let item = items::Entity::find_by_id(item_code.clone())
+                .one(conn)
+                .await?
+                .ok_or_else(|| {
+                    HealthProgramError::Invalid(format!("Item {} not found", item_code))
+                })?;
+            if item.item_category != ItemCategory::Medicine {
+                return Err(HealthProgramError::Invalid(format!(
+                    "Item {} is not a medicine item",
+                    item_code
+                )));
+            }
+        }
+    }
+
+    // 2. Only one program is the default
+    let is_default = payload.is_default.unwrap_or(false);
+    if is_default {
+        health_programs::Entity::update_many()
+            .col_expr(health_programs::Column::IsDefault, Expr::value(false))
+            .exec(conn)
+            .await?;
+    }
+
+    let program = health_programs::ActiveModel {
+        name: Set(name),
+        is_default: Set(is_default),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    let mut steps = Vec::with_capacity(payload.steps.len());
+    for step in payload.steps {
+        steps.push(
+            health_program_steps::ActiveModel {
+                program_id: Set(program.program_id),
+                day_of_age: Set(step.day_of_age),
+                task_name: Set(step.task_name),
+                item_code: Set(step.item_code),
+                dose_per_bird: Set(step.dose_per_bird),
+                notes: Set(step.notes),
+                ..Default::default()
+            }
+            .insert(conn)
+            .await?,
+        );
+    }
+    steps.sort_by_key(|s| s.day_of_age);
+
+    Ok(HealthProgramResponse { program, steps })
+}
+
+/// Open tasks on a supervisor's active batches: overdue ones, and those due
+/// between `as_of` and `within_days` later.
+pub async fn get_supervisor_health_tasks_handler(
+    State(db): State<DatabaseConnection>,
+    Path(supervisor_id): Path<i32>,
+    Query(query): Query<HealthTaskQuery>,
+) -> Result<Json<SupervisorHealthTasks>, (StatusCode, Json<ResponseMessage>)> {
+    let as_of = query.as_of.unwrap_or_else(|| Utc::now().date_naive());
+
+    let result: Result<SupervisorHealthTasks, HealthProgramError> = async {
+        let within_days = query.within_days.unwrap_or(0).max(0) as u64;
+        let horizon = as_of
+            .checked_add_days(Days::new(within_days))
+            .ok_or_else(|| {
+                HealthProgramError::Invalid(format!(
+                    "within_days {} reaches past the last supported date",
+                    within_days
+                ))
+            })?;
+
+        let batch_list: HashMap<i32, batches::Model> = batches::Entity::find()
+            .filter(batches::Column::SupervisorId.eq(supervisor_id))
+            .filter(batches::Column::Status.is_in([
+                BatchStatus::Planned,
+                BatchStatus::Open,
+                BatchStatus::Selling,
+            ]))
+            .all(&db)
+            .await?
+            .into_iter()
+            .map(|b| (b.batch_id, b))
+            .collect();
+
+        let farmer_names: HashMap<i32, String> = farmers::Entity::find()
+            .filter(
+                farmers::Column::FarmerId
+                    .is_in(batch_list.values().map(|b| b.farmer_id).collect::<Vec<_>>()),
+            )
+            .all(&db)
+            .await?
+            .into_iter()
+            .map(|f| (f.farmer_id, f.name))
+            .collect();
+
+        let tasks = batch_health_tasks::Entity::find()
+            .filter(
+                batch_health_tasks::Column::BatchId
+                    .is_in(batch_list.keys().copied().collect::<Vec<_>>()),
+            )
+            .filter(batch_health_tasks::Column::CompletedAt.is_null())
+            .filter(batch_health_tasks::Column::DueDate.lte(horizon))
+            .order_by_asc(batch_health_tasks::Column::DueDate)
+            .all(&db)
+            .await?;
+
+        let mut overdue = Vec::new();
+        let mut due = Vec::new();
+        for task in tasks {
+            let Some(batch) = batch_list.get(&task.batch_id) else {
+                continue;
+            };
+            let days_overdue = (as_of - task.due_date).num_days();
+            let line = HealthTaskLine {
+                line_id: batch.line_id,
+                farmer_id: batch.farmer_id,
+                farmer_name: farmer_names
+                    .get(&batch.farmer_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                days_overdue,
+                task,
+            };
+            if days_overdue > 0 {
+                overdue.push(line);
+            } else {
+                due.push(line);
+            }
+        }
+
+        Ok(SupervisorHealthTasks {
+            supervisor_id,
+            as_of,
+            overdue,
+            due,
+        })
+    }
+    .await;
+
+    result
+        .map(Json)
+        .map_err(error_response("fetch health tasks"))
+}
+
+pub async fn complete_health_task_handler(
+    State(db): State<DatabaseConnection>,
+    Path(task_id): Path<i32>,
+    Json(payload): Json<CompleteHealthTask>,
+) -> Result<Json<CompleteHealthTaskResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match complete_health_task(&txn, task_id, payload).await {
+            Ok(resp) => txn
+                .commit()
+                .await
+                .map(|_| resp)
+                .map_err(HealthProgramError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(HealthProgramError::from(e)),
+    };
+
+    result
+        .map(Json)
+        .map_err(error_response("complete health task"))
+}
+
+async fn complete_health_task<C: ConnectionTrait>(
+    conn: &C,
+    task_id: i32,
+    payload: CompleteHealthTask,
+) -> Result<CompleteHealthTaskResponse, HealthProgramError> {
+    let task = batch_health_tasks::Entity::find_by_id(task_id)
+        .one(conn)
+        .await?
+        .ok_or(HealthProgramError::TaskNotFound(task_id))?;
+    if task.completed_at.is_some() {
+        return Err(HealthProgramError::AlreadyCompleted(task_id));
+    }
+
+    let batch = ensure_batch_allows(conn, task.batch_id, BatchOperation::RecordHealthTask).await?;
+
+    // Optionally request the medicine the task used
+    let requirement = if payload.raise_requirement.unwrap_or(false) {
+        ensure_batch_allows(conn, task.batch_id, BatchOperation::RaiseRequirement).await?;
+        let item_code = task
+            .item_code
+            .clone()
+            .ok_or(HealthProgramError::NoMedicine(task_id))?;
+
+        let live_birds = batch.current_bird_count.unwrap_or(batch.initial_bird_count);
+        let quantity = payload
+            .quantity
+            .or_else(|| task.dose_per_bird.map(|d| d * Decimal::from(live_birds)))
+            .unwrap_or_default();
+        if quantity <= Decimal::ZERO {
+            return Err(HealthProgramError::Invalid(
+                "Requirement quantity must be positive".to_string(),
+            ));
+        }
+
+        Some(
+            batch_requirements::ActiveModel {
+                batch_id: Set(batch.batch_id),
+                line_id: Set(batch.line_id),
+                supervisor_id: Set(batch.supervisor_id),
+                item_code: Set(item_code),
+                quantity: Set(quantity),
+                request_date: Set(Utc::now().date_naive()),
+                status: Set(RequirementStatus::Pending),
+                ..Default::default()
+            }
+            .insert(conn)
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    let notes = payload.notes.or_else(|| task.notes.clone());
+    let mut active: batch_health_tasks::ActiveModel = task.into();
+    active.completed_at = Set(Some(Utc::now().into()));
+    active.completed_by = Set(Some(payload.completed_by));
+    active.requirement_id = Set(requirement.as_ref().map(|r| r.requirement_id));
+    active.notes = Set(notes);
+    let task = active.update(conn).await?;
+
+    Ok(CompleteHealthTaskResponse { task, requirement })
+}
@@ -1,12 +1,24 @@
+use crate::handlers::batch_closure::add_commission_to_summary;
+use crate::handlers::batch_lifecycle::{
+    ensure_batch_allows, ensure_requirement_batch_allows, BatchOperation, BatchWriteError,
+};
+use crate::handlers::journal::post_journal;
+use crate::handlers::periods::{ensure_period_open, period_status};
+use crate::handlers::posting_rules::resolve_posting_rule;
+use crate::handlers::purchases::{internal_error, update_account_balance};
+use crate::handlers::reports::normal_balance;
+use crate::handlers::units::{item_base_quantity, requirement_base_quantity, unit_status};
 use crate::models::*;
 use axum::{extract::State, http::StatusCode, Json};
 use chrono::Utc;
-use entity::sea_orm_active_enums::{BatchStatus, LedgerAccountType};
+use entity::sea_orm_active_enums::{LedgerAccountType, PostingEvent};
 use entity::{sea_orm_active_enums::RequirementStatus, *};
 use sea_orm::prelude::Decimal;
-use sea_orm::EntityTrait;
 use sea_orm::TransactionTrait;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, Set,
+};
 use tracing::error;
 use uuid::Uuid;
 
@@ -36,6 +48,9 @@ pub async fn create_item(
         item_name: Set(payload.item_name),
         item_category: Set(payload.item_category),
         unit: Set(payload.unit),
+        reorder_point: Set(payload.reorder_point),
+        safety_stock: Set(payload.safety_stock.unwrap_or_default()),
+        preferred_supplier_id: Set(payload.preferred_supplier_id),
     };
 
     new_item
@@ -48,13 +63,23 @@ pub async fn create_item(
 pub async fn create_batch_requirement(
     State(db): State<DatabaseConnection>,
     Json(payload): Json<CreateBatchRequirement>,
-) -> Result<Json<batch_requirements::Model>, StatusCode> {
+) -> Result<Json<batch_requirements::Model>, BatchWriteError> {
+    ensure_batch_allows(&db, payload.batch_id, BatchOperation::RaiseRequirement).await?;
+    let quantity = item_base_quantity(
+        &db,
+        &payload.item_code,
+        payload.unit.as_deref(),
+        payload.quantity,
+    )
+    .await
+    .map_err(unit_status)?;
+
     let new_req = batch_requirements::ActiveModel {
         batch_id: Set(payload.batch_id),
         line_id: Set(payload.line_id),
         supervisor_id: Set(payload.supervisor_id),
         item_code: Set(payload.item_code.clone()),
-        quantity: Set(payload.quantity),
+        quantity: Set(quantity),
         request_date: Set(payload.request_date),
         // 👇 ensure status is always set
         status: Set(RequirementStatus::Pending),
@@ -66,7 +91,7 @@ pub async fn create_batch_requirement(
 
         Err(e) => {
             eprintln!("❌ Failed to insert batch requirement: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into())
         }
     }
 }
@@ -75,10 +100,21 @@ pub async fn create_batch_requirement(
 pub async fn create_batch_allocation(
     State(db): State<DatabaseConnection>,
     Json(payload): Json<CreateBatchAllocation>,
-) -> Result<Json<batch_allocations::Model>, StatusCode> {
+) -> Result<Json<batch_allocations::Model>, BatchWriteError> {
+    ensure_requirement_batch_allows(&db, payload.requirement_id, BatchOperation::AllocateStock)
+        .await?;
+    let allocated_qty = requirement_base_quantity(
+        &db,
+        payload.requirement_id,
+        payload.unit.as_deref(),
+        payload.allocated_qty,
+    )
+    .await
+    .map_err(unit_status)?;
+
     let new_alloc = batch_allocations::ActiveModel {
         requirement_id: Set(Some(payload.requirement_id)),
-        allocated_qty: Set(payload.allocated_qty),
+        allocated_qty: Set(allocated_qty),
         allocation_date: Set(payload.allocation_date),
         allocated_by: Set(payload.allocated_by),
         ..Default::default()
@@ -88,7 +124,7 @@ pub async fn create_batch_allocation(
         .insert(&db)
         .await
         .map(Json)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into())
 }
 
 /// Farmers
@@ -125,6 +161,7 @@ pub async fn create_trader(
         bank_account_no: Set(payload.bank_account_no),
         bank_name: Set(payload.bank_name),
         ifsc_code: Set(payload.ifsc_code),
+        credit_limit: Set(payload.credit_limit),
         ..Default::default()
     };
     new_trader
@@ -160,12 +197,15 @@ pub async fn create_supplier(
 pub async fn create_bird_count_history(
     State(db): State<DatabaseConnection>,
     Json(payload): Json<CreateBirdCountHistory>,
-) -> Result<Json<bird_count_history::Model>, StatusCode> {
+) -> Result<Json<bird_count_history::Model>, BatchWriteError> {
     let txn = db
         .begin()
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let batch =
+        ensure_batch_allows(&txn, payload.batch_id, BatchOperation::RecordBirdCount).await?;
+
     // Insert into bird_count_history
     let new_record = bird_count_history::ActiveModel {
         batch_id: Set(payload.batch_id),
@@ -192,12 +232,6 @@ pub async fn create_bird_count_history(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let batch = batches::Entity::find_by_id(payload.batch_id)
-        .one(&txn)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
-
     let new_count = batch.current_bird_count.unwrap_or(0) + payload.additions - payload.deaths;
 
     let mut batch_model: batches::ActiveModel = batch.into();
@@ -219,7 +253,9 @@ pub async fn create_bird_count_history(
 pub async fn create_bird_sell_history(
     State(db): State<DatabaseConnection>,
     Json(payload): Json<CreateBirdSellHistory>,
-) -> Result<Json<bird_sell_history::Model>, StatusCode> {
+) -> Result<Json<bird_sell_history::Model>, BatchWriteError> {
+    ensure_batch_allows(&db, payload.batch_id, BatchOperation::RecordSale).await?;
+
     let new_sale = bird_sell_history::ActiveModel {
         batch_id: Set(payload.batch_id),
         trader_id: Set(payload.trader_id),
@@ -234,58 +270,207 @@ pub async fn create_bird_sell_history(
         .insert(&db)
         .await
         .map(Json)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into())
 }
 
+/// Equity account the other side of an opening balance is posted to
+const OPENING_BALANCE_ACCOUNT: &str = "opening balance equity";
+
 pub async fn create_ledger_account(
     State(db): State<DatabaseConnection>,
     Json(payload): Json<CreateLedgerAccount>,
 ) -> Result<Json<ledger_accounts::Model>, StatusCode> {
-    let new_account = ledger_accounts::ActiveModel {
-        name: Set((payload.name).to_lowercase()),
+    let txn = db.begin().await.map_err(|e| {
+        error!("Failed to start transaction: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let account = open_ledger_account(&txn, payload).await?;
+
+    txn.commit().await.map_err(|e| {
+        error!("Failed to commit transaction: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(account))
+}
+
+/// Create the account at zero and post any opening balance as a journal
+/// against the opening balance equity account, so the balance is backed by
+/// ledger entries like every other.
+async fn open_ledger_account<C: ConnectionTrait>(
+    txn: &C,
+    payload: CreateLedgerAccount,
+) -> Result<ledger_accounts::Model, StatusCode> {
+    let name = payload.name.to_lowercase();
+    let opening = payload.current_balance;
+    if opening != Decimal::ZERO && name == OPENING_BALANCE_ACCOUNT {
+        eprintln!("Refused an opening balance on the opening balance equity account");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let account = ledger_accounts::ActiveModel {
+        name: Set(name),
         account_type: Set(payload.account_type),
-        current_balance: Set(payload.current_balance),
+        current_balance: Set(Decimal::ZERO),
         ..Default::default()
-    };
-
-    new_account.insert(&db).await.map(Json).map_err(|err| {
+    }
+    .insert(txn)
+    .await
+    .map_err(|err| {
         eprintln!("Failed to insert ledger account: {:?}", err);
         StatusCode::INTERNAL_SERVER_ERROR
-    })
+    })?;
+
+    if opening == Decimal::ZERO {
+        return Ok(account);
+    }
+
+    let today = Utc::now().date_naive();
+    ensure_period_open(txn, today)
+        .await
+        .map_err(period_status)?;
+
+    let equity = opening_balance_account(txn).await.map_err(|err| {
+        eprintln!("Failed to find the opening balance account: {:?}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // A positive balance sits on the account's normal side
+    let debit_normal =
+        normal_balance(&account.account_type, Decimal::ONE, Decimal::ZERO) > Decimal::ZERO;
+    let amount = opening.abs();
+    let (debit, credit) = if debit_normal == (opening > Decimal::ZERO) {
+        (Some(amount), None)
+    } else {
+        (None, Some(amount))
+    };
+    post_journal(
+        txn,
+        &CreateJournalVoucher {
+            txn_date: today,
+            narration: Some(format!("Opening balance for {}", account.name)),
+            reference_table: Some("ledger_accounts".into()),
+            reference_id: Some(account.account_id),
+            created_by: None,
+            lines: vec![
+                JournalLine {
+                    account_id: account.account_id,
+                    debit,
+                    credit,
+                    narration: None,
+                },
+                JournalLine {
+                    account_id: equity.account_id,
+                    debit: credit,
+                    credit: debit,
+                    narration: None,
+                },
+            ],
+        },
+    )
+    .await
+    .map_err(|err| {
+        eprintln!("Failed to post opening balance: {}", err);
+        err.status()
+    })?;
+
+    ledger_accounts::Entity::find_by_id(account.account_id)
+        .one(txn)
+        .await
+        .map_err(internal_error("fetch ledger account"))?
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// The opening balance equity account, created the first time it is needed.
+async fn opening_balance_account<C: ConnectionTrait>(
+    conn: &C,
+) -> Result<ledger_accounts::Model, DbErr> {
+    let existing = ledger_accounts::Entity::find()
+        .filter(ledger_accounts::Column::Name.eq(OPENING_BALANCE_ACCOUNT))
+        .one(conn)
+        .await?;
+    if let Some(account) = existing {
+        return Ok(account);
+    }
+
+    ledger_accounts::ActiveModel {
+        name: Set(OPENING_BALANCE_ACCOUNT.to_string()),
+        account_type: Set(LedgerAccountType::Equity),
+        current_balance: Set(Decimal::ZERO),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
 }
 
 pub async fn create_farmer_commission(
     State(db): State<DatabaseConnection>,
     Json(payload): Json<CreateFarmerCommission>,
-) -> Result<Json<farmer_commission_history::Model>, StatusCode> {
-    const CASH_ACCOUNT_ID: i32 = 101;
-    const COMMISSION_EXPENSE_ACCOUNT_ID: i32 = 106;
-
+) -> Result<Json<farmer_commission_history::Model>, BatchWriteError> {
     let txn = db.begin().await.map_err(|e| {
         error!("Failed to start transaction: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    let saved_commission = record_farmer_commission(&txn, payload).await?;
+
+    txn.commit().await.map_err(|e| {
+        error!("Failed to commit transaction: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(saved_commission))
+}
+
+/// Book a farmer commission: the history row, its ledger entries and, for a
+/// closed batch, the cost sheet. Shared with grower settlement.
+pub async fn record_farmer_commission<C: TransactionTrait + ConnectionTrait>(
+    txn: &C,
+    payload: CreateFarmerCommission,
+) -> Result<farmer_commission_history::Model, BatchWriteError> {
+    let today = chrono::Local::now().date_naive();
+    ensure_period_open(txn, today)
+        .await
+        .map_err(period_status)?;
+
+    let rule = resolve_posting_rule(txn, PostingEvent::FarmerCommission, None)
+        .await
+        .map_err(|e| {
+            error!("Failed to resolve farmer commission posting rule: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let commission_expense_account_id = rule.debit_account_id;
+    let cash_account_id = rule.credit_account_id;
+
+    // A commission tied to a batch must be for that batch's farmer
+    if let Some(batch_id) = payload.batch_id {
+        let batch = ensure_batch_allows(txn, batch_id, BatchOperation::RecordCommission).await?;
+        if batch.farmer_id != payload.farmer_id {
+            return Err(StatusCode::BAD_REQUEST.into());
+        }
+    }
+
     // 1) insert farmer commission history
     let new_commission = farmer_commission_history::ActiveModel {
         farmer_id: Set(payload.farmer_id),
         commission_amount: Set(payload.commission_amount),
         description: Set(payload.description),
         created_at: Set(Utc::now().into()),
+        batch_id: Set(payload.batch_id),
         ..Default::default()
     };
 
-    let saved_commission = new_commission.insert(&txn).await.map_err(|e| {
+    let saved_commission = new_commission.insert(txn).await.map_err(|e| {
         error!("Failed to insert farmer commission history: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
     // 2) create ledger entries
     let txn_group_id = Uuid::new_v4();
-    let today = chrono::Local::now().date_naive();
 
     let debit_entry = ledger_entries::ActiveModel {
-        account_id: Set(COMMISSION_EXPENSE_ACCOUNT_ID),
+        account_id: Set(commission_expense_account_id),
         debit: Set(Some(payload.commission_amount)),
         credit: Set(None),
         txn_date: Set(today),
@@ -298,7 +483,7 @@ pub async fn create_farmer_commission(
     };
 
     let credit_entry = ledger_entries::ActiveModel {
-        account_id: Set(CASH_ACCOUNT_ID),
+        account_id: Set(cash_account_id),
         debit: Set(None),
         credit: Set(Some(payload.commission_amount)),
         txn_date: Set(today),
@@ -310,192 +495,35 @@ pub async fn create_farmer_commission(
         ..Default::default()
     };
 
-    debit_entry.insert(&txn).await.map_err(|e| {
+    debit_entry.insert(txn).await.map_err(|e| {
         error!("Failed to insert debit ledger entry: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    credit_entry.insert(&txn).await.map_err(|e| {
+    credit_entry.insert(txn).await.map_err(|e| {
         error!("Failed to insert credit ledger entry: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    // 3) update balances
-    let mut commission_acct: ledger_accounts::ActiveModel =
-        ledger_accounts::Entity::find_by_id(COMMISSION_EXPENSE_ACCOUNT_ID)
-            .one(&txn)
+    // A commission booked after closing belongs on the batch's cost sheet
+    if let Some(batch_id) = payload.batch_id {
+        add_commission_to_summary(txn, batch_id, payload.commission_amount)
             .await
             .map_err(|e| {
-                error!("Failed to fetch commission account: {:?}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?
-            .ok_or_else(|| {
-                error!(
-                    "Commission account not found: {}",
-                    COMMISSION_EXPENSE_ACCOUNT_ID
-                );
+                error!("Failed to update batch closure summary: {:?}", e);
                 StatusCode::INTERNAL_SERVER_ERROR
-            })?
-            .into();
-
-    commission_acct.current_balance =
-        Set(commission_acct.current_balance.unwrap() + payload.commission_amount);
-
-    commission_acct.update(&txn).await.map_err(|e| {
-        error!("Failed to update commission account balance: {:?}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let mut cash_acct: ledger_accounts::ActiveModel =
-        ledger_accounts::Entity::find_by_id(CASH_ACCOUNT_ID)
-            .one(&txn)
-            .await
-            .map_err(|e| {
-                error!("Failed to fetch cash account: {:?}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?
-            .ok_or_else(|| {
-                error!("Cash account not found: {}", CASH_ACCOUNT_ID);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?
-            .into();
-
-    cash_acct.current_balance = Set(cash_acct.current_balance.unwrap() - payload.commission_amount);
-
-    cash_acct.update(&txn).await.map_err(|e| {
-        error!("Failed to update cash account balance: {:?}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    txn.commit().await.map_err(|e| {
-        error!("Failed to commit transaction: {:?}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    Ok(Json(saved_commission))
-}
-
-pub async fn create_batch_closure_summary(
-    State(db): State<DatabaseConnection>,
-    Json(payload): Json<CreateBatchClosureSummary>,
-) -> Result<Json<batch_closure_summary::Model>, StatusCode> {
-    let txn = db
-        .begin()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let new_record = batch_closure_summary::ActiveModel {
-        batch_id: Set(payload.batch_id),
-        start_date: Set(payload.start_date),
-        end_date: Set(payload.end_date),
-        initial_chicken_count: Set(payload.initial_chicken_count),
-        available_chicken_count: Set(payload.available_chicken_count),
-        revenue: Set(payload.revenue),
-        gross_profit: Set(payload.gross_profit),
-        ..Default::default()
-    };
-
-    let inserted = new_record
-        .insert(&txn)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let mut batch: batches::ActiveModel = batches::Entity::find_by_id(payload.batch_id)
-        .one(&txn)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?
-        .into();
-
-    batch.status = Set(BatchStatus::Closed);
-
-    batch
-        .update(&txn)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    txn.commit()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    Ok(Json(inserted))
-}
-
-pub async fn create_ledger_entry(
-    State(db): State<DatabaseConnection>,
-    Json(payload): Json<CreateLedgerEntry>,
-) -> Result<Json<ledger_entries::Model>, StatusCode> {
-    // Validate presence and non-negativity of amounts
-    let debit_val = payload.debit.unwrap_or(Decimal::ZERO);
-    let credit_val = payload.credit.unwrap_or(Decimal::ZERO);
-
-    if debit_val == Decimal::ZERO && credit_val == Decimal::ZERO {
-        return Err(StatusCode::BAD_REQUEST);
+            })?;
     }
-    if debit_val < Decimal::ZERO || credit_val < Decimal::ZERO {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
-    // Start transaction
-    let txn = db
-        .begin()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    // 1. Fetch account (within transaction to keep consistent read)
-    let account = ledger_accounts::Entity::find_by_id(payload.account_id)
-        .one(&txn)
-        .await
-        .map_err(|_| {
-            // try to rollback if fetch fails (best-effort)
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or_else(|| StatusCode::NOT_FOUND)?;
-
-    // 2. Compute delta depending on account type
-    //    For Asset/Expense: delta = debit - credit
-    //    For Liability/Equity/Revenue: delta = credit - debit
-    let delta: Decimal = match account.account_type {
-        LedgerAccountType::Asset | LedgerAccountType::Expense => debit_val - credit_val,
-        LedgerAccountType::Liability | LedgerAccountType::Equity | LedgerAccountType::Revenue => {
-            credit_val - debit_val
-        }
-    };
 
-    let new_balance = account.current_balance + delta;
-
-    // 3. Build and insert ledger entry (stores both debit and credit as provided)
-    let new_entry = ledger_entries::ActiveModel {
-        account_id: Set(payload.account_id),
-        debit: Set(payload.debit),
-        credit: Set(payload.credit),
-        txn_date: Set(payload.txn_date),
-        reference_table: Set(payload.reference_table.clone()),
-        reference_id: Set(payload.reference_id),
-        narration: Set(payload.narration.clone()),
-        txn_group_id: Set(Uuid::new_v4()),
-        created_at: Set(Utc::now().into()),
-        created_by: Set(payload.created_by),
-        ..Default::default()
-    };
-
-    let inserted_entry = new_entry
-        .insert(&txn)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    // 4. Update account balance
-    let mut account_am: ledger_accounts::ActiveModel = account.into();
-    account_am.current_balance = Set(new_balance);
-    account_am
-        .update(&txn)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    // 5. Commit transaction
-    txn.commit()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    Ok(Json(inserted_entry))
+    // 3) update balances
+    update_account_balance(
+        txn,
+        commission_expense_account_id,
+        Some(payload.commission_amount),
+        true,
+    )
+    .await?;
+    update_account_balance(txn, cash_account_id, Some(payload.commission_amount), false).await?;
+
+    Ok(saved_commission)
 }
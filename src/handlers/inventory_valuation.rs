@@ -0,0 +1,373 @@
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Days, NaiveDate, Utc};
+use entity::sea_orm_active_enums::{ItemCategory, PostingEvent};
+use entity::{
+    batch_allocation_lines, batch_allocations, inventory, inventory_movements, items, purchases,
+    stock_adjustment_lines, stock_adjustments, stock_receipts,
+};
+use sea_orm::prelude::{DateTimeWithTimeZone, Decimal};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+
+use crate::handlers::posting_rules::{resolve_posting_rule, PostingRuleError};
+use crate::handlers::purchases::internal_error;
+use crate::handlers::reports::{account_balances, add_to_ageing, csv_response, csv_row};
+use crate::handlers::units::{display_factors, from_base_quantity};
+use crate::models::{
+    AgeingBuckets, InventoryAccountCheck, InventoryValuationCategory, InventoryValuationLine,
+    InventoryValuationQuery, InventoryValuationResponse, ReportFormat,
+};
+
+/// Start of the day after `as_of`, in UTC; anything stamped at or after it
+/// happened after the valuation date. `None` for the last representable day.
+fn end_of_day(as_of: NaiveDate) -> Option<DateTimeWithTimeZone> {
+    as_of
+        .checked_add_days(Days::new(1))?
+        .and_hms_opt(0, 0, 0)
+        .map(|midnight| midnight.and_utc().into())
+}
+
+/// Quantity each lot has lost since `as_of`, so that adding it back to
+/// `remaining_qty` gives what the lot held on that date. Allocations made
+/// after the date are added back; allocations voided after the date had
+/// their stock returned to the lot, so they are taken off again. Voided
+/// purchases emptied their lot without an allocation line.
+async fn consumed_since<C: ConnectionTrait>(
+    conn: &C,
+    as_of: NaiveDate,
+    cutoff: DateTimeWithTimeZone,
+) -> Result<HashMap<i32, Decimal>, DbErr> {
+    let mut consumed: HashMap<i32, Decimal> = HashMap::new();
+
+    let allocations = batch_allocations::Entity::find()
+        .filter(
+            Condition::any()
+                .add(batch_allocations::Column::AllocationDate.gt(as_of))
+                .add(batch_allocations::Column::VoidedAt.gte(cutoff)),
+        )
+        .all(conn)
+        .await?;
+    let mut factors: HashMap<i32, Decimal> = HashMap::new();
+    for allocation in &allocations {
+        let mut factor = Decimal::ZERO;
+        if allocation.allocation_date > as_of {
+            factor += Decimal::ONE;
+        }
+        if allocation.voided_at.is_some_and(|at| at >= cutoff) {
+            factor -= Decimal::ONE;
+        }
+        if factor != Decimal::ZERO {
+            factors.insert(allocation.allocation_id, factor);
+        }
+    }
+    if !factors.is_empty() {
+        for line in batch_allocation_lines::Entity::find()
+            .filter(
+                batch_allocation_lines::Column::AllocationId
+                    .is_in(factors.keys().copied().collect::<Vec<_>>()),
+            )
+            .all(conn)
+            .await?
+        {
+            *consumed.entry(line.lot_id).or_default() += line.qty * factors[&line.allocation_id];
+        }
+    }
+
+    // Only stock losses write lines; gains open a lot dated on the adjustment
+    let adjustment_ids: Vec<i32> = stock_adjustments::Entity::find()
+        .filter(stock_adjustments::Column::AdjustmentDate.gt(as_of))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|a| a.adjustment_id)
+        .collect();
+    if !adjustment_ids.is_empty() {
+        for line in stock_adjustment_lines::Entity::find()
+            .filter(stock_adjustment_lines::Column::AdjustmentId.is_in(adjustment_ids))
+            .all(conn)
+            .await?
+        {
+            *consumed.entry(line.lot_id).or_default() += line.qty;
+        }
+    }
+
+    let voided_purchase_ids: Vec<i32> = purchases::Entity::find()
+        .filter(purchases::Column::VoidedAt.gte(cutoff))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|p| p.purchase_id)
+        .collect();
+    if !voided_purchase_ids.is_empty() {
+        for lot in stock_receipts::Entity::find()
+            .filter(stock_receipts::Column::PurchaseId.is_in(voided_purchase_ids))
+            .all(conn)
+            .await?
+        {
+            *consumed.entry(lot.lot_id).or_default() += lot.received_qty - lot.remaining_qty;
+        }
+    }
+
+    Ok(consumed)
+}
+
+/// Each item's inventory quantity as of `cutoff`: today's quantity less every
+/// movement recorded since. Transfers net to zero across locations.
+async fn book_quantities<C: ConnectionTrait>(
+    conn: &C,
+    cutoff: DateTimeWithTimeZone,
+) -> Result<HashMap<String, Decimal>, DbErr> {
+    let mut book: HashMap<String, Decimal> = inventory::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|inv| (inv.item_code, inv.current_qty))
+        .collect();
+
+    for movement in inventory_movements::Entity::find()
+        .filter(inventory_movements::Column::MovementDate.gte(cutoff))
+        .all(conn)
+        .await?
+    {
+        *book.entry(movement.item_code).or_default() -= movement.qty_change;
+    }
+
+    Ok(book)
+}
+
+/// Value stock on hand at `as_of` lot by lot at each lot's unit cost, and
+/// check it against the inventory quantities and the inventory asset
+/// accounts in the ledger. `cutoff` is the end of that day, see
+/// [`end_of_day`].
+pub async fn inventory_valuation<C: ConnectionTrait>(
+    conn: &C,
+    as_of: NaiveDate,
+    cutoff: DateTimeWithTimeZone,
+) -> Result<InventoryValuationResponse, PostingRuleError> {
+    let all_items = items::Entity::find()
+        .order_by_asc(items::Column::ItemCode)
+        .all(conn)
+        .await?;
+    let consumed = consumed_since(conn, as_of, cutoff).await?;
+    let book = book_quantities(conn, cutoff).await?;
+
+    let mut lots_by_item: HashMap<String, Vec<(stock_receipts::Model, Decimal)>> = HashMap::new();
+    for lot in stock_receipts::Entity::find()
+        .filter(stock_receipts::Column::ReceivedDate.lte(as_of))
+        .all(conn)
+        .await?
+    {
+        let qty = lot.remaining_qty + consumed.get(&lot.lot_id).copied().unwrap_or_default();
+        if qty > Decimal::ZERO {
+            lots_by_item
+                .entry(lot.item_code.clone())
+                .or_default()
+                .push((lot, qty));
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut categories: Vec<InventoryValuationCategory> = Vec::new();
+    let mut total_ageing = AgeingBuckets::default();
+    for item in all_items {
+        let lots = lots_by_item.remove(&item.item_code).unwrap_or_default();
+        let book_qty = book.get(&item.item_code).copied().unwrap_or_default();
+        if lots.is_empty() && book_qty == Decimal::ZERO {
+            continue;
+        }
+
+        let mut ageing = AgeingBuckets::default();
+        let mut lot_qty = Decimal::ZERO;
+        let mut value = Decimal::ZERO;
+        for (lot, qty) in &lots {
+            let lot_value = qty * lot.unit_cost;
+            let age_days = (as_of - lot.received_date).num_days();
+            add_to_ageing(&mut ageing, age_days, lot_value);
+            add_to_ageing(&mut total_ageing, age_days, lot_value);
+            lot_qty += qty;
+            value += lot_value;
+        }
+
+        match categories
+            .iter_mut()
+            .find(|c| c.item_category == item.item_category)
+        {
+            Some(category) => {
+                category.value += value;
+                add_ageing(&mut category.ageing, &ageing);
+            }
+            None => categories.push(InventoryValuationCategory {
+                item_category: item.item_category.clone(),
+                value,
+                ageing,
+            }),
+        }
+
+        lines.push(InventoryValuationLine {
+            item_code: item.item_code,
+            item_name: item.item_name,
+            item_category: item.item_category,
+            unit: item.unit,
+            lot_qty,
+            value,
+            lot_count: lots.len(),
+            ageing,
+            book_qty,
+            qty_difference: book_qty - lot_qty,
+        });
+    }
+
+    // Categories sharing an inventory account are checked together
+    let mut by_account: BTreeMap<i32, (Vec<ItemCategory>, Decimal)> = BTreeMap::new();
+    for category in &categories {
+        let rule = match resolve_posting_rule(
+            conn,
+            PostingEvent::Purchase,
+            Some(category.item_category.clone()),
+        )
+        .await
+        {
+            Ok(rule) => rule,
+            Err(PostingRuleError::NotConfigured { .. }) => continue,
+            Err(e) => return Err(e),
+        };
+        let entry = by_account.entry(rule.debit_account_id).or_default();
+        entry.0.push(category.item_category.clone());
+        entry.1 += category.value;
+    }
+
+    let balances: HashMap<i32, _> = account_balances(conn, None, as_of, true)
+        .await?
+        .into_iter()
+        .map(|(account, balance)| (account.account_id, (account.name, balance)))
+        .collect();
+    let accounts: Vec<InventoryAccountCheck> = by_account
+        .into_iter()
+        .map(|(account_id, (item_categories, stock_value))| {
+            let (account_name, ledger_balance) = balances
+                .get(&account_id)
+                .cloned()
+                .unwrap_or_else(|| (String::new(), Decimal::ZERO));
+            InventoryAccountCheck {
+                account_id,
+                account_name,
+                item_categories,
+                stock_value,
+                ledger_balance,
+                difference: ledger_balance - stock_value,
+            }
+        })
+        .collect();
+
+    let reconciled = lines.iter().all(|l| l.qty_difference == Decimal::ZERO)
+        && accounts.iter().all(|a| a.difference == Decimal::ZERO);
+
+    Ok(InventoryValuationResponse {
+        as_of,
+        total_value: categories.iter().map(|c| c.value).sum(),
+        items: lines,
+        categories,
+        accounts,
+        ageing: total_ageing,
+        reconciled,
+    })
+}
+
+fn add_ageing(total: &mut AgeingBuckets, other: &AgeingBuckets) {
+    total.days_0_30 += other.days_0_30;
+    total.days_31_60 += other.days_31_60;
+    total.days_61_90 += other.days_61_90;
+    total.days_over_90 += other.days_over_90;
+}
+
+pub async fn get_inventory_valuation_handler(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<InventoryValuationQuery>,
+) -> Result<Response, StatusCode> {
+    let as_of = query.as_of.unwrap_or_else(|| Utc::now().date_naive());
+    let cutoff = end_of_day(as_of).ok_or(StatusCode::BAD_REQUEST)?;
+    let mut report = inventory_valuation(&db, as_of, cutoff)
+        .await
+        .map_err(internal_error("value inventory"))?;
+
+    if let Some(unit) = query.unit.as_deref() {
+        let factors = display_factors(&db, unit)
+            .await
+            .map_err(internal_error("fetch unit conversions"))?;
+        for line in &mut report.items {
+            let Some(&factor) = factors.get(&line.item_code) else {
+                continue;
+            };
+            for qty in [
+                &mut line.lot_qty,
+                &mut line.book_qty,
+                &mut line.qty_difference,
+            ] {
+                *qty = from_base_quantity(*qty, factor);
+            }
+            line.unit = Some(unit.to_string());
+        }
+    }
+
+    if query.format == ReportFormat::Csv {
+        let mut rows = vec![csv_row([
+            "item_code",
+            "item_name",
+            "item_category",
+            "unit",
+            "lot_qty",
+            "value",
+            "days_0_30",
+            "days_31_60",
+            "days_61_90",
+            "days_over_90",
+            "book_qty",
+            "qty_difference",
+        ])];
+        for line in &report.items {
+            rows.push(csv_row([
+                line.item_code.clone(),
+                line.item_name.clone(),
+                format!("{:?}", line.item_category),
+                line.unit.clone().unwrap_or_default(),
+                line.lot_qty.to_string(),
+                line.value.to_string(),
+                line.ageing.days_0_30.to_string(),
+                line.ageing.days_31_60.to_string(),
+                line.ageing.days_61_90.to_string(),
+                line.ageing.days_over_90.to_string(),
+                line.book_qty.to_string(),
+                line.qty_difference.to_string(),
+            ]));
+        }
+        rows.push(csv_row([
+            "TOTAL".to_string(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            report.total_value.to_string(),
+            report.ageing.days_0_30.to_string(),
+            report.ageing.days_31_60.to_string(),
+            report.ageing.days_61_90.to_string(),
+            report.ageing.days_over_90.to_string(),
+            String::new(),
+            String::new(),
+        ]));
+        return Ok(csv_response(
+            &format!("inventory_valuation_{}.csv", as_of),
+            rows,
+        ));
+    }
+
+    Ok(Json(report).into_response())
+}
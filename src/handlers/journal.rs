@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::Utc;
+use entity::{ledger_accounts, ledger_entries};
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::handlers::periods::ensure_period_open;
+use crate::handlers::reports::normal_balance;
+use crate::models::{
+    CreateJournalVoucher, JournalEntryLine, JournalLine, JournalResponse, ResponseMessage,
+};
+
+#[derive(Debug, Error)]
+pub enum JournalError {
+    #[error("A journal voucher needs at least two lines")]
+    TooFewLines,
+    #[error("Line {0} must carry exactly one positive debit or credit")]
+    InvalidLine(usize),
+    #[error("Voucher is unbalanced: debits {debit} do not equal credits {credit}")]
+    Unbalanced { debit: Decimal, credit: Decimal },
+    #[error("Ledger account {0} not found")]
+    AccountNotFound(i32),
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl JournalError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            JournalError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Check that every line is one-sided and positive and that the voucher
+/// balances. Returns the voucher total.
+pub fn validate_lines(lines: &[JournalLine]) -> Result<Decimal, JournalError> {
+    if lines.len() < 2 {
+        return Err(JournalError::TooFewLines);
+    }
+
+    let mut total_debit = Decimal::ZERO;
+    let mut total_credit = Decimal::ZERO;
+
+    for (idx, line) in lines.iter().enumerate() {
+        match (line.debit, line.credit) {
+            (Some(debit), None) if debit > Decimal::ZERO => total_debit += debit,
+            (None, Some(credit)) if credit > Decimal::ZERO => total_credit += credit,
+            _ => return Err(JournalError::InvalidLine(idx + 1)),
+        }
+    }
+
+    if total_debit != total_credit {
+        return Err(JournalError::Unbalanced {
+            debit: total_debit,
+            credit: total_credit,
+        });
+    }
+
+    Ok(total_debit)
+}
+
+/// Post a balanced voucher under a fresh `txn_group_id` and move the account
+/// balances with it. Run it inside a transaction so a failure leaves nothing
+/// half-posted.
+pub async fn post_journal<C: ConnectionTrait>(
+    conn: &C,
+    voucher: &CreateJournalVoucher,
+) -> Result<Uuid, JournalError> {
+    validate_lines(&voucher.lines)?;
+
+    let account_ids: Vec<i32> = voucher.lines.iter().map(|l| l.account_id).collect();
+    let accounts: HashMap<i32, ledger_accounts::Model> = ledger_accounts::Entity::find()
+        .filter(ledger_accounts::Column::AccountId.is_in(account_ids))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|a| (a.account_id, a))
+        .collect();
+
+    let txn_group_id = Uuid::new_v4();
+    let mut deltas: HashMap<i32, Decimal> = HashMap::new();
+
+    for line in &voucher.lines {
+        let account = accounts
+            .get(&line.account_id)
+            .ok_or(JournalError::AccountNotFound(line.account_id))?;
+
+        let entry = ledger_entries::ActiveModel {
+            account_id: Set(line.account_id),
+            debit: Set(line.debit),
+            credit: Set(line.credit),
+            txn_date: Set(voucher.txn_date),
+            reference_table: Set(voucher.reference_table.clone()),
+            reference_id: Set(voucher.reference_id),
+            narration: Set(line.narration.clone().or_else(|| voucher.narration.clone())),
+            txn_group_id: Set(txn_group_id),
+            created_at: Set(Utc::now().into()),
+            created_by: Set(voucher.created_by),
+            ..Default::default()
+        };
+        entry.insert(conn).await?;
+
+        *deltas.entry(line.account_id).or_default() += normal_balance(
+            &account.account_type,
+            line.debit.unwrap_or_default(),
+            line.credit.unwrap_or_default(),
+        );
+    }
+
+    for (account_id, delta) in deltas {
+        let account = accounts[&account_id].clone();
+        let new_balance = account.current_balance + delta;
+        let mut active: ledger_accounts::ActiveModel = account.into();
+        active.current_balance = Set(new_balance);
+        active.update(conn).await?;
+    }
+
+    Ok(txn_group_id)
+}
+
+/// Both sides of a posted transaction, or `None` if the group has no entries.
+pub async fn load_journal<C: ConnectionTrait>(
+    conn: &C,
+    txn_group_id: Uuid,
+) -> Result<Option<JournalResponse>, DbErr> {
+    let entries = ledger_entries::Entity::find()
+        .filter(ledger_entries::Column::TxnGroupId.eq(txn_group_id))
+        .find_also_related(ledger_accounts::Entity)
+        .order_by_asc(ledger_entries::Column::EntryId)
+        .all(conn)
+        .await?;
+
+    let Some((first, _)) = entries.first() else {
+        return Ok(None);
+    };
+    let txn_date = first.txn_date;
+
+    let total_debit: Decimal = entries.iter().filter_map(|(e, _)| e.debit).sum();
+    let total_credit: Decimal = entries.iter().filter_map(|(e, _)| e.credit).sum();
+
+    let entries = entries
+        .into_iter()
+        .map(|(e, account)| JournalEntryLine {
+            entry_id: e.entry_id,
+            account_id: e.account_id,
+            account_name: account.map(|a| a.name),
+            debit: e.debit,
+            credit: e.credit,
+            narration: e.narration,
+            reference_table: e.reference_table,
+            reference_id: e.reference_id,
+            created_by: e.created_by,
+            created_at: e.created_at,
+        })
+        .collect();
+
+    Ok(Some(JournalResponse {
+        txn_group_id,
+        txn_date,
+        entries,
+        total_debit,
+        total_credit,
+        is_balanced: total_debit == total_credit,
+    }))
+}
+
+pub async fn create_journal_voucher(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateJournalVoucher>,
+) -> Result<Json<JournalResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let txn = db.begin().await.map_err(journal_error)?;
+
+    ensure_period_open(&txn, payload.txn_date)
+        .await
+        .map_err(|e| {
+            eprintln!("Posting refused: {}", e);
+            (
+                e.status(),
+                Json(ResponseMessage {
+                    message: e.to_string(),
+                }),
+            )
+        })?;
+
+    let txn_group_id = post_journal(&txn, &payload).await.map_err(|e| {
+        eprintln!("Failed to post journal voucher: {}", e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    })?;
+
+    let journal = load_journal(&txn, txn_group_id)
+        .await
+        .map_err(journal_error)?
+        .ok_or_else(|| journal_error("posted journal not found"))?;
+
+    txn.commit().await.map_err(journal_error)?;
+
+    Ok(Json(journal))
+}
+
+fn journal_error<E: std::fmt::Display>(err: E) -> (StatusCode, Json<ResponseMessage>) {
+    eprintln!("Journal voucher failed: {}", err);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ResponseMessage {
+            message: "Failed to post journal voucher".to_string(),
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn debit(account_id: i32, amount: i64) -> JournalLine {
+        JournalLine {
+            account_id,
+            debit: Some(Decimal::from(amount)),
+            credit: None,
+            narration: None,
+        }
+    }
+
+    fn credit(account_id: i32, amount: i64) -> JournalLine {
+        JournalLine {
+            account_id,
+            debit: None,
+            credit: Some(Decimal::from(amount)),
+            narration: None,
+        }
+    }
+
+    #[test]
+    fn balanced_voucher_returns_total() {
+        let lines = [debit(1, 70), debit(2, 30), credit(3, 100)];
+        assert_eq!(validate_lines(&lines).unwrap(), Decimal::from(100));
+    }
+
+    #[test]
+    fn single_line_is_too_few() {
+        assert!(matches!(
+            validate_lines(&[debit(1, 10)]),
+            Err(JournalError::TooFewLines)
+        ));
+    }
+
+    #[test]
+    fn unbalanced_voucher_is_refused() {
+        let lines = [debit(1, 100), credit(2, 90)];
+        match validate_lines(&lines) {
+            Err(JournalError::Unbalanced { debit, credit }) => {
+                assert_eq!(debit, Decimal::from(100));
+                assert_eq!(credit, Decimal::from(90));
+            }
+            other => panic!("expected an unbalanced voucher, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn negative_and_zero_amounts_are_refused() {
+        let lines = [debit(1, -10), credit(2, -10)];
+        assert!(matches!(
+            validate_lines(&lines),
+            Err(JournalError::InvalidLine(1))
+        ));
+
+        let lines = [debit(1, 10), credit(2, 10), credit(3, 0)];
+        assert!(matches!(
+            validate_lines(&lines),
+            Err(JournalError::InvalidLine(3))
+        ));
+    }
+
+    #[test]
+    fn lines_must_be_single_sided() {
+        let both = JournalLine {
+            account_id: 2,
+            debit: Some(Decimal::from(10)),
+            credit: Some(Decimal::from(10)),
+            narration: None,
+        };
+        let neither = JournalLine {
+            account_id: 3,
+            debit: None,
+            credit: None,
+            narration: None,
+        };
+        assert!(matches!(
+            validate_lines(&[debit(1, 10), both]),
+            Err(JournalError::InvalidLine(2))
+        ));
+        assert!(matches!(
+            validate_lines(&[neither, credit(1, 10)]),
+            Err(JournalError::InvalidLine(1))
+        ));
+    }
+}
@@ -0,0 +1,495 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use entity::sea_orm_active_enums::{LocationType, MovementType};
+use entity::{
+    farmers, inventory_locations, inventory_movements, items, stock_receipts, stock_transfers,
+    storage_locations,
+};
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use thiserror::Error;
+
+use crate::handlers::costing::issue_order_lots;
+use crate::handlers::periods::{ensure_period_open, PeriodError};
+use crate::models::{CreateStockTransfer, CreateStorageLocation, ResponseMessage};
+
+#[derive(Debug, Error)]
+pub enum LocationError {
+    #[error("Location {0} not found")]
+    NotFound(i32),
+    #[error("No default warehouse is configured")]
+    NoDefaultWarehouse,
+    #[error("Farmer {0} not found")]
+    FarmerNotFound(i32),
+    #[error("Item {0} not found")]
+    ItemNotFound(String),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Insufficient stock of {item_code} at location {location_id}: requested {requested}, available {available}")]
+    InsufficientStock {
+        item_code: String,
+        location_id: i32,
+        requested: Decimal,
+        available: Decimal,
+    },
+    #[error(transparent)]
+    Period(#[from] PeriodError),
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl LocationError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            LocationError::NotFound(_)
+            | LocationError::FarmerNotFound(_)
+            | LocationError::ItemNotFound(_) => StatusCode::NOT_FOUND,
+            LocationError::Invalid(_) | LocationError::InsufficientStock { .. } => {
+                StatusCode::BAD_REQUEST
+            }
+            LocationError::Period(e) => e.status(),
+            LocationError::NoDefaultWarehouse | LocationError::Db(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+/// Map a location error for handlers that answer with a bare status code.
+pub fn location_status(err: LocationError) -> StatusCode {
+    eprintln!("Stock location refused: {}", err);
+    err.status()
+}
+
+fn error_response(
+    action: &'static str,
+) -> impl FnOnce(LocationError) -> (StatusCode, Json<ResponseMessage>) {
+    move |e| {
+        eprintln!("Failed to {}: {}", action, e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    }
+}
+
+/// The named location, or the default warehouse when none is given.
+pub async fn resolve_location<C: ConnectionTrait>(
+    conn: &C,
+    location_id: Option<i32>,
+) -> Result<storage_locations::Model, LocationError> {
+    match location_id {
+        Some(id) => storage_locations::Entity::find_by_id(id)
+            .one(conn)
+            .await?
+            .ok_or(LocationError::NotFound(id)),
+        None => storage_locations::Entity::find()
+            .filter(storage_locations::Column::IsDefault.eq(true))
+            .one(conn)
+            .await?
+            .ok_or(LocationError::NoDefaultWarehouse),
+    }
+}
+
+/// The farmer's farm location, created the first time stock is sent there.
+pub async fn farm_location<C: ConnectionTrait>(
+    conn: &C,
+    farmer_id: i32,
+) -> Result<storage_locations::Model, LocationError> {
+    if let Some(location) = storage_locations::Entity::find()
+        .filter(storage_locations::Column::FarmerId.eq(farmer_id))
+        .one(conn)
+        .await?
+    {
+        return Ok(location);
+    }
+
+    let farmer = farmers::Entity::find_by_id(farmer_id)
+        .one(conn)
+        .await?
+        .ok_or(LocationError::FarmerNotFound(farmer_id))?;
+
+    Ok(storage_locations::ActiveModel {
+        name: Set(format!("{} farm #{}", farmer.name, farmer.farmer_id)),
+        location_type: Set(LocationType::Farm),
+        farmer_id: Set(Some(farmer.farmer_id)),
+        is_default: Set(false),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?)
+}
+
+/// Apply a quantity change to an item's stock at one location, refusing to
+/// take it below zero.
+pub async fn adjust_location_stock<C: ConnectionTrait>(
+    conn: &C,
+    item_code: &str,
+    location_id: i32,
+    qty_change: Decimal,
+) -> Result<(), LocationError> {
+    let existing = inventory_locations::Entity::find_by_id((item_code.to_string(), location_id))
+        .one(conn)
+        .await?;
+    let available = existing.as_ref().map(|s| s.current_qty).unwrap_or_default();
+
+    let new_qty = available + qty_change;
+    if new_qty < Decimal::ZERO {
+        return Err(LocationError::InsufficientStock {
+            item_code: item_code.to_string(),
+            location_id,
+            requested: -qty_change,
+            available,
+        });
+    }
+
+    match existing {
+        Some(stock) => {
+            let mut active: inventory_locations::ActiveModel = stock.into();
+            active.current_qty = Set(new_qty);
+            active.last_updated = Set(Utc::now().into());
+            active.update(conn).await?;
+        }
+        None => {
+            inventory_locations::ActiveModel {
+                item_code: Set(item_code.to_string()),
+                location_id: Set(location_id),
+                current_qty: Set(new_qty),
+                last_updated: Set(Utc::now().into()),
+            }
+            .insert(conn)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Where a transfer goes, how much, and why.
+pub struct TransferRequest<'a> {
+    pub item_code: &'a str,
+    pub from_location_id: i32,
+    pub to_location_id: i32,
+    pub quantity: Decimal,
+    pub value: Decimal,
+    pub transfer_date: NaiveDate,
+    pub allocation_id: Option<i32>,
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+}
+
+/// Record a transfer: the `stock_transfers` row, a pair of
+/// `MovementType::Transfer` movements out of one location and into the
+/// other, and both locations' stock. Lots are the caller's business.
+pub async fn record_transfer<C: ConnectionTrait>(
+    conn: &C,
+    request: TransferRequest<'_>,
+) -> Result<stock_transfers::Model, LocationError> {
+    if request.from_location_id == request.to_location_id {
+        return Err(LocationError::Invalid(
+            "Source and destination locations must differ".to_string(),
+        ));
+    }
+
+    adjust_location_stock(
+        conn,
+        request.item_code,
+        request.from_location_id,
+        -request.quantity,
+    )
+    .await?;
+    adjust_location_stock(
+        conn,
+        request.item_code,
+        request.to_location_id,
+        request.quantity,
+    )
+    .await?;
+
+    let transfer = stock_transfers::ActiveModel {
+        item_code: Set(request.item_code.to_string()),
+        from_location_id: Set(request.from_location_id),
+        to_location_id: Set(request.to_location_id),
+        quantity: Set(request.quantity),
+        value: Set(request.value),
+        transfer_date: Set(request.transfer_date),
+        allocation_id: Set(request.allocation_id),
+        notes: Set(request.notes),
+        created_by: Set(request.created_by),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    for (location_id, qty_change) in [
+        (request.from_location_id, -request.quantity),
+        (request.to_location_id, request.quantity),
+    ] {
+        inventory_movements::ActiveModel {
+            item_code: Set(request.item_code.to_string()),
+            movement_type: Set(MovementType::Transfer),
+            qty_change: Set(qty_change),
+            reference_id: Set(Some(transfer.transfer_id)),
+            movement_date: Set(Utc::now().into()),
+            location_id: Set(Some(location_id)),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+    }
+
+    Ok(transfer)
+}
+
+/// Send allocated stock to the batch's farm (`request.to_location_id`) and
+/// issue it there. The lots have already been drawn down at the source by
+/// the caller; stock allocated straight from the farm is only issued.
+pub async fn issue_to_farm<C: ConnectionTrait>(
+    conn: &C,
+    request: TransferRequest<'_>,
+) -> Result<(), LocationError> {
+    let (item_code, farm_location_id, quantity) =
+        (request.item_code, request.to_location_id, request.quantity);
+    if request.from_location_id != farm_location_id {
+        record_transfer(conn, request).await?;
+    }
+
+    adjust_location_stock(conn, item_code, farm_location_id, -quantity).await
+}
+
+/// Split lots at `from` into new lots at `to`, keeping their cost, receipt
+/// date and expiry. Lots are taken in the order they would be issued, and
+/// lots expired by `transfer_date` stay where they are. Returns the cost
+/// moved.
+async fn move_lots<C: ConnectionTrait>(
+    conn: &C,
+    item: &items::Model,
+    from_location_id: i32,
+    to_location_id: i32,
+    quantity: Decimal,
+    transfer_date: NaiveDate,
+) -> Result<Decimal, LocationError> {
+    let item_code = item.item_code.as_str();
+    let (receipts, _expired) = issue_order_lots(
+        conn,
+        item_code,
+        &item.item_category,
+        from_location_id,
+        Some(transfer_date),
+    )
+    .await?;
+
+    let in_lots: Decimal = receipts.iter().map(|r| r.remaining_qty).sum();
+    if in_lots < quantity {
+        return Err(LocationError::InsufficientStock {
+            item_code: item_code.to_string(),
+            location_id: from_location_id,
+            requested: quantity,
+            available: in_lots,
+        });
+    }
+
+    let mut to_move = quantity;
+    let mut total_value = Decimal::ZERO;
+
+    for receipt in receipts {
+        if to_move <= Decimal::ZERO {
+            break;
+        }
+
+        let take = std::cmp::min(receipt.remaining_qty, to_move);
+
+        stock_receipts::ActiveModel {
+            purchase_id: Set(receipt.purchase_id),
+            item_code: Set(receipt.item_code.clone()),
+            received_qty: Set(take),
+            remaining_qty: Set(take),
+            unit_cost: Set(receipt.unit_cost),
+            received_date: Set(receipt.received_date),
+            supplier: Set(receipt.supplier.clone()),
+            location_id: Set(to_location_id),
+            source_lot_id: Set(Some(receipt.lot_id)),
+            manufacturer_lot_no: Set(receipt.manufacturer_lot_no.clone()),
+            expiry_date: Set(receipt.expiry_date),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+
+        total_value += take * receipt.unit_cost;
+        to_move -= take;
+
+        let remaining = receipt.remaining_qty - take;
+        let mut active: stock_receipts::ActiveModel = receipt.into();
+        active.remaining_qty = Set(remaining);
+        active.update(conn).await?;
+    }
+
+    Ok(total_value)
+}
+
+pub async fn get_storage_locations_handler(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<Vec<storage_locations::Model>>, (StatusCode, Json<ResponseMessage>)> {
+    storage_locations::Entity::find()
+        .order_by_asc(storage_locations::Column::LocationId)
+        .all(&db)
+        .await
+        .map(Json)
+        .map_err(|e| error_response("fetch storage locations")(LocationError::from(e)))
+}
+
+pub async fn create_storage_location(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateStorageLocation>,
+) -> Result<Json<storage_locations::Model>, (StatusCode, Json<ResponseMessage>)> {
+    insert_storage_location(&db, payload)
+        .await
+        .map(Json)
+        .map_err(error_response("create storage location"))
+}
+
+async fn insert_storage_location<C: ConnectionTrait>(
+    conn: &C,
+    payload: CreateStorageLocation,
+) -> Result<storage_locations::Model, LocationError> {
+    if payload.name.trim().is_empty() {
+        return Err(LocationError::Invalid("Name is required".to_string()));
+    }
+    match (&payload.location_type, payload.farmer_id) {
+        (LocationType::Farm, None) => {
+            return Err(LocationError::Invalid(
+                "A farm location needs a farmer_id".to_string(),
+            ))
+        }
+        (LocationType::Warehouse, Some(_)) => {
+            return Err(LocationError::Invalid(
+                "A warehouse does not belong to a farmer".to_string(),
+            ))
+        }
+        (LocationType::Farm, Some(farmer_id)) => {
+            farmers::Entity::find_by_id(farmer_id)
+                .one(conn)
+                .await?
+                .ok_or(LocationError::FarmerNotFound(farmer_id))?;
+        }
+        (LocationType::Warehouse, None) => {}
+    }
+
+    Ok(storage_locations::ActiveModel {
+        name: Set(payload.name.trim().to_string()),
+        location_type: Set(payload.location_type),
+        farmer_id: Set(payload.farmer_id),
+        is_default: Set(false),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?)
+}
+
+pub async fn get_location_stock_handler(
+    Path(location_id): Path<i32>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<Vec<inventory_locations::Model>>, (StatusCode, Json<ResponseMessage>)> {
+    let fetch = async {
+        resolve_location(&db, Some(location_id)).await?;
+        Ok(inventory_locations::Entity::find()
+            .filter(inventory_locations::Column::LocationId.eq(location_id))
+            .filter(inventory_locations::Column::CurrentQty.ne(Decimal::ZERO))
+            .order_by_asc(inventory_locations::Column::ItemCode)
+            .all(&db)
+            .await?)
+    };
+
+    fetch
+        .await
+        .map(Json)
+        .map_err(error_response("fetch location stock"))
+}
+
+pub async fn create_stock_transfer(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateStockTransfer>,
+) -> Result<Json<stock_transfers::Model>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match transfer_stock(&txn, payload).await {
+            Ok(transfer) => txn
+                .commit()
+                .await
+                .map(|_| transfer)
+                .map_err(LocationError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(LocationError::from(e)),
+    };
+
+    result.map(Json).map_err(error_response("transfer stock"))
+}
+
+/// Move stock between locations, carrying its lots and their cost along.
+/// Nothing is posted: the stock stays on the same inventory account.
+async fn transfer_stock<C: ConnectionTrait>(
+    conn: &C,
+    payload: CreateStockTransfer,
+) -> Result<stock_transfers::Model, LocationError> {
+    if payload.quantity <= Decimal::ZERO {
+        return Err(LocationError::Invalid(
+            "Quantity must be positive".to_string(),
+        ));
+    }
+
+    let item = items::Entity::find_by_id(payload.item_code.clone())
+        .one(conn)
+        .await?
+        .ok_or_else(|| LocationError::ItemNotFound(payload.item_code.clone()))?;
+    let from = resolve_location(conn, Some(payload.from_location_id)).await?;
+    let to = resolve_location(conn, Some(payload.to_location_id)).await?;
+    if from.location_id == to.location_id {
+        return Err(LocationError::Invalid(
+            "Source and destination locations must differ".to_string(),
+        ));
+    }
+
+    let transfer_date = payload
+        .transfer_date
+        .unwrap_or_else(|| Utc::now().date_naive());
+    ensure_period_open(conn, transfer_date).await?;
+
+    let value = move_lots(
+        conn,
+        &item,
+        from.location_id,
+        to.location_id,
+        payload.quantity,
+        transfer_date,
+    )
+    .await?;
+
+    record_transfer(
+        conn,
+        TransferRequest {
+            item_code: &item.item_code,
+            from_location_id: from.location_id,
+            to_location_id: to.location_id,
+            quantity: payload.quantity,
+            value,
+            transfer_date,
+            allocation_id: None,
+            notes: payload.notes,
+            created_by: payload.created_by,
+        },
+    )
+    .await
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Days, NaiveDate, Utc};
+use entity::{items, stock_receipts, storage_locations};
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+
+use crate::handlers::purchases::internal_error;
+use crate::handlers::reports::{csv_response, csv_row};
+use crate::models::{ExpiringLot, ExpiringLotsQuery, ExpiringLotsResponse, ReportFormat};
+
+const DEFAULT_EXPIRY_WINDOW_DAYS: i64 = 30;
+
+/// Lots still holding stock that expire on or before `horizon`, soonest
+/// first. Lots already past expiry on `as_of` are included and flagged.
+pub async fn expiring_lots<C: ConnectionTrait>(
+    conn: &C,
+    as_of: NaiveDate,
+    horizon: NaiveDate,
+    item_code: Option<&str>,
+    location_id: Option<i32>,
+) -> Result<Vec<ExpiringLot>, DbErr> {
+    let mut query = stock_receipts::Entity::find()
+        .filter(stock_receipts::Column::RemainingQty.gt(Decimal::ZERO))
+        .filter(stock_receipts::Column::ExpiryDate.lte(horizon));
+    if let Some(item_code) = item_code {
+        query = query.filter(stock_receipts::Column::ItemCode.eq(item_code));
+    }
+    if let Some(location_id) = location_id {
+        query = query.filter(stock_receipts::Column::LocationId.eq(location_id));
+    }
+    let lots = query
+        .order_by_asc(stock_receipts::Column::ExpiryDate)
+        .order_by_asc(stock_receipts::Column::LotId)
+        .all(conn)
+        .await?;
+
+    let item_names: HashMap<String, String> = items::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|i| (i.item_code, i.item_name))
+        .collect();
+    let location_names: HashMap<i32, String> = storage_locations::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|l| (l.location_id, l.name))
+        .collect();
+
+    Ok(lots
+        .into_iter()
+        .filter_map(|lot| {
+            let expiry_date = lot.expiry_date?;
+            let days_to_expiry = (expiry_date - as_of).num_days();
+            Some(ExpiringLot {
+                lot_id: lot.lot_id,
+                item_name: item_names.get(&lot.item_code).cloned().unwrap_or_default(),
+                item_code: lot.item_code,
+                location_id: lot.location_id,
+                location_name: location_names
+                    .get(&lot.location_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                manufacturer_lot_no: lot.manufacturer_lot_no,
+                expiry_date,
+                days_to_expiry,
+                expired: expiry_date < as_of,
+                remaining_qty: lot.remaining_qty,
+                unit_cost: lot.unit_cost,
+                value: lot.remaining_qty * lot.unit_cost,
+            })
+        })
+        .collect())
+}
+
+pub async fn get_expiring_lots_handler(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<ExpiringLotsQuery>,
+) -> Result<Response, StatusCode> {
+    let days = query.days.unwrap_or(DEFAULT_EXPIRY_WINDOW_DAYS);
+    let as_of = Utc::now().date_naive();
+    let horizon = u64::try_from(days)
+        .ok()
+        .and_then(|days| as_of.checked_add_days(Days::new(days)))
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    let lots = expiring_lots(
+        &db,
+        as_of,
+        horizon,
+        query.item_code.as_deref(),
+        query.location_id,
+    )
+    .await
+    .map_err(internal_error("fetch expiring lots"))?;
+
+    if query.format == ReportFormat::Csv {
+        let mut rows = vec![csv_row([
+            "lot_id",
+            "item_code",
+            "item_name",
+            "location",
+            "manufacturer_lot_no",
+            "expiry_date",
+            "days_to_expiry",
+            "remaining_qty",
+            "value",
+        ])];
+        for lot in &lots {
+            rows.push(csv_row([
+                lot.lot_id.to_string(),
+                lot.item_code.clone(),
+                lot.item_name.clone(),
+                lot.location_name.clone(),
+                lot.manufacturer_lot_no.clone().unwrap_or_default(),
+                lot.expiry_date.to_string(),
+                lot.days_to_expiry.to_string(),
+                lot.remaining_qty.to_string(),
+                lot.value.to_string(),
+            ]));
+        }
+        return Ok(csv_response("expiring_lots.csv", rows));
+    }
+
+    Ok(Json(ExpiringLotsResponse {
+        as_of,
+        days,
+        total_value: lots.iter().map(|l| l.value).sum(),
+        lots,
+    })
+    .into_response())
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use entity::sea_orm_active_enums::RequirementStatus;
+use entity::{batch_requirements, inventory, items, low_stock_alerts, suppliers};
+use sea_orm::prelude::Decimal;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set,
+};
+use tracing::{error, info};
+
+use crate::handlers::purchases::internal_error;
+use crate::handlers::reports::{csv_response, csv_row};
+use crate::handlers::units::{display_factors, from_base_quantity};
+use crate::models::{
+    LowStockLine, LowStockQuery, LowStockResponse, PeriodQuery, ReportFormat, UpdateReorderLevels,
+};
+
+/// How often the alert job wakes up. Alerts are keyed by day, so waking
+/// more than once a day only catches items that ran low since the last run.
+const ALERT_JOB_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Items with a reorder point, measured against stock on hand less what
+/// pending batch requirements have already asked for. Only items at or below
+/// their reorder point are returned unless `include_all` is set.
+pub async fn low_stock_lines<C: ConnectionTrait>(
+    conn: &C,
+    include_all: bool,
+) -> Result<Vec<LowStockLine>, DbErr> {
+    let tracked = items::Entity::find()
+        .filter(items::Column::ReorderPoint.is_not_null())
+        .order_by_asc(items::Column::ItemCode)
+        .all(conn)
+        .await?;
+
+    let on_hand: HashMap<String, Decimal> = inventory::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|inv| (inv.item_code, inv.current_qty))
+        .collect();
+
+    let mut committed: HashMap<String, Decimal> = HashMap::new();
+    for requirement in batch_requirements::Entity::find()
+        .filter(batch_requirements::Column::Status.eq(RequirementStatus::Pending))
+        .all(conn)
+        .await?
+    {
+        *committed.entry(requirement.item_code).or_default() += requirement.quantity;
+    }
+
+    let supplier_names: HashMap<i32, String> = suppliers::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|s| (s.supplier_id, s.name))
+        .collect();
+
+    let mut lines = Vec::new();
+    for item in tracked {
+        let reorder_point = item.reorder_point.unwrap_or_default();
+        let on_hand_qty = on_hand.get(&item.item_code).copied().unwrap_or_default();
+        let committed_qty = committed.get(&item.item_code).copied().unwrap_or_default();
+        let available_qty = on_hand_qty - committed_qty;
+        let below_reorder_point = available_qty <= reorder_point;
+        if !below_reorder_point && !include_all {
+            continue;
+        }
+
+        let suggested_order_qty =
+            (reorder_point + item.safety_stock - available_qty).max(Decimal::ZERO);
+        lines.push(LowStockLine {
+            item_code: item.item_code,
+            item_name: item.item_name,
+            unit: item.unit,
+            on_hand_qty,
+            committed_qty,
+            available_qty,
+            reorder_point,
+            safety_stock: item.safety_stock,
+            below_reorder_point,
+            below_safety_stock: available_qty < item.safety_stock,
+            suggested_order_qty,
+            preferred_supplier_id: item.preferred_supplier_id,
+            preferred_supplier_name: item
+                .preferred_supplier_id
+                .and_then(|id| supplier_names.get(&id).cloned()),
+        });
+    }
+
+    Ok(lines)
+}
+
+pub async fn get_low_stock_handler(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<LowStockQuery>,
+) -> Result<Response, StatusCode> {
+    let mut lines = low_stock_lines(&db, query.include_all)
+        .await
+        .map_err(internal_error("compute low stock"))?;
+
+    if let Some(unit) = query.unit.as_deref() {
+        let factors = display_factors(&db, unit)
+            .await
+            .map_err(internal_error("fetch unit conversions"))?;
+        for line in &mut lines {
+            let Some(&factor) = factors.get(&line.item_code) else {
+                continue;
+            };
+            for qty in [
+                &mut line.on_hand_qty,
+                &mut line.committed_qty,
+                &mut line.available_qty,
+                &mut line.reorder_point,
+                &mut line.safety_stock,
+                &mut line.suggested_order_qty,
+            ] {
+                *qty = from_base_quantity(*qty, factor);
+            }
+            line.unit = Some(unit.to_string());
+        }
+    }
+
+    if query.format == ReportFormat::Csv {
+        let mut rows = vec![csv_row([
+            "item_code",
+            "item_name",
+            "unit",
+            "on_hand_qty",
+            "committed_qty",
+            "available_qty",
+            "reorder_point",
+            "safety_stock",
+            "suggested_order_qty",
+            "preferred_supplier",
+        ])];
+        for line in &lines {
+            rows.push(csv_row([
+                line.item_code.clone(),
+                line.item_name.clone(),
+                line.unit.clone().unwrap_or_default(),
+                line.on_hand_qty.to_string(),
+                line.committed_qty.to_string(),
+                line.available_qty.to_string(),
+                line.reorder_point.to_string(),
+                line.safety_stock.to_string(),
+                line.suggested_order_qty.to_string(),
+                line.preferred_supplier_name.clone().unwrap_or_default(),
+            ]));
+        }
+        return Ok(csv_response("low_stock.csv", rows));
+    }
+
+    Ok(Json(LowStockResponse {
+        as_of: Utc::now().date_naive(),
+        items: lines,
+    })
+    .into_response())
+}
+
+/// Record today's alerts for items at or below their reorder point. Items
+/// already alerted today are left alone; returns how many were added.
+pub async fn record_low_stock_alerts<C: ConnectionTrait>(
+    conn: &C,
+    alert_date: NaiveDate,
+) -> Result<u64, DbErr> {
+    let lines = low_stock_lines(conn, false).await?;
+    if lines.is_empty() {
+        return Ok(0);
+    }
+
+    let alerts = lines.into_iter().map(|line| low_stock_alerts::ActiveModel {
+        item_code: Set(line.item_code),
+        alert_date: Set(alert_date),
+        on_hand_qty: Set(line.on_hand_qty),
+        committed_qty: Set(line.committed_qty),
+        available_qty: Set(line.available_qty),
+        reorder_point: Set(line.reorder_point),
+        safety_stock: Set(line.safety_stock),
+        suggested_order_qty: Set(line.suggested_order_qty),
+        preferred_supplier_id: Set(line.preferred_supplier_id),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    });
+
+    low_stock_alerts::Entity::insert_many(alerts)
+        .on_conflict(
+            OnConflict::columns([
+                low_stock_alerts::Column::ItemCode,
+                low_stock_alerts::Column::AlertDate,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(conn)
+        .await
+}
+
+/// Background job recording low-stock alerts once per day.
+pub async fn run_low_stock_alert_job(db: DatabaseConnection) {
+    let mut interval = tokio::time::interval(ALERT_JOB_INTERVAL);
+    loop {
+        interval.tick().await;
+        let today = Utc::now().date_naive();
+        match record_low_stock_alerts(&db, today).await {
+            Ok(0) => {}
+            Ok(count) => info!("Recorded {} low-stock alerts for {}", count, today),
+            Err(e) => error!("Failed to record low-stock alerts: {}", e),
+        }
+    }
+}
+
+pub async fn get_low_stock_alerts_handler(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<PeriodQuery>,
+) -> Result<Response, StatusCode> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = query.from.unwrap_or(to);
+
+    let alerts = low_stock_alerts::Entity::find()
+        .filter(low_stock_alerts::Column::AlertDate.between(from, to))
+        .order_by_desc(low_stock_alerts::Column::AlertDate)
+        .order_by_asc(low_stock_alerts::Column::ItemCode)
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch low stock alerts"))?;
+
+    if query.format == ReportFormat::Csv {
+        let mut rows = vec![csv_row([
+            "alert_date",
+            "item_code",
+            "on_hand_qty",
+            "committed_qty",
+            "available_qty",
+            "reorder_point",
+            "suggested_order_qty",
+        ])];
+        for alert in &alerts {
+            rows.push(csv_row([
+                alert.alert_date.to_string(),
+                alert.item_code.clone(),
+                alert.on_hand_qty.to_string(),
+                alert.committed_qty.to_string(),
+                alert.available_qty.to_string(),
+                alert.reorder_point.to_string(),
+                alert.suggested_order_qty.to_string(),
+            ]));
+        }
+        return Ok(csv_response("low_stock_alerts.csv", rows));
+    }
+
+    Ok(Json(alerts).into_response())
+}
+
+pub async fn update_reorder_levels(
+    State(db): State<DatabaseConnection>,
+    Path(item_code): Path<String>,
+    Json(payload): Json<UpdateReorderLevels>,
+) -> Result<Json<items::Model>, StatusCode> {
+    if payload.reorder_point.is_some_and(|q| q < Decimal::ZERO)
+        || payload.safety_stock.is_some_and(|q| q < Decimal::ZERO)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let item = items::Entity::find_by_id(item_code)
+        .one(&db)
+        .await
+        .map_err(internal_error("fetch item"))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if let Some(supplier_id) = payload.preferred_supplier_id {
+        suppliers::Entity::find_by_id(supplier_id)
+            .one(&db)
+            .await
+            .map_err(internal_error("fetch supplier"))?
+            .ok_or(StatusCode::BAD_REQUEST)?;
+    }
+
+    let mut active: items::ActiveModel = item.into();
+    active.reorder_point = Set(payload.reorder_point);
+    active.safety_stock = Set(payload.safety_stock.unwrap_or_default());
+    active.preferred_supplier_id = Set(payload.preferred_supplier_id);
+    active
+        .update(&db)
+        .await
+        .map(Json)
+        .map_err(internal_error("update reorder levels"))
+}
@@ -1,8 +1,31 @@
+pub mod batch_closure;
+pub mod batch_lifecycle;
+pub mod batch_performance;
 pub mod batch_requirements;
 pub mod batch_sales;
 pub mod batches;
+pub mod costing;
+pub mod farmer_advances;
 pub mod fetch_all;
 pub mod fetch_by_id;
+pub mod flock_logs;
+pub mod health_programs;
 pub mod inserts;
+pub mod inventory_valuation;
+pub mod journal;
+pub mod locations;
+pub mod lot_expiry;
+pub mod low_stock;
+pub mod periods;
+pub mod posting_rules;
 pub mod purchases;
+pub mod reconcile;
+pub mod reports;
+pub mod reversals;
+pub mod settlements;
+pub mod stock_adjustments;
+pub mod stock_takes;
+pub mod supplier_payables;
+pub mod trader_receivables;
+pub mod units;
 pub mod visibility;
@@ -0,0 +1,479 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Datelike, Days, Months, NaiveDate, Utc};
+use entity::sea_orm_active_enums::LedgerAccountType;
+use entity::{accounting_periods, fiscal_years, ledger_accounts};
+use sea_orm::prelude::{DateTimeWithTimeZone, Decimal, Expr};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use thiserror::Error;
+
+use crate::handlers::journal::{load_journal, post_journal, JournalError};
+use crate::handlers::purchases::internal_error;
+use crate::handlers::reports::account_balances;
+use crate::models::{
+    CloseFiscalYear, CloseFiscalYearResponse, CreateFiscalYear, CreateJournalVoucher,
+    FiscalYearResponse, JournalLine, LockPeriod, ResponseMessage,
+};
+
+/// `reference_table` of the year-end closing entries.
+pub const CLOSING_REFERENCE_TABLE: &str = "fiscal_years";
+
+/// Equity account net profit is closed into.
+const RETAINED_EARNINGS_ACCOUNT: &str = "retained-earnings";
+
+#[derive(Debug, Error)]
+pub enum PeriodError {
+    #[error("Accounting period {period_start} to {period_end} is locked; cannot post on {date}")]
+    Locked {
+        date: NaiveDate,
+        period_start: NaiveDate,
+        period_end: NaiveDate,
+    },
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl PeriodError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            PeriodError::Locked { .. } => StatusCode::CONFLICT,
+            PeriodError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Refuse a posting dated inside a locked period. Dates not covered by any
+/// fiscal year are open.
+pub async fn ensure_period_open<C: ConnectionTrait>(
+    conn: &C,
+    date: NaiveDate,
+) -> Result<(), PeriodError> {
+    let locked = accounting_periods::Entity::find()
+        .filter(accounting_periods::Column::PeriodStart.lte(date))
+        .filter(accounting_periods::Column::PeriodEnd.gte(date))
+        .filter(accounting_periods::Column::IsLocked.eq(true))
+        .one(conn)
+        .await?;
+
+    match locked {
+        Some(period) => Err(PeriodError::Locked {
+            date,
+            period_start: period.period_start,
+            period_end: period.period_end,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Log a refused posting and turn it into the handler's status code.
+pub fn period_status(err: PeriodError) -> StatusCode {
+    eprintln!("Posting refused: {}", err);
+    err.status()
+}
+
+/// Calendar-month periods covering `start..=end`; the first and last may be
+/// partial months.
+fn monthly_periods(start: NaiveDate, end: NaiveDate) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut periods = Vec::new();
+    let mut period_start = start;
+
+    while period_start <= end {
+        // The last representable month has no next month to step back from
+        let period_end = period_start
+            .with_day(1)
+            .and_then(|d| d.checked_add_months(Months::new(1)))
+            .and_then(|d| d.pred_opt())
+            .map_or(end, |d| d.min(end));
+        periods.push((period_start, period_end));
+        match period_end.succ_opt() {
+            Some(next) => period_start = next,
+            None => break,
+        }
+    }
+
+    periods
+}
+
+async fn fiscal_year_response<C: ConnectionTrait>(
+    conn: &C,
+    fiscal_year: fiscal_years::Model,
+) -> Result<FiscalYearResponse, DbErr> {
+    let periods = accounting_periods::Entity::find()
+        .filter(accounting_periods::Column::FiscalYearId.eq(fiscal_year.fiscal_year_id))
+        .order_by_asc(accounting_periods::Column::PeriodStart)
+        .all(conn)
+        .await?;
+
+    Ok(FiscalYearResponse {
+        fiscal_year,
+        periods,
+    })
+}
+
+pub async fn get_fiscal_years_handler(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<Vec<FiscalYearResponse>>, StatusCode> {
+    let years = fiscal_years::Entity::find()
+        .order_by_asc(fiscal_years::Column::StartDate)
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch fiscal years"))?;
+
+    let mut resp = Vec::with_capacity(years.len());
+    for year in years {
+        resp.push(
+            fiscal_year_response(&db, year)
+                .await
+                .map_err(internal_error("fetch accounting periods"))?,
+        );
+    }
+
+    Ok(Json(resp))
+}
+
+pub async fn create_fiscal_year(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateFiscalYear>,
+) -> Result<Json<FiscalYearResponse>, StatusCode> {
+    let latest_end = payload
+        .start_date
+        .checked_add_days(Days::new(366))
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    if payload.start_date > payload.end_date || payload.end_date > latest_end {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(internal_error("begin transaction"))?;
+
+    // fiscal years must not overlap
+    let overlapping = fiscal_years::Entity::find()
+        .filter(fiscal_years::Column::StartDate.lte(payload.end_date))
+        .filter(fiscal_years::Column::EndDate.gte(payload.start_date))
+        .one(&txn)
+        .await
+        .map_err(internal_error("fetch fiscal years"))?;
+
+    if overlapping.is_some() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let year = fiscal_years::ActiveModel {
+        name: Set(payload.name),
+        start_date: Set(payload.start_date),
+        end_date: Set(payload.end_date),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(internal_error("insert fiscal year"))?;
+
+    for (period_start, period_end) in monthly_periods(year.start_date, year.end_date) {
+        accounting_periods::ActiveModel {
+            fiscal_year_id: Set(year.fiscal_year_id),
+            period_start: Set(period_start),
+            period_end: Set(period_end),
+            is_locked: Set(false),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .map_err(internal_error("insert accounting period"))?;
+    }
+
+    let resp = fiscal_year_response(&txn, year)
+        .await
+        .map_err(internal_error("fetch accounting periods"))?;
+
+    txn.commit()
+        .await
+        .map_err(internal_error("commit transaction"))?;
+
+    Ok(Json(resp))
+}
+
+pub async fn lock_period_handler(
+    State(db): State<DatabaseConnection>,
+    Path(period_id): Path<i32>,
+    Json(payload): Json<LockPeriod>,
+) -> Result<Json<accounting_periods::Model>, StatusCode> {
+    set_period_lock(&db, period_id, true, payload.user_id).await
+}
+
+pub async fn unlock_period_handler(
+    State(db): State<DatabaseConnection>,
+    Path(period_id): Path<i32>,
+    Json(payload): Json<LockPeriod>,
+) -> Result<Json<accounting_periods::Model>, StatusCode> {
+    set_period_lock(&db, period_id, false, payload.user_id).await
+}
+
+async fn set_period_lock(
+    db: &DatabaseConnection,
+    period_id: i32,
+    lock: bool,
+    user_id: Option<i32>,
+) -> Result<Json<accounting_periods::Model>, StatusCode> {
+    let (period, year) = accounting_periods::Entity::find_by_id(period_id)
+        .find_also_related(fiscal_years::Entity)
+        .one(db)
+        .await
+        .map_err(internal_error("fetch accounting period"))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // a closed year stays locked
+    if !lock && year.is_some_and(|y| y.closed_at.is_some()) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let mut active: accounting_periods::ActiveModel = period.into();
+    active.is_locked = Set(lock);
+    if lock {
+        active.locked_at = Set(Some(Utc::now().into()));
+        active.locked_by = Set(user_id);
+    } else {
+        active.locked_at = Set(None);
+        active.locked_by = Set(None);
+    }
+
+    active
+        .update(db)
+        .await
+        .map(Json)
+        .map_err(internal_error("update accounting period"))
+}
+
+pub async fn close_fiscal_year_handler(
+    State(db): State<DatabaseConnection>,
+    Path(fiscal_year_id): Path<i32>,
+    Json(payload): Json<CloseFiscalYear>,
+) -> Result<Json<CloseFiscalYearResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let txn = db.begin().await.map_err(close_error)?;
+
+    let year = fiscal_years::Entity::find_by_id(fiscal_year_id)
+        .one(&txn)
+        .await
+        .map_err(close_error)?
+        .ok_or_else(|| close_rejected(StatusCode::NOT_FOUND, "Fiscal year not found"))?;
+
+    if year.closed_at.is_some() {
+        return Err(close_rejected(
+            StatusCode::CONFLICT,
+            "Fiscal year is already closed",
+        ));
+    }
+
+    let retained_earnings = ledger_accounts::Entity::find()
+        .filter(ledger_accounts::Column::Name.eq(RETAINED_EARNINGS_ACCOUNT))
+        .filter(ledger_accounts::Column::AccountType.eq(LedgerAccountType::Equity))
+        .one(&txn)
+        .await
+        .map_err(close_error)?
+        .ok_or_else(|| {
+            close_rejected(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "No retained-earnings equity account configured",
+            )
+        })?;
+
+    // 1. Zero every revenue and expense account for the year
+    let balances = account_balances(&txn, Some(year.start_date), year.end_date, false)
+        .await
+        .map_err(close_error)?;
+
+    let mut lines = Vec::new();
+    let mut net_profit = Decimal::ZERO;
+
+    for (account, balance) in &balances {
+        if balance.is_zero() {
+            continue;
+        }
+        // revenue is credit-normal, so closing it debits the balance
+        let debit_to_close = match account.account_type {
+            LedgerAccountType::Revenue => {
+                net_profit += *balance;
+                *balance
+            }
+            LedgerAccountType::Expense => {
+                net_profit -= *balance;
+                -*balance
+            }
+            _ => continue,
+        };
+        lines.push(closing_line(account.account_id, debit_to_close, &year.name));
+    }
+
+    // 2. Book the difference into retained earnings
+    if !net_profit.is_zero() {
+        lines.push(closing_line(
+            retained_earnings.account_id,
+            -net_profit,
+            &year.name,
+        ));
+    }
+
+    let closing_txn_group_id = if lines.len() >= 2 {
+        let voucher = CreateJournalVoucher {
+            txn_date: year.end_date,
+            narration: Some(format!("Year-end close {}", year.name)),
+            reference_table: Some(CLOSING_REFERENCE_TABLE.to_string()),
+            reference_id: Some(year.fiscal_year_id),
+            created_by: payload.closed_by,
+            lines,
+        };
+        Some(post_journal(&txn, &voucher).await.map_err(|e| {
+            eprintln!("Failed to post closing entries: {}", e);
+            match e {
+                JournalError::Db(_) => close_error(e),
+                _ => close_rejected(e.status(), &e.to_string()),
+            }
+        })?)
+    } else {
+        None
+    };
+
+    // 3. Lock the whole year
+    accounting_periods::Entity::update_many()
+        .col_expr(accounting_periods::Column::IsLocked, Expr::value(true))
+        .col_expr(
+            accounting_periods::Column::LockedAt,
+            Expr::value(Some(DateTimeWithTimeZone::from(Utc::now()))),
+        )
+        .col_expr(
+            accounting_periods::Column::LockedBy,
+            Expr::value(payload.closed_by),
+        )
+        .filter(accounting_periods::Column::FiscalYearId.eq(year.fiscal_year_id))
+        .filter(accounting_periods::Column::IsLocked.eq(false))
+        .exec(&txn)
+        .await
+        .map_err(close_error)?;
+
+    let mut active: fiscal_years::ActiveModel = year.into();
+    active.closed_at = Set(Some(Utc::now().into()));
+    active.closed_by = Set(payload.closed_by);
+    active.closing_txn_group_id = Set(closing_txn_group_id);
+    let fiscal_year = active.update(&txn).await.map_err(close_error)?;
+
+    let closing_entries = match closing_txn_group_id {
+        Some(id) => load_journal(&txn, id).await.map_err(close_error)?,
+        None => None,
+    };
+
+    txn.commit().await.map_err(close_error)?;
+
+    Ok(Json(CloseFiscalYearResponse {
+        fiscal_year,
+        net_profit,
+        closing_entries,
+    }))
+}
+
+/// One side of a closing entry; a positive amount is a debit.
+fn closing_line(account_id: i32, amount: Decimal, year_name: &str) -> JournalLine {
+    let (debit, credit) = if amount > Decimal::ZERO {
+        (Some(amount), None)
+    } else {
+        (None, Some(-amount))
+    };
+    JournalLine {
+        account_id,
+        debit,
+        credit,
+        narration: Some(format!("Year-end close {}", year_name)),
+    }
+}
+
+fn close_rejected(status: StatusCode, message: &str) -> (StatusCode, Json<ResponseMessage>) {
+    (
+        status,
+        Json(ResponseMessage {
+            message: message.to_string(),
+        }),
+    )
+}
+
+fn close_error<E: std::fmt::Display>(err: E) -> (StatusCode, Json<ResponseMessage>) {
+    eprintln!("Fiscal year close failed: {}", err);
+    close_rejected(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to close fiscal year",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn calendar_year_has_twelve_months() {
+        let periods = monthly_periods(date(2025, 1, 1), date(2025, 12, 31));
+
+        assert_eq!(periods.len(), 12);
+        assert_eq!(periods[0], (date(2025, 1, 1), date(2025, 1, 31)));
+        assert_eq!(periods[1], (date(2025, 2, 1), date(2025, 2, 28)));
+        assert_eq!(periods[11], (date(2025, 12, 1), date(2025, 12, 31)));
+    }
+
+    #[test]
+    fn fiscal_year_with_partial_months() {
+        let periods = monthly_periods(date(2024, 1, 15), date(2024, 3, 10));
+
+        assert_eq!(
+            periods,
+            vec![
+                (date(2024, 1, 15), date(2024, 1, 31)),
+                (date(2024, 2, 1), date(2024, 2, 29)),
+                (date(2024, 3, 1), date(2024, 3, 10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn periods_are_contiguous_across_year_end() {
+        let periods = monthly_periods(date(2025, 4, 1), date(2026, 3, 31));
+
+        assert_eq!(periods.len(), 12);
+        assert_eq!(periods[8], (date(2025, 12, 1), date(2025, 12, 31)));
+        assert_eq!(periods[9], (date(2026, 1, 1), date(2026, 1, 31)));
+        for pair in periods.windows(2) {
+            assert_eq!(pair[0].1.succ_opt(), Some(pair[1].0));
+        }
+    }
+
+    #[test]
+    fn range_ending_on_the_last_representable_date() {
+        let last = NaiveDate::MAX;
+        let november = last.with_day(1).unwrap().with_month(11).unwrap();
+
+        let periods = monthly_periods(november, last);
+
+        assert_eq!(
+            periods,
+            vec![
+                (november, november.with_day(30).unwrap()),
+                (last.with_day(1).unwrap(), last),
+            ]
+        );
+    }
+
+    #[test]
+    fn single_day_and_empty_ranges() {
+        let day = date(2025, 6, 15);
+        assert_eq!(monthly_periods(day, day), vec![(day, day)]);
+        assert!(monthly_periods(date(2025, 6, 16), day).is_empty());
+    }
+}
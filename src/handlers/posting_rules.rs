@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use entity::ledger_accounts;
+use entity::posting_rules;
+use entity::sea_orm_active_enums::{ItemCategory, PostingEvent};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set,
+};
+use thiserror::Error;
+
+use crate::handlers::purchases::internal_error;
+use crate::models::{CreatePostingRule, PostingRuleResponse, UpdatePostingRule};
+
+#[derive(Debug, Error)]
+pub enum PostingRuleError {
+    #[error("No posting rule configured for {event:?} (category {item_category:?})")]
+    NotConfigured {
+        event: PostingEvent,
+        item_category: Option<ItemCategory>,
+    },
+    #[error("Failed to fetch posting rule: {0}")]
+    Db(#[from] DbErr),
+}
+
+/// Look up the debit/credit accounts for a business event.
+///
+/// A rule for the exact item category wins; otherwise the event's fallback
+/// rule (stored with no category) is used.
+pub async fn resolve_posting_rule<C: ConnectionTrait>(
+    conn: &C,
+    event: PostingEvent,
+    item_category: Option<ItemCategory>,
+) -> Result<posting_rules::Model, PostingRuleError> {
+    if let Some(category) = item_category.clone() {
+        if let Some(rule) = posting_rules::Entity::find()
+            .filter(posting_rules::Column::Event.eq(event.clone()))
+            .filter(posting_rules::Column::ItemCategory.eq(category))
+            .one(conn)
+            .await?
+        {
+            return Ok(rule);
+        }
+    }
+
+    posting_rules::Entity::find()
+        .filter(posting_rules::Column::Event.eq(event.clone()))
+        .filter(posting_rules::Column::ItemCategory.is_null())
+        .one(conn)
+        .await?
+        .ok_or(PostingRuleError::NotConfigured {
+            event,
+            item_category,
+        })
+}
+
+pub async fn get_posting_rules_handler(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<Vec<PostingRuleResponse>>, StatusCode> {
+    let rules = posting_rules::Entity::find()
+        .order_by_asc(posting_rules::Column::RuleId)
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch posting rules"))?;
+
+    let account_names: HashMap<i32, String> = ledger_accounts::Entity::find()
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch ledger accounts"))?
+        .into_iter()
+        .map(|a| (a.account_id, a.name))
+        .collect();
+
+    let resp = rules
+        .into_iter()
+        .map(|r| PostingRuleResponse {
+            rule_id: r.rule_id,
+            event: r.event,
+            item_category: r.item_category,
+            debit_account_id: r.debit_account_id,
+            debit_account_name: account_names.get(&r.debit_account_id).cloned(),
+            credit_account_id: r.credit_account_id,
+            credit_account_name: account_names.get(&r.credit_account_id).cloned(),
+            description: r.description,
+            updated_at: r.updated_at,
+        })
+        .collect();
+
+    Ok(Json(resp))
+}
+
+pub async fn create_posting_rule(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreatePostingRule>,
+) -> Result<Json<posting_rules::Model>, StatusCode> {
+    validate_accounts(&db, payload.debit_account_id, payload.credit_account_id).await?;
+
+    let existing = posting_rules::Entity::find()
+        .filter(posting_rules::Column::Event.eq(payload.event.clone()))
+        .filter(match payload.item_category.clone() {
+            Some(category) => posting_rules::Column::ItemCategory.eq(category),
+            None => posting_rules::Column::ItemCategory.is_null(),
+        })
+        .one(&db)
+        .await
+        .map_err(internal_error("fetch posting rule"))?;
+
+    if existing.is_some() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let new_rule = posting_rules::ActiveModel {
+        event: Set(payload.event),
+        item_category: Set(payload.item_category),
+        debit_account_id: Set(payload.debit_account_id),
+        credit_account_id: Set(payload.credit_account_id),
+        description: Set(payload.description),
+        updated_at: Set(Utc::now().into()),
+        ..Default::default()
+    };
+
+    new_rule
+        .insert(&db)
+        .await
+        .map(Json)
+        .map_err(internal_error("insert posting rule"))
+}
+
+pub async fn update_posting_rule(
+    State(db): State<DatabaseConnection>,
+    Path(rule_id): Path<i32>,
+    Json(payload): Json<UpdatePostingRule>,
+) -> Result<Json<posting_rules::Model>, StatusCode> {
+    validate_accounts(&db, payload.debit_account_id, payload.credit_account_id).await?;
+
+    let mut rule: posting_rules::ActiveModel = posting_rules::Entity::find_by_id(rule_id)
+        .one(&db)
+        .await
+        .map_err(internal_error("fetch posting rule"))?
+        .ok_or(StatusCode::NOT_FOUND)?
+        .into();
+
+    rule.debit_account_id = Set(payload.debit_account_id);
+    rule.credit_account_id = Set(payload.credit_account_id);
+    if payload.description.is_some() {
+        rule.description = Set(payload.description);
+    }
+    rule.updated_at = Set(Utc::now().into());
+
+    rule.update(&db)
+        .await
+        .map(Json)
+        .map_err(internal_error("update posting rule"))
+}
+
+async fn validate_accounts(
+    db: &DatabaseConnection,
+    debit_account_id: i32,
+    credit_account_id: i32,
+) -> Result<(), StatusCode> {
+    if debit_account_id == credit_account_id {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let found = ledger_accounts::Entity::find()
+        .filter(ledger_accounts::Column::AccountId.is_in([debit_account_id, credit_account_id]))
+        .all(db)
+        .await
+        .map_err(internal_error("fetch ledger accounts"))?;
+
+    if found.len() != 2 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(())
+}
@@ -1,9 +1,14 @@
+use crate::handlers::locations::{adjust_location_stock, location_status, resolve_location};
+use crate::handlers::periods::{ensure_period_open, period_status};
+use crate::handlers::posting_rules::resolve_posting_rule;
+use crate::handlers::units::{conversion_factor, to_base_quantity, unit_status};
 use crate::models::CreatePurchase;
 use axum::{extract::State, Json};
 use chrono::Utc;
-use entity::sea_orm_active_enums::MovementType;
+use entity::sea_orm_active_enums::{MovementType, PostingEvent};
 use entity::{
-    inventory, inventory_movements, ledger_accounts, ledger_entries, purchases, stock_receipts,
+    inventory, inventory_movements, items, ledger_accounts, ledger_entries, purchases,
+    stock_receipts, suppliers,
 };
 use reqwest::StatusCode;
 use sea_orm::prelude::Decimal;
@@ -15,27 +20,88 @@ use uuid::Uuid;
 
 pub async fn create_purchase(
     State(db): State<DatabaseConnection>,
-    Json(payload): Json<CreatePurchase>,
+    Json(mut payload): Json<CreatePurchase>,
 ) -> Result<Json<purchases::Model>, StatusCode> {
     let txn = db
         .begin()
         .await
         .map_err(internal_error("begin transaction"))?;
 
-    // 1. Insert purchase
-    let purchase = insert_purchase(&txn, &payload).await?;
+    // Stock already past its expiry cannot be received
+    if payload
+        .expiry_date
+        .is_some_and(|expiry| expiry < payload.purchase_date)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
 
-    // 2. Insert stock receipt
-    insert_stock_receipt(&txn, &payload, purchase.purchase_id).await?;
+    // Stock is kept in the item's base unit; a purchase in bags or boxes is
+    // converted, quantity and unit cost alike
+    if let Some(unit) = payload.unit.as_deref() {
+        let item = items::Entity::find_by_id(payload.item_code.clone())
+            .one(&txn)
+            .await
+            .map_err(internal_error("fetch item"))?
+            .ok_or(StatusCode::BAD_REQUEST)?;
+        let factor = conversion_factor(&txn, &item, unit)
+            .await
+            .map_err(unit_status)?;
+        let quantity = to_base_quantity(&txn, &item, Some(unit), payload.quantity)
+            .await
+            .map_err(unit_status)?;
+        payload.cost_per_unit = (payload.cost_per_unit / factor).round_dp(4);
+        payload.quantity = quantity;
+    }
 
-    // 3. Update or create inventory
-    upsert_inventory(&txn, &payload).await?;
+    ensure_period_open(&txn, payload.purchase_date)
+        .await
+        .map_err(period_status)?;
+    let location = resolve_location(&txn, payload.location_id)
+        .await
+        .map_err(location_status)?;
+
+    // 1. Work out the accounts; a purchase credited to payables is owed to the supplier
+    let (inventory_account_id, payment_account_id) =
+        resolve_purchase_accounts(&txn, &payload).await?;
+    let payables_account_id = resolve_posting_rule(&txn, PostingEvent::SupplierPayment, None)
+        .await
+        .map_err(internal_error("resolve supplier payment posting rule"))?
+        .debit_account_id;
+    let amount_due = if payment_account_id == payables_account_id {
+        payload.total_cost.unwrap_or_default()
+    } else {
+        Decimal::ZERO
+    };
 
-    // 4. Insert inventory movement
-    insert_inventory_movement(&txn, &payload, purchase.purchase_id).await?;
+    // 2. Insert purchase
+    let purchase = insert_purchase(&txn, &payload, amount_due).await?;
 
-    // 5. Insert ledger entries
-    insert_ledger_entries(&txn, &payload, &purchase).await?;
+    // 3. Insert stock receipt
+    insert_stock_receipt(&txn, &payload, purchase.purchase_id, location.location_id).await?;
+
+    // 4. Update or create inventory, in total and at the receiving location
+    upsert_inventory(&txn, &payload).await?;
+    adjust_location_stock(
+        &txn,
+        &payload.item_code,
+        location.location_id,
+        payload.quantity,
+    )
+    .await
+    .map_err(location_status)?;
+
+    // 5. Insert inventory movement
+    insert_inventory_movement(&txn, &payload, purchase.purchase_id, location.location_id).await?;
+
+    // 6. Insert ledger entries
+    insert_ledger_entries(
+        &txn,
+        &payload,
+        &purchase,
+        inventory_account_id,
+        payment_account_id,
+    )
+    .await?;
 
     txn.commit()
         .await
@@ -47,15 +113,37 @@ pub async fn create_purchase(
 async fn insert_purchase<C: TransactionTrait + sea_orm::ConnectionTrait>(
     txn: &C,
     payload: &CreatePurchase,
+    amount_due: Decimal,
 ) -> Result<purchases::Model, StatusCode> {
+    // A linked supplier's name also fills the legacy free-text column
+    let supplier_name = match payload.supplier_id {
+        Some(supplier_id) => Some(
+            suppliers::Entity::find_by_id(supplier_id)
+                .one(txn)
+                .await
+                .map_err(internal_error("fetch supplier"))?
+                .ok_or(StatusCode::BAD_REQUEST)?
+                .name,
+        ),
+        None => payload.supplier.clone(),
+    };
+
+    // Credit purchases must say who is owed
+    if amount_due > Decimal::ZERO && payload.supplier_id.is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
     let new_purchase = purchases::ActiveModel {
         item_code: Set(payload.item_code.clone()),
         cost_per_unit: Set(payload.cost_per_unit),
         total_cost: Set(payload.total_cost),
         quantity: Set(payload.quantity),
         purchase_date: Set(payload.purchase_date),
-        supplier: Set(payload.supplier.clone()),
+        supplier: Set(supplier_name),
         created_by: Set(payload.created_by),
+        supplier_id: Set(payload.supplier_id),
+        amount_due: Set(amount_due),
+        amount_paid: Set(Decimal::ZERO),
         ..Default::default()
     };
 
@@ -69,6 +157,7 @@ async fn insert_stock_receipt<C: TransactionTrait + sea_orm::ConnectionTrait>(
     txn: &C,
     payload: &CreatePurchase,
     purchase_id: i32,
+    location_id: i32,
 ) -> Result<(), StatusCode> {
     let new_receipt = stock_receipts::ActiveModel {
         purchase_id: Set(Some(purchase_id)),
@@ -78,6 +167,9 @@ async fn insert_stock_receipt<C: TransactionTrait + sea_orm::ConnectionTrait>(
         unit_cost: Set(payload.cost_per_unit),
         received_date: Set(payload.purchase_date),
         supplier: Set(payload.supplier.clone()),
+        location_id: Set(location_id),
+        manufacturer_lot_no: Set(payload.manufacturer_lot_no.clone()),
+        expiry_date: Set(payload.expiry_date),
         ..Default::default()
     };
 
@@ -123,12 +215,14 @@ async fn insert_inventory_movement<C: TransactionTrait + sea_orm::ConnectionTrai
     txn: &C,
     payload: &CreatePurchase,
     purchase_id: i32,
+    location_id: i32,
 ) -> Result<(), StatusCode> {
     let movement = inventory_movements::ActiveModel {
         item_code: Set(payload.item_code.clone()),
         movement_type: Set(MovementType::Purchase),
         qty_change: Set(payload.quantity),
         reference_id: Set(Some(purchase_id)),
+        location_id: Set(Some(location_id)),
         ..Default::default()
     };
 
@@ -139,17 +233,52 @@ async fn insert_inventory_movement<C: TransactionTrait + sea_orm::ConnectionTrai
     Ok(())
 }
 
+/// Inventory and payment accounts for a purchase. Explicit accounts on the
+/// payload win; `on_credit` books the payment side to supplier payables;
+/// anything still missing comes from the posting rules.
+async fn resolve_purchase_accounts<C: TransactionTrait + sea_orm::ConnectionTrait>(
+    txn: &C,
+    payload: &CreatePurchase,
+) -> Result<(i32, i32), StatusCode> {
+    let mut payment_account_id = payload.payment_account_id;
+    if payment_account_id.is_none() && payload.on_credit.unwrap_or(false) {
+        let rule = resolve_posting_rule(txn, PostingEvent::SupplierPayment, None)
+            .await
+            .map_err(internal_error("resolve supplier payment posting rule"))?;
+        payment_account_id = Some(rule.debit_account_id);
+    }
+
+    match (payload.inventory_account_id, payment_account_id) {
+        (Some(inventory_account_id), Some(payment_account_id)) => {
+            Ok((inventory_account_id, payment_account_id))
+        }
+        (inventory_account_id, payment_account_id) => {
+            let item = items::Entity::find_by_id(payload.item_code.clone())
+                .one(txn)
+                .await
+                .map_err(internal_error("fetch item"))?
+                .ok_or(StatusCode::BAD_REQUEST)?;
+            let rule = resolve_posting_rule(txn, PostingEvent::Purchase, Some(item.item_category))
+                .await
+                .map_err(internal_error("resolve purchase posting rule"))?;
+            Ok((
+                inventory_account_id.unwrap_or(rule.debit_account_id),
+                payment_account_id.unwrap_or(rule.credit_account_id),
+            ))
+        }
+    }
+}
+
 async fn insert_ledger_entries<C: TransactionTrait + sea_orm::ConnectionTrait>(
     txn: &C,
     payload: &CreatePurchase,
     purchase: &purchases::Model,
+    inventory_account_id: i32,
+    payment_account_id: i32,
 ) -> Result<(), StatusCode> {
     let total_cost = payload.total_cost;
     let txn_group_id = Uuid::new_v4();
 
-    let inventory_account_id = payload.inventory_account_id;
-    let payment_account_id = payload.payment_account_id;
-
     // Debit entry → Inventory (Asset)
     let debit_entry = ledger_entries::ActiveModel {
         account_id: Set(inventory_account_id),
@@ -0,0 +1,44 @@
+use axum::{extract::State, http::StatusCode, Json};
+use migration::reconcile::{reconcile_balances, BalanceDrift};
+use sea_orm::DatabaseConnection;
+
+use crate::handlers::purchases::internal_error;
+use crate::models::{BalanceDriftLine, ReconcileBalancesResponse};
+
+/// Report accounts whose stored balance drifted from their ledger entries.
+pub async fn get_balance_drift_handler(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ReconcileBalancesResponse>, StatusCode> {
+    let drift = reconcile_balances(&db, false)
+        .await
+        .map_err(internal_error("compute balance drift"))?;
+
+    Ok(Json(drift_response(drift, false)))
+}
+
+/// Recompute every drifted balance from ledger entries in one transaction.
+pub async fn reconcile_balances_handler(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<ReconcileBalancesResponse>, StatusCode> {
+    let drift = reconcile_balances(&db, true)
+        .await
+        .map_err(internal_error("reconcile ledger balances"))?;
+
+    Ok(Json(drift_response(drift, true)))
+}
+
+fn drift_response(drift: Vec<BalanceDrift>, fixed: bool) -> ReconcileBalancesResponse {
+    ReconcileBalancesResponse {
+        fixed,
+        drifted_accounts: drift
+            .into_iter()
+            .map(|d| BalanceDriftLine {
+                drift: d.drift(),
+                account_id: d.account_id,
+                name: d.name,
+                stored_balance: d.stored_balance,
+                computed_balance: d.computed_balance,
+            })
+            .collect(),
+    }
+}
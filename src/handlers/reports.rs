@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Datelike, NaiveDate, Utc};
+use entity::sea_orm_active_enums::LedgerAccountType;
+use entity::{ledger_accounts, ledger_entries};
+use sea_orm::prelude::{Decimal, Expr};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+
+use crate::handlers::periods::CLOSING_REFERENCE_TABLE;
+use crate::handlers::purchases::internal_error;
+use crate::models::{
+    AccountTotals, AgeingBuckets, AsOfQuery, BalanceSheetQuery, BalanceSheetResponse, PeriodQuery,
+    ProfitAndLossResponse, ReportFormat, StatementLine, TrialBalanceLine, TrialBalanceResponse,
+};
+
+/// Sum debits and credits per account for entries dated within `from..=to`.
+/// Accounts without entries in the range are absent from the map. Year-end
+/// closing entries are left out unless `include_closing` is set.
+pub async fn account_totals<C: ConnectionTrait>(
+    conn: &C,
+    from: Option<NaiveDate>,
+    to: NaiveDate,
+    include_closing: bool,
+) -> Result<HashMap<i32, AccountTotals>, DbErr> {
+    let mut query = ledger_entries::Entity::find()
+        .select_only()
+        .column(ledger_entries::Column::AccountId)
+        .column_as(
+            Expr::col(ledger_entries::Column::Debit).sum(),
+            "total_debit",
+        )
+        .column_as(
+            Expr::col(ledger_entries::Column::Credit).sum(),
+            "total_credit",
+        )
+        .filter(ledger_entries::Column::TxnDate.lte(to))
+        .group_by(ledger_entries::Column::AccountId);
+
+    if let Some(from) = from {
+        query = query.filter(ledger_entries::Column::TxnDate.gte(from));
+    }
+    if !include_closing {
+        query = query.filter(
+            Condition::any()
+                .add(ledger_entries::Column::ReferenceTable.is_null())
+                .add(ledger_entries::Column::ReferenceTable.ne(CLOSING_REFERENCE_TABLE)),
+        );
+    }
+
+    let rows = query.into_model::<AccountTotals>().all(conn).await?;
+
+    Ok(rows.into_iter().map(|r| (r.account_id, r)).collect())
+}
+
+/// Net balance on the account type's normal side:
+/// Asset/Expense are debit-normal, Liability/Equity/Revenue are credit-normal.
+pub fn normal_balance(
+    account_type: &LedgerAccountType,
+    debit: Decimal,
+    credit: Decimal,
+) -> Decimal {
+    match account_type {
+        LedgerAccountType::Asset | LedgerAccountType::Expense => debit - credit,
+        LedgerAccountType::Liability | LedgerAccountType::Equity | LedgerAccountType::Revenue => {
+            credit - debit
+        }
+    }
+}
+
+pub async fn get_trial_balance_handler(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<AsOfQuery>,
+) -> Result<Json<TrialBalanceResponse>, StatusCode> {
+    let as_of = query.as_of.unwrap_or_else(|| Utc::now().date_naive());
+
+    let accounts = ledger_accounts::Entity::find()
+        .order_by_asc(ledger_accounts::Column::AccountId)
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch ledger accounts"))?;
+
+    let totals = account_totals(&db, None, as_of, true)
+        .await
+        .map_err(internal_error("sum ledger entries"))?;
+
+    let mut total_debit = Decimal::ZERO;
+    let mut total_credit = Decimal::ZERO;
+
+    let lines: Vec<TrialBalanceLine> = accounts
+        .into_iter()
+        .map(|account| {
+            let (debit, credit) = totals
+                .get(&account.account_id)
+                .map(|t| {
+                    (
+                        t.total_debit.unwrap_or_default(),
+                        t.total_credit.unwrap_or_default(),
+                    )
+                })
+                .unwrap_or_default();
+
+            let net_debit = debit - credit;
+            let (debit_balance, credit_balance) = if net_debit >= Decimal::ZERO {
+                (net_debit, Decimal::ZERO)
+            } else {
+                (Decimal::ZERO, -net_debit)
+            };
+            total_debit += debit_balance;
+            total_credit += credit_balance;
+
+            TrialBalanceLine {
+                account_id: account.account_id,
+                balance: normal_balance(&account.account_type, debit, credit),
+                name: account.name,
+                account_type: account.account_type,
+                total_debit: debit,
+                total_credit: credit,
+                debit_balance,
+                credit_balance,
+            }
+        })
+        .collect();
+
+    Ok(Json(TrialBalanceResponse {
+        as_of,
+        lines,
+        total_debit,
+        total_credit,
+        is_balanced: total_debit == total_credit,
+    }))
+}
+
+/// Every ledger account with its normal-side balance over `from..=to`.
+pub async fn account_balances<C: ConnectionTrait>(
+    conn: &C,
+    from: Option<NaiveDate>,
+    to: NaiveDate,
+    include_closing: bool,
+) -> Result<Vec<(ledger_accounts::Model, Decimal)>, DbErr> {
+    let accounts = ledger_accounts::Entity::find()
+        .order_by_asc(ledger_accounts::Column::AccountId)
+        .all(conn)
+        .await?;
+    let totals = account_totals(conn, from, to, include_closing).await?;
+
+    Ok(accounts
+        .into_iter()
+        .map(|account| {
+            let balance = totals
+                .get(&account.account_id)
+                .map(|t| {
+                    normal_balance(
+                        &account.account_type,
+                        t.total_debit.unwrap_or_default(),
+                        t.total_credit.unwrap_or_default(),
+                    )
+                })
+                .unwrap_or_default();
+            (account, balance)
+        })
+        .collect())
+}
+
+fn statement_lines(
+    balances: &[(ledger_accounts::Model, Decimal)],
+    account_type: LedgerAccountType,
+) -> (Vec<StatementLine>, Decimal) {
+    let lines: Vec<StatementLine> = balances
+        .iter()
+        .filter(|(account, _)| account.account_type == account_type)
+        .map(|(account, amount)| StatementLine {
+            account_id: account.account_id,
+            name: account.name.clone(),
+            amount: *amount,
+        })
+        .collect();
+    let total = lines.iter().map(|l| l.amount).sum();
+    (lines, total)
+}
+
+pub async fn get_profit_and_loss_handler(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<PeriodQuery>,
+) -> Result<Response, StatusCode> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    // default to the month `to` falls in
+    let from = query.from.unwrap_or_else(|| to.with_day(1).unwrap_or(to));
+    if from > to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let balances = account_balances(&db, Some(from), to, false)
+        .await
+        .map_err(internal_error("compute account balances"))?;
+
+    let (revenue, total_revenue) = statement_lines(&balances, LedgerAccountType::Revenue);
+    let (expenses, total_expenses) = statement_lines(&balances, LedgerAccountType::Expense);
+
+    let report = ProfitAndLossResponse {
+        from,
+        to,
+        revenue,
+        expenses,
+        total_revenue,
+        total_expenses,
+        net_profit: total_revenue - total_expenses,
+    };
+
+    if query.format == ReportFormat::Csv {
+        let mut rows = vec![csv_row(["section", "account_id", "account", "amount"])];
+        statement_csv_rows(&mut rows, "revenue", &report.revenue);
+        rows.push(total_csv_row("Total revenue", report.total_revenue));
+        statement_csv_rows(&mut rows, "expense", &report.expenses);
+        rows.push(total_csv_row("Total expenses", report.total_expenses));
+        rows.push(total_csv_row("Net profit", report.net_profit));
+        return Ok(csv_response(
+            &format!("profit_and_loss_{}_{}.csv", from, to),
+            rows,
+        ));
+    }
+
+    Ok(Json(report).into_response())
+}
+
+pub async fn get_balance_sheet_handler(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<BalanceSheetQuery>,
+) -> Result<Response, StatusCode> {
+    let as_of = query.as_of.unwrap_or_else(|| Utc::now().date_naive());
+
+    let balances = account_balances(&db, None, as_of, true)
+        .await
+        .map_err(internal_error("compute account balances"))?;
+
+    let (assets, total_assets) = statement_lines(&balances, LedgerAccountType::Asset);
+    let (liabilities, total_liabilities) = statement_lines(&balances, LedgerAccountType::Liability);
+    let (equity, equity_accounts) = statement_lines(&balances, LedgerAccountType::Equity);
+    let (_, total_revenue) = statement_lines(&balances, LedgerAccountType::Revenue);
+    let (_, total_expenses) = statement_lines(&balances, LedgerAccountType::Expense);
+
+    let retained_earnings = total_revenue - total_expenses;
+    let total_equity = equity_accounts + retained_earnings;
+    let total_liabilities_and_equity = total_liabilities + total_equity;
+
+    let report = BalanceSheetResponse {
+        as_of,
+        assets,
+        liabilities,
+        equity,
+        total_assets,
+        total_liabilities,
+        total_equity,
+        retained_earnings,
+        total_liabilities_and_equity,
+        is_balanced: total_assets == total_liabilities_and_equity,
+    };
+
+    if query.format == ReportFormat::Csv {
+        let mut rows = vec![csv_row(["section", "account_id", "account", "amount"])];
+        statement_csv_rows(&mut rows, "asset", &report.assets);
+        rows.push(total_csv_row("Total assets", report.total_assets));
+        statement_csv_rows(&mut rows, "liability", &report.liabilities);
+        rows.push(total_csv_row("Total liabilities", report.total_liabilities));
+        statement_csv_rows(&mut rows, "equity", &report.equity);
+        rows.push(total_csv_row("Retained earnings", report.retained_earnings));
+        rows.push(total_csv_row("Total equity", report.total_equity));
+        rows.push(total_csv_row(
+            "Total liabilities and equity",
+            report.total_liabilities_and_equity,
+        ));
+        return Ok(csv_response(&format!("balance_sheet_{}.csv", as_of), rows));
+    }
+
+    Ok(Json(report).into_response())
+}
+
+fn statement_csv_rows(rows: &mut Vec<String>, section: &str, lines: &[StatementLine]) {
+    for line in lines {
+        rows.push(csv_row([
+            section.to_string(),
+            line.account_id.to_string(),
+            line.name.clone(),
+            line.amount.to_string(),
+        ]));
+    }
+}
+
+fn total_csv_row(label: &str, amount: Decimal) -> String {
+    csv_row(["total", "", label, &amount.to_string()])
+}
+
+/// Join fields into one CSV line, quoting fields that need it.
+pub fn csv_row<I, S>(fields: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    fields
+        .into_iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn csv_response(filename: &str, rows: Vec<String>) -> Response {
+    let mut body = rows.join("\r\n");
+    body.push_str("\r\n");
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Add `amount` to the 0-30 / 31-60 / 61-90 / 90+ day bucket for its age.
+pub fn add_to_ageing(buckets: &mut AgeingBuckets, age_days: i64, amount: Decimal) {
+    match age_days {
+        ..=30 => buckets.days_0_30 += amount,
+        31..=60 => buckets.days_31_60 += amount,
+        61..=90 => buckets.days_61_90 += amount,
+        _ => buckets.days_over_90 += amount,
+    }
+}
@@ -0,0 +1,727 @@
+use std::collections::HashSet;
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::Utc;
+use entity::sea_orm_active_enums::{ItemCategory, MovementType, RequirementStatus};
+use entity::{
+    batch_allocation_lines, batch_allocations, batch_closure_summary, batch_placements,
+    batch_requirements, batch_sales, batches, bird_count_history, farmer_advances,
+    farmer_commission_history, inventory, inventory_movements, items, ledger_entries, purchases,
+    stock_receipts, supplier_payment_allocations, supplier_payments, trader_receipt_allocations,
+    trader_receipts,
+};
+use num_traits::ToPrimitive;
+use sea_orm::prelude::{Decimal, Expr};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::handlers::batch_closure::add_commission_to_summary;
+use crate::handlers::batch_lifecycle::{ensure_batch_allows, BatchOperation, BatchStateError};
+use crate::handlers::farmer_advances::outstanding_advances;
+use crate::handlers::journal::{load_journal, post_journal, JournalError};
+use crate::handlers::locations::{adjust_location_stock, LocationError};
+use crate::handlers::periods::{ensure_period_open, PeriodError, CLOSING_REFERENCE_TABLE};
+use crate::models::{
+    CreateJournalVoucher, JournalLine, ResponseMessage, ReversalResponse, ReverseTransaction,
+};
+
+#[derive(Debug, Error)]
+pub enum ReversalError {
+    #[error("Provide either txn_group_id or both reference_table and reference_id")]
+    MissingKey,
+    #[error("No posted transaction found to reverse")]
+    NotFound,
+    #[error("Transaction {0} has already been reversed")]
+    AlreadyReversed(Uuid),
+    #[error("Transaction {0} is itself a reversal")]
+    IsReversal(Uuid),
+    #[error("{0}")]
+    Conflict(String),
+    #[error(transparent)]
+    Period(#[from] PeriodError),
+    #[error(transparent)]
+    Batch(#[from] BatchStateError),
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+    #[error(transparent)]
+    Location(#[from] LocationError),
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl ReversalError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ReversalError::MissingKey => StatusCode::BAD_REQUEST,
+            ReversalError::NotFound => StatusCode::NOT_FOUND,
+            ReversalError::AlreadyReversed(_)
+            | ReversalError::IsReversal(_)
+            | ReversalError::Conflict(_) => StatusCode::CONFLICT,
+            ReversalError::Period(e) => e.status(),
+            ReversalError::Batch(e) => e.status(),
+            ReversalError::Journal(e) => e.status(),
+            ReversalError::Location(e) => e.status(),
+            ReversalError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+pub async fn reverse_transaction_handler(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<ReverseTransaction>,
+) -> Result<Json<ReversalResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match reverse_transaction(&txn, &payload).await {
+            Ok(resp) => txn
+                .commit()
+                .await
+                .map(|_| resp)
+                .map_err(ReversalError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(ReversalError::from(e)),
+    };
+
+    result.map(Json).map_err(|e| {
+        eprintln!("Failed to reverse transaction: {}", e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    })
+}
+
+/// Post mirror-image entries for every unreversed transaction of the source
+/// record, then undo the record's side effects and mark it voided.
+pub async fn reverse_transaction<C: ConnectionTrait>(
+    conn: &C,
+    payload: &ReverseTransaction,
+) -> Result<ReversalResponse, ReversalError> {
+    // 1. Work out which source record is being reversed
+    let (reference_table, reference_id) = match payload.txn_group_id {
+        Some(group_id) => {
+            let entries = ledger_entries::Entity::find()
+                .filter(ledger_entries::Column::TxnGroupId.eq(group_id))
+                .all(conn)
+                .await?;
+            let first = entries.first().ok_or(ReversalError::NotFound)?;
+            if first.reverses_txn_group_id.is_some() {
+                return Err(ReversalError::IsReversal(group_id));
+            }
+            (first.reference_table.clone(), first.reference_id)
+        }
+        None => match (&payload.reference_table, payload.reference_id) {
+            (Some(table), Some(id)) => (Some(table.clone()), Some(id)),
+            _ => return Err(ReversalError::MissingKey),
+        },
+    };
+
+    // 2. Collect the transactions to mirror: the whole source record when it is
+    //    known, otherwise just the requested group
+    let candidate_groups: Vec<Uuid> = match (&reference_table, reference_id) {
+        (Some(table), Some(id)) => {
+            let mut seen = HashSet::new();
+            ledger_entries::Entity::find()
+                .filter(ledger_entries::Column::ReferenceTable.eq(table.clone()))
+                .filter(ledger_entries::Column::ReferenceId.eq(id))
+                .filter(ledger_entries::Column::ReversesTxnGroupId.is_null())
+                .order_by_asc(ledger_entries::Column::EntryId)
+                .all(conn)
+                .await?
+                .into_iter()
+                .filter(|e| seen.insert(e.txn_group_id))
+                .map(|e| e.txn_group_id)
+                .collect()
+        }
+        _ => payload.txn_group_id.into_iter().collect(),
+    };
+
+    if candidate_groups.is_empty() {
+        return Err(ReversalError::NotFound);
+    }
+
+    let already_reversed: HashSet<Uuid> = ledger_entries::Entity::find()
+        .filter(ledger_entries::Column::ReversesTxnGroupId.is_in(candidate_groups.clone()))
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter_map(|e| e.reverses_txn_group_id)
+        .collect();
+
+    let groups: Vec<Uuid> = candidate_groups
+        .iter()
+        .copied()
+        .filter(|g| !already_reversed.contains(g))
+        .collect();
+
+    if groups.is_empty() {
+        return Err(ReversalError::AlreadyReversed(candidate_groups[0]));
+    }
+
+    // 3. Mirror each transaction
+    let txn_date = payload.txn_date.unwrap_or_else(|| Utc::now().date_naive());
+    ensure_period_open(conn, txn_date).await?;
+    let mut reversals = Vec::new();
+
+    for group_id in groups {
+        let entries = ledger_entries::Entity::find()
+            .filter(ledger_entries::Column::TxnGroupId.eq(group_id))
+            .order_by_asc(ledger_entries::Column::EntryId)
+            .all(conn)
+            .await?;
+
+        let lines: Vec<JournalLine> = entries
+            .iter()
+            .filter(|e| {
+                e.debit.unwrap_or_default() > Decimal::ZERO
+                    || e.credit.unwrap_or_default() > Decimal::ZERO
+            })
+            .map(|e| JournalLine {
+                account_id: e.account_id,
+                debit: e.credit.filter(|c| *c > Decimal::ZERO),
+                credit: e.debit.filter(|d| *d > Decimal::ZERO),
+                narration: Some(format!(
+                    "Reversal: {}",
+                    e.narration.clone().unwrap_or_default()
+                )),
+            })
+            .collect();
+
+        // zero-value transactions have nothing to mirror
+        if lines.len() < 2 {
+            continue;
+        }
+
+        let voucher = CreateJournalVoucher {
+            txn_date,
+            narration: Some(format!("Reversal of {}: {}", group_id, payload.reason)),
+            reference_table: reference_table.clone(),
+            reference_id,
+            created_by: payload.created_by,
+            lines,
+        };
+        let reversal_group_id = post_journal(conn, &voucher).await?;
+
+        ledger_entries::Entity::update_many()
+            .col_expr(
+                ledger_entries::Column::ReversesTxnGroupId,
+                Expr::value(group_id),
+            )
+            .filter(ledger_entries::Column::TxnGroupId.eq(reversal_group_id))
+            .exec(conn)
+            .await?;
+
+        if let Some(journal) = load_journal(conn, reversal_group_id).await? {
+            reversals.push(journal);
+        }
+    }
+
+    // 4. Undo side effects and void the source record
+    if let (Some(table), Some(id)) = (&reference_table, reference_id) {
+        match table.as_str() {
+            "purchases" => void_purchase(conn, id, &payload.reason).await?,
+            "batch_sales" => void_batch_sale(conn, id, &payload.reason).await?,
+            "farmer_commission_history" => void_commission(conn, id, &payload.reason).await?,
+            "farmer_advances" => void_farmer_advance(conn, id, &payload.reason).await?,
+            "farmer_settlements" => {
+                return Err(ReversalError::Conflict(format!(
+                    "Settlement {} is final; its advance recovery cannot be reversed on its own",
+                    id
+                )))
+            }
+            "stock_adjustments" => {
+                return Err(ReversalError::Conflict(format!(
+                    "Stock adjustment {} cannot be voided; record a counter adjustment instead",
+                    id
+                )))
+            }
+            "supplier_payments" => void_supplier_payment(conn, id, &payload.reason).await?,
+            "trader_receipts" => void_trader_receipt(conn, id, &payload.reason).await?,
+            "allocations" => void_allocation(conn, id, &payload.reason).await?,
+            "batches" => void_batch_stocking(conn, id, &payload.reason).await?,
+            CLOSING_REFERENCE_TABLE => {
+                return Err(ReversalError::Conflict(format!(
+                "Closing entry of fiscal year {} cannot be reversed while the year stays closed",
+                id
+            )))
+            }
+            // An opening balance is only its journal; reversing it takes the
+            // account back to zero and leaves the account itself in place
+            "ledger_accounts" => {}
+            // manual journals have no source record
+            _ => {}
+        }
+    }
+
+    Ok(ReversalResponse {
+        reference_table,
+        reference_id,
+        reversals,
+    })
+}
+
+async fn void_purchase<C: ConnectionTrait>(
+    conn: &C,
+    purchase_id: i32,
+    reason: &str,
+) -> Result<(), ReversalError> {
+    let purchase = purchases::Entity::find_by_id(purchase_id)
+        .one(conn)
+        .await?
+        .ok_or(ReversalError::NotFound)?;
+
+    if purchase.voided_at.is_some() {
+        return Err(ReversalError::Conflict(format!(
+            "Purchase {} is already voided",
+            purchase_id
+        )));
+    }
+
+    if purchase.amount_paid > Decimal::ZERO {
+        return Err(ReversalError::Conflict(format!(
+            "Purchase {} has supplier payments against it; reverse those first",
+            purchase_id
+        )));
+    }
+
+    // Stock that has been allocated can't be taken back from the godown
+    let receipts = stock_receipts::Entity::find()
+        .filter(stock_receipts::Column::PurchaseId.eq(purchase_id))
+        .all(conn)
+        .await?;
+
+    for receipt in receipts {
+        if receipt.remaining_qty != receipt.received_qty {
+            return Err(ReversalError::Conflict(format!(
+                "Lot {} from purchase {} has already been allocated",
+                receipt.lot_id, purchase_id
+            )));
+        }
+        let (location_id, qty) = (receipt.location_id, receipt.remaining_qty);
+        let mut active: stock_receipts::ActiveModel = receipt.into();
+        active.remaining_qty = Set(Decimal::ZERO);
+        active.update(conn).await?;
+
+        adjust_location_stock(conn, &purchase.item_code, location_id, -qty).await?;
+        record_adjustment(conn, &purchase.item_code, -qty, purchase_id, location_id).await?;
+    }
+
+    adjust_inventory(conn, &purchase.item_code, -purchase.quantity).await?;
+
+    let mut active: purchases::ActiveModel = purchase.into();
+    active.voided_at = Set(Some(Utc::now().into()));
+    active.void_reason = Set(Some(reason.to_string()));
+    active.update(conn).await?;
+
+    Ok(())
+}
+
+async fn void_batch_sale<C: ConnectionTrait>(
+    conn: &C,
+    sale_id: i32,
+    reason: &str,
+) -> Result<(), ReversalError> {
+    let sale = batch_sales::Entity::find_by_id(sale_id)
+        .one(conn)
+        .await?
+        .ok_or(ReversalError::NotFound)?;
+
+    if sale.voided_at.is_some() {
+        return Err(ReversalError::Conflict(format!(
+            "Batch sale {} is already voided",
+            sale_id
+        )));
+    }
+
+    if sale.amount_received > Decimal::ZERO {
+        return Err(ReversalError::Conflict(format!(
+            "Batch sale {} has trader receipts against it; reverse those first",
+            sale_id
+        )));
+    }
+
+    ensure_batch_allows(conn, sale.batch_id, BatchOperation::RecordSale).await?;
+
+    if let Some(summary) = batch_closure_summary::Entity::find()
+        .filter(batch_closure_summary::Column::BatchId.eq(sale.batch_id))
+        .one(conn)
+        .await?
+    {
+        let quantity = sale.quantity.to_i32().unwrap_or_default();
+        let mut active: batch_closure_summary::ActiveModel = summary.clone().into();
+        active.revenue = Set(summary.revenue - sale.value);
+        active.gross_profit = Set(summary.gross_profit - sale.value);
+        active.available_chicken_count = Set(summary.available_chicken_count + quantity);
+        active.update(conn).await?;
+    }
+
+    let mut active: batch_sales::ActiveModel = sale.into();
+    active.voided_at = Set(Some(Utc::now().into()));
+    active.void_reason = Set(Some(reason.to_string()));
+    active.update(conn).await?;
+
+    Ok(())
+}
+
+async fn void_commission<C: ConnectionTrait>(
+    conn: &C,
+    commission_id: i32,
+    reason: &str,
+) -> Result<(), ReversalError> {
+    let commission = farmer_commission_history::Entity::find_by_id(commission_id)
+        .one(conn)
+        .await?
+        .ok_or(ReversalError::NotFound)?;
+
+    if commission.voided_at.is_some() {
+        return Err(ReversalError::Conflict(format!(
+            "Farmer commission {} is already voided",
+            commission_id
+        )));
+    }
+
+    if let Some(batch_id) = commission.batch_id {
+        ensure_batch_allows(conn, batch_id, BatchOperation::RecordCommission).await?;
+        add_commission_to_summary(conn, batch_id, -commission.commission_amount).await?;
+    }
+
+    let mut active: farmer_commission_history::ActiveModel = commission.into();
+    active.voided_at = Set(Some(Utc::now().into()));
+    active.void_reason = Set(Some(reason.to_string()));
+    active.update(conn).await?;
+
+    Ok(())
+}
+
+/// An advance already recovered through a settlement has been repaid, so it
+/// can no longer be voided.
+async fn void_farmer_advance<C: ConnectionTrait>(
+    conn: &C,
+    advance_id: i32,
+    reason: &str,
+) -> Result<(), ReversalError> {
+    let advance = farmer_advances::Entity::find_by_id(advance_id)
+        .one(conn)
+        .await?
+        .ok_or(ReversalError::NotFound)?;
+
+    if advance.voided_at.is_some() {
+        return Err(ReversalError::Conflict(format!(
+            "Farmer advance {} is already voided",
+            advance_id
+        )));
+    }
+
+    if let Some(batch_id) = advance.batch_id {
+        ensure_batch_allows(conn, batch_id, BatchOperation::RecordAdvance).await?;
+    }
+
+    let outstanding = outstanding_advances(conn, advance.farmer_id).await?;
+    if outstanding < advance.amount {
+        return Err(ReversalError::Conflict(format!(
+            "Farmer advance {} has already been recovered through a settlement",
+            advance_id
+        )));
+    }
+
+    let mut active: farmer_advances::ActiveModel = advance.into();
+    active.voided_at = Set(Some(Utc::now().into()));
+    active.void_reason = Set(Some(reason.to_string()));
+    active.update(conn).await?;
+
+    Ok(())
+}
+
+/// Take the payment's settlements back off the purchases they paid.
+async fn void_supplier_payment<C: ConnectionTrait>(
+    conn: &C,
+    payment_id: i32,
+    reason: &str,
+) -> Result<(), ReversalError> {
+    let payment = supplier_payments::Entity::find_by_id(payment_id)
+        .one(conn)
+        .await?
+        .ok_or(ReversalError::NotFound)?;
+
+    if payment.voided_at.is_some() {
+        return Err(ReversalError::Conflict(format!(
+            "Supplier payment {} is already voided",
+            payment_id
+        )));
+    }
+
+    let settlements = supplier_payment_allocations::Entity::find()
+        .filter(supplier_payment_allocations::Column::PaymentId.eq(payment_id))
+        .all(conn)
+        .await?;
+
+    for settlement in settlements {
+        if let Some(purchase) = purchases::Entity::find_by_id(settlement.purchase_id)
+            .one(conn)
+            .await?
+        {
+            let paid = purchase.amount_paid - settlement.amount;
+            let mut active: purchases::ActiveModel = purchase.into();
+            active.amount_paid = Set(paid);
+            active.update(conn).await?;
+        }
+    }
+
+    let mut active: supplier_payments::ActiveModel = payment.into();
+    active.voided_at = Set(Some(Utc::now().into()));
+    active.void_reason = Set(Some(reason.to_string()));
+    active.update(conn).await?;
+
+    Ok(())
+}
+
+/// Take the receipt's settlements back off the sales they paid.
+async fn void_trader_receipt<C: ConnectionTrait>(
+    conn: &C,
+    receipt_id: i32,
+    reason: &str,
+) -> Result<(), ReversalError> {
+    let receipt = trader_receipts::Entity::find_by_id(receipt_id)
+        .one(conn)
+        .await?
+        .ok_or(ReversalError::NotFound)?;
+
+    if receipt.voided_at.is_some() {
+        return Err(ReversalError::Conflict(format!(
+            "Trader receipt {} is already voided",
+            receipt_id
+        )));
+    }
+
+    let settlements = trader_receipt_allocations::Entity::find()
+        .filter(trader_receipt_allocations::Column::ReceiptId.eq(receipt_id))
+        .all(conn)
+        .await?;
+
+    for settlement in settlements {
+        if let Some(sale) = batch_sales::Entity::find_by_id(settlement.sale_id)
+            .one(conn)
+            .await?
+        {
+            let received = sale.amount_received - settlement.amount;
+            let mut active: batch_sales::ActiveModel = sale.into();
+            active.amount_received = Set(received);
+            active.update(conn).await?;
+        }
+    }
+
+    let mut active: trader_receipts::ActiveModel = receipt.into();
+    active.voided_at = Set(Some(Utc::now().into()));
+    active.void_reason = Set(Some(reason.to_string()));
+    active.update(conn).await?;
+
+    Ok(())
+}
+
+/// Chicks placed when the batch was created: those sit on the stocking
+/// requirements `create_batch` inserts together with the batch, one per item.
+async fn void_batch_stocking<C: ConnectionTrait>(
+    conn: &C,
+    batch_id: i32,
+    reason: &str,
+) -> Result<(), ReversalError> {
+    ensure_batch_allows(conn, batch_id, BatchOperation::AllocateStock).await?;
+
+    let requirement_ids: Vec<i32> = batch_requirements::Entity::find()
+        .filter(batch_requirements::Column::BatchId.eq(batch_id))
+        .filter(batch_requirements::Column::IsStocking.eq(true))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|r| r.requirement_id)
+        .collect();
+    if requirement_ids.is_empty() {
+        return Err(ReversalError::NotFound);
+    }
+
+    let allocations = batch_allocations::Entity::find()
+        .filter(batch_allocations::Column::RequirementId.is_in(requirement_ids))
+        .filter(batch_allocations::Column::VoidedAt.is_null())
+        .all(conn)
+        .await?;
+
+    for allocation in allocations {
+        void_allocation(conn, allocation.allocation_id, reason).await?;
+    }
+
+    Ok(())
+}
+
+/// Put allocated stock back on its lots and, for chicks, take the birds back
+/// off the batch.
+async fn void_allocation<C: ConnectionTrait>(
+    conn: &C,
+    allocation_id: i32,
+    reason: &str,
+) -> Result<(), ReversalError> {
+    let allocation = batch_allocations::Entity::find_by_id(allocation_id)
+        .one(conn)
+        .await?
+        .ok_or(ReversalError::NotFound)?;
+
+    if allocation.voided_at.is_some() {
+        return Err(ReversalError::Conflict(format!(
+            "Allocation {} is already voided",
+            allocation_id
+        )));
+    }
+
+    let requirement_id = allocation.requirement_id.ok_or_else(|| {
+        ReversalError::Conflict(format!(
+            "Allocation {} has no requirement to restore stock for",
+            allocation_id
+        ))
+    })?;
+    let requirement = batch_requirements::Entity::find_by_id(requirement_id)
+        .one(conn)
+        .await?
+        .ok_or(ReversalError::NotFound)?;
+    ensure_batch_allows(conn, requirement.batch_id, BatchOperation::AllocateStock).await?;
+
+    // 1. Restore lot quantities
+    let lines = batch_allocation_lines::Entity::find()
+        .filter(batch_allocation_lines::Column::AllocationId.eq(allocation_id))
+        .all(conn)
+        .await?;
+
+    for line in lines {
+        if let Some(lot) = stock_receipts::Entity::find_by_id(line.lot_id)
+            .one(conn)
+            .await?
+        {
+            // Stock goes back to where the lot is held, not the farm it was sent to
+            let location_id = lot.location_id;
+            let remaining = lot.remaining_qty + line.qty;
+            let mut active: stock_receipts::ActiveModel = lot.into();
+            active.remaining_qty = Set(remaining);
+            active.update(conn).await?;
+
+            adjust_location_stock(conn, &requirement.item_code, location_id, line.qty).await?;
+            record_adjustment(
+                conn,
+                &requirement.item_code,
+                line.qty,
+                allocation_id,
+                location_id,
+            )
+            .await?;
+        }
+    }
+
+    // 2. Restore inventory
+    adjust_inventory(conn, &requirement.item_code, allocation.allocated_qty).await?;
+
+    // 3. Take chicks back off the batch
+    let item = items::Entity::find_by_id(requirement.item_code.clone())
+        .one(conn)
+        .await?
+        .ok_or(ReversalError::NotFound)?;
+
+    if item.item_category == ItemCategory::Chicks {
+        let birds = allocation.allocated_qty.to_i32().unwrap_or_default();
+
+        bird_count_history::ActiveModel {
+            batch_id: Set(requirement.batch_id),
+            record_date: Set(Utc::now().date_naive()),
+            deaths: Set(0),
+            additions: Set(-birds),
+            notes: Set(format!(
+                "{} birds removed: allocation #{} reversed ({})",
+                birds, allocation_id, reason
+            )),
+            created_at: Set(Utc::now().into()),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+
+        // A reversed stocking no longer counts among the batch's placements
+        batch_placements::Entity::delete_many()
+            .filter(batch_placements::Column::AllocationId.eq(allocation_id))
+            .exec(conn)
+            .await?;
+
+        if let Some(batch) = batches::Entity::find_by_id(requirement.batch_id)
+            .one(conn)
+            .await?
+        {
+            let current = batch.current_bird_count.unwrap_or(0);
+            let mut active: batches::ActiveModel = batch.into();
+            active.current_bird_count = Set(Some(current - birds));
+            active.update(conn).await?;
+        }
+    }
+
+    // 4. Requirement can be approved again
+    let mut req_active: batch_requirements::ActiveModel = requirement.into();
+    req_active.status = Set(RequirementStatus::Pending);
+    req_active.update(conn).await?;
+
+    let mut active: batch_allocations::ActiveModel = allocation.into();
+    active.voided_at = Set(Some(Utc::now().into()));
+    active.void_reason = Set(Some(reason.to_string()));
+    active.update(conn).await?;
+
+    Ok(())
+}
+
+async fn adjust_inventory<C: ConnectionTrait>(
+    conn: &C,
+    item_code: &str,
+    qty_change: Decimal,
+) -> Result<(), ReversalError> {
+    let inv = inventory::Entity::find_by_id(item_code.to_string())
+        .one(conn)
+        .await?
+        .ok_or_else(|| {
+            ReversalError::Conflict(format!("No inventory record found for item {}", item_code))
+        })?;
+
+    let new_qty = inv.current_qty + qty_change;
+    if new_qty < Decimal::ZERO {
+        return Err(ReversalError::Conflict(format!(
+            "Reversal would leave negative stock for item {}",
+            item_code
+        )));
+    }
+
+    let mut active: inventory::ActiveModel = inv.into();
+    active.current_qty = Set(new_qty);
+    active.last_updated = Set(Utc::now().into());
+    active.update(conn).await?;
+
+    Ok(())
+}
+
+async fn record_adjustment<C: ConnectionTrait>(
+    conn: &C,
+    item_code: &str,
+    qty_change: Decimal,
+    reference_id: i32,
+    location_id: i32,
+) -> Result<(), ReversalError> {
+    inventory_movements::ActiveModel {
+        item_code: Set(item_code.to_string()),
+        movement_type: Set(MovementType::Adjustment),
+        qty_change: Set(qty_change),
+        reference_id: Set(Some(reference_id)),
+        movement_date: Set(Utc::now().into()),
+        location_id: Set(Some(location_id)),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,607 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use entity::sea_orm_active_enums::{BatchStatus, PostingEvent};
+use entity::{
+    batch_closure_summary, batch_sales, batches, farmer_settlements, settlement_rate_tables,
+};
+use num_traits::ToPrimitive;
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use thiserror::Error;
+
+use crate::handlers::batch_lifecycle::{
+    ensure_batch_allows, set_batch_status, BatchOperation, BatchStateError, BatchWriteError,
+};
+use crate::handlers::farmer_advances::outstanding_advances;
+use crate::handlers::inserts::record_farmer_commission;
+use crate::handlers::journal::{post_journal, JournalError};
+use crate::handlers::posting_rules::{resolve_posting_rule, PostingRuleError};
+use crate::models::{
+    ApproveSettlement, CreateFarmerCommission, CreateJournalVoucher, CreateSettlementRateTable,
+    JournalLine, ResponseMessage, SettlementBreakdown,
+};
+
+#[derive(Debug, Error)]
+pub enum SettlementError {
+    #[error("Batch {0} not found")]
+    BatchNotFound(i32),
+    #[error("Batch {0} has no cost sheet; close it before settling")]
+    NoCostSheet(i32),
+    #[error("No settlement rate table is in effect on {0}")]
+    NoRateTable(NaiveDate),
+    #[error("Batch {0} is already settled")]
+    AlreadySettled(i32),
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Lifecycle(#[from] BatchStateError),
+    #[error("Failed to book the settlement as farmer commission")]
+    Commission(StatusCode),
+    #[error(transparent)]
+    PostingRule(#[from] PostingRuleError),
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl SettlementError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            SettlementError::BatchNotFound(_) => StatusCode::NOT_FOUND,
+            SettlementError::NoCostSheet(_)
+            | SettlementError::NoRateTable(_)
+            | SettlementError::AlreadySettled(_) => StatusCode::CONFLICT,
+            SettlementError::Invalid(_) => StatusCode::BAD_REQUEST,
+            SettlementError::Lifecycle(e) => e.status(),
+            SettlementError::Commission(status) => *status,
+            SettlementError::Journal(e) => e.status(),
+            SettlementError::PostingRule(_) | SettlementError::Db(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+impl From<BatchWriteError> for SettlementError {
+    fn from(err: BatchWriteError) -> Self {
+        match err {
+            BatchWriteError::Lifecycle(e) => SettlementError::Lifecycle(e),
+            BatchWriteError::Status(status) => SettlementError::Commission(status),
+        }
+    }
+}
+
+fn error_response(
+    action: &'static str,
+) -> impl FnOnce(SettlementError) -> (StatusCode, Json<ResponseMessage>) {
+    move |e| {
+        eprintln!("Failed to {}: {}", action, e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    }
+}
+
+pub async fn get_settlement_rates_handler(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<Vec<settlement_rate_tables::Model>>, (StatusCode, Json<ResponseMessage>)> {
+    settlement_rate_tables::Entity::find()
+        .order_by_asc(settlement_rate_tables::Column::Version)
+        .all(&db)
+        .await
+        .map(Json)
+        .map_err(|e| error_response("fetch settlement rates")(e.into()))
+}
+
+/// Add a new version of the rate table. Earlier versions stay as they were so
+/// settlements already approved can still be explained.
+pub async fn create_settlement_rate_table(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateSettlementRateTable>,
+) -> Result<Json<settlement_rate_tables::Model>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match insert_rate_table(&txn, payload).await {
+            Ok(rate) => txn
+                .commit()
+                .await
+                .map(|_| rate)
+                .map_err(SettlementError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(SettlementError::from(e)),
+    };
+
+    result
+        .map(Json)
+        .map_err(error_response("create settlement rate table"))
+}
+
+async fn insert_rate_table<C: ConnectionTrait>(
+    conn: &C,
+    payload: CreateSettlementRateTable,
+) -> Result<settlement_rate_tables::Model, SettlementError> {
+    let hundred = Decimal::from(100);
+    if [
+        payload.base_rate_per_kg,
+        payload.standard_cost_per_kg,
+        payload.mortality_penalty_per_bird,
+    ]
+    .iter()
+    .any(|v| *v < Decimal::ZERO)
+        || !(Decimal::ZERO..=hundred).contains(&payload.incentive_share_pct)
+        || !(Decimal::ZERO..=hundred).contains(&payload.mortality_allowance_pct)
+    {
+        return Err(SettlementError::Invalid(
+            "Rates must not be negative and percentages must be between 0 and 100".to_string(),
+        ));
+    }
+
+    let version = settlement_rate_tables::Entity::find()
+        .order_by_desc(settlement_rate_tables::Column::Version)
+        .one(conn)
+        .await?
+        .map_or(1, |latest| latest.version + 1);
+
+    Ok(settlement_rate_tables::ActiveModel {
+        version: Set(version),
+        effective_from: Set(payload.effective_from),
+        base_rate_per_kg: Set(payload.base_rate_per_kg),
+        standard_cost_per_kg: Set(payload.standard_cost_per_kg),
+        incentive_share_pct: Set(payload.incentive_share_pct),
+        mortality_allowance_pct: Set(payload.mortality_allowance_pct),
+        mortality_penalty_per_bird: Set(payload.mortality_penalty_per_bird),
+        notes: Set(payload.notes),
+        created_by: Set(payload.created_by),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?)
+}
+
+/// The rate table a batch is settled on: the latest version in effect on the
+/// day it was placed.
+async fn rate_table_for<C: ConnectionTrait>(
+    conn: &C,
+    batch: &batches::Model,
+) -> Result<settlement_rate_tables::Model, SettlementError> {
+    settlement_rate_tables::Entity::find()
+        .filter(settlement_rate_tables::Column::EffectiveFrom.lte(batch.start_date))
+        .order_by_desc(settlement_rate_tables::Column::EffectiveFrom)
+        .order_by_desc(settlement_rate_tables::Column::Version)
+        .one(conn)
+        .await?
+        .ok_or(SettlementError::NoRateTable(batch.start_date))
+}
+
+/// Deaths within the allowance, and those above it.
+fn mortality_split(
+    birds_placed: i32,
+    mortality: i32,
+    rate: &settlement_rate_tables::Model,
+) -> (i32, i32) {
+    let allowed = (Decimal::from(birds_placed) * rate.mortality_allowance_pct / Decimal::from(100))
+        .floor()
+        .to_i32()
+        .unwrap_or_default();
+    (allowed, (mortality - allowed).max(0))
+}
+
+/// What a grower is owed, before it is tied to a batch.
+#[derive(Debug, PartialEq)]
+struct SettlementAmounts {
+    cost_per_kg: Option<Decimal>,
+    base_amount: Decimal,
+    cost_incentive: Decimal,
+    allowed_mortality: i32,
+    excess_mortality: i32,
+    mortality_penalty: Decimal,
+    gross_amount: Decimal,
+    advances_deducted: Decimal,
+    net_amount: Decimal,
+}
+
+/// The settlement arithmetic:
+///
+/// - base: live weight sold times the base rate per kg
+/// - cost incentive: the agreed share of the saving against the standard
+///   production cost, negative when cost ran over
+/// - mortality penalty: deaths above the allowance times the per-bird penalty
+///
+/// Gross is never below zero. Outstanding advances are recovered from it,
+/// as far as the gross amount covers them.
+fn settlement_amounts(
+    rate: &settlement_rate_tables::Model,
+    live_weight_kg: Decimal,
+    production_cost: Decimal,
+    birds_placed: i32,
+    mortality: i32,
+    outstanding_advances: Decimal,
+) -> SettlementAmounts {
+    let cost_per_kg =
+        (live_weight_kg > Decimal::ZERO).then(|| (production_cost / live_weight_kg).round_dp(2));
+
+    let base_amount = (live_weight_kg * rate.base_rate_per_kg).round_dp(2);
+    let cost_incentive = if live_weight_kg > Decimal::ZERO {
+        ((rate.standard_cost_per_kg * live_weight_kg - production_cost) * rate.incentive_share_pct
+            / Decimal::from(100))
+        .round_dp(2)
+    } else {
+        Decimal::ZERO
+    };
+
+    let (allowed_mortality, excess_mortality) = mortality_split(birds_placed, mortality, rate);
+    let mortality_penalty =
+        (Decimal::from(excess_mortality) * rate.mortality_penalty_per_bird).round_dp(2);
+
+    let gross_amount = (base_amount + cost_incentive - mortality_penalty).max(Decimal::ZERO);
+    let advances_deducted = outstanding_advances.clamp(Decimal::ZERO, gross_amount);
+
+    SettlementAmounts {
+        cost_per_kg,
+        base_amount,
+        cost_incentive,
+        allowed_mortality,
+        excess_mortality,
+        mortality_penalty,
+        gross_amount,
+        advances_deducted,
+        net_amount: gross_amount - advances_deducted,
+    }
+}
+
+/// Work out what a closed batch's grower is owed from its cost sheet, its
+/// sales and the farmer's outstanding advances; see [`settlement_amounts`].
+pub async fn compute_settlement<C: ConnectionTrait>(
+    conn: &C,
+    batch: &batches::Model,
+) -> Result<SettlementBreakdown, SettlementError> {
+    let summary = batch_closure_summary::Entity::find()
+        .filter(batch_closure_summary::Column::BatchId.eq(batch.batch_id))
+        .one(conn)
+        .await?
+        .ok_or(SettlementError::NoCostSheet(batch.batch_id))?;
+    let rate = rate_table_for(conn, batch).await?;
+
+    let live_weight_kg: Decimal = batch_sales::Entity::find()
+        .filter(batch_sales::Column::BatchId.eq(batch.batch_id))
+        .filter(batch_sales::Column::VoidedAt.is_null())
+        .all(conn)
+        .await?
+        .iter()
+        .map(|s| s.avg_weight * s.quantity)
+        .sum();
+
+    let production_cost = summary.chick_cost + summary.feed_cost + summary.medicine_cost;
+    let amounts = settlement_amounts(
+        &rate,
+        live_weight_kg,
+        production_cost,
+        summary.birds_placed,
+        summary.mortality,
+        outstanding_advances(conn, batch.farmer_id).await?,
+    );
+
+    Ok(SettlementBreakdown {
+        batch_id: batch.batch_id,
+        farmer_id: batch.farmer_id,
+        rate_table: rate,
+        birds_placed: summary.birds_placed,
+        mortality: summary.mortality,
+        mortality_pct: summary.mortality_pct,
+        allowed_mortality: amounts.allowed_mortality,
+        excess_mortality: amounts.excess_mortality,
+        live_weight_kg,
+        production_cost,
+        cost_per_kg: amounts.cost_per_kg,
+        base_amount: amounts.base_amount,
+        cost_incentive: amounts.cost_incentive,
+        mortality_penalty: amounts.mortality_penalty,
+        gross_amount: amounts.gross_amount,
+        advances_deducted: amounts.advances_deducted,
+        net_amount: amounts.net_amount,
+        settlement: None,
+    })
+}
+
+/// The breakdown as it was approved, from the stored settlement.
+async fn stored_breakdown<C: ConnectionTrait>(
+    conn: &C,
+    settlement: farmer_settlements::Model,
+) -> Result<SettlementBreakdown, SettlementError> {
+    let rate = settlement_rate_tables::Entity::find_by_id(settlement.rate_table_id)
+        .one(conn)
+        .await?
+        .ok_or_else(|| {
+            DbErr::RecordNotFound(format!(
+                "settlement rate table {}",
+                settlement.rate_table_id
+            ))
+        })?;
+    let (allowed_mortality, excess_mortality) =
+        mortality_split(settlement.birds_placed, settlement.mortality, &rate);
+
+    Ok(SettlementBreakdown {
+        batch_id: settlement.batch_id,
+        farmer_id: settlement.farmer_id,
+        rate_table: rate,
+        birds_placed: settlement.birds_placed,
+        mortality: settlement.mortality,
+        mortality_pct: settlement.mortality_pct,
+        allowed_mortality,
+        excess_mortality,
+        live_weight_kg: settlement.live_weight_kg,
+        production_cost: settlement.production_cost,
+        cost_per_kg: settlement.cost_per_kg,
+        base_amount: settlement.base_amount,
+        cost_incentive: settlement.cost_incentive,
+        mortality_penalty: settlement.mortality_penalty,
+        gross_amount: settlement.gross_amount,
+        advances_deducted: settlement.advances_deducted,
+        net_amount: settlement.net_amount,
+        settlement: Some(settlement),
+    })
+}
+
+/// The approved settlement for a batch, or a preview of it while unapproved.
+pub async fn get_settlement_handler(
+    State(db): State<DatabaseConnection>,
+    Path(batch_id): Path<i32>,
+) -> Result<Json<SettlementBreakdown>, (StatusCode, Json<ResponseMessage>)> {
+    let result: Result<SettlementBreakdown, SettlementError> = async {
+        if let Some(settlement) = farmer_settlements::Entity::find()
+            .filter(farmer_settlements::Column::BatchId.eq(batch_id))
+            .one(&db)
+            .await?
+        {
+            return stored_breakdown(&db, settlement).await;
+        }
+
+        let batch = batches::Entity::find_by_id(batch_id)
+            .one(&db)
+            .await?
+            .ok_or(SettlementError::BatchNotFound(batch_id))?;
+        compute_settlement(&db, &batch).await
+    }
+    .await;
+
+    result
+        .map(Json)
+        .map_err(error_response("compute farmer settlement"))
+}
+
+pub async fn approve_settlement_handler(
+    State(db): State<DatabaseConnection>,
+    Path(batch_id): Path<i32>,
+    Json(payload): Json<ApproveSettlement>,
+) -> Result<Json<SettlementBreakdown>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match approve_settlement(&txn, batch_id, payload).await {
+            Ok(breakdown) => txn
+                .commit()
+                .await
+                .map(|_| breakdown)
+                .map_err(SettlementError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(SettlementError::from(e)),
+    };
+
+    result
+        .map(Json)
+        .map_err(error_response("approve farmer settlement"))
+}
+
+/// Approve a closed batch's settlement: book the gross amount as farmer
+/// commission, recover any advances out of the cash paid, store the
+/// breakdown and mark the batch settled.
+async fn approve_settlement<C: TransactionTrait + ConnectionTrait>(
+    conn: &C,
+    batch_id: i32,
+    payload: ApproveSettlement,
+) -> Result<SettlementBreakdown, SettlementError> {
+    let batch = ensure_batch_allows(conn, batch_id, BatchOperation::Settle).await?;
+
+    if farmer_settlements::Entity::find()
+        .filter(farmer_settlements::Column::BatchId.eq(batch_id))
+        .one(conn)
+        .await?
+        .is_some()
+    {
+        return Err(SettlementError::AlreadySettled(batch_id));
+    }
+
+    // 1. Compute the breakdown
+    let mut breakdown = compute_settlement(conn, &batch).await?;
+    if breakdown.gross_amount <= Decimal::ZERO {
+        return Err(SettlementError::Invalid(format!(
+            "Batch {} settles to nothing payable; mark it settled through the status endpoint",
+            batch_id
+        )));
+    }
+    let advances = breakdown.advances_deducted;
+
+    // 2. Book the gross amount through the commission path
+    let commission = record_farmer_commission(
+        conn,
+        CreateFarmerCommission {
+            farmer_id: batch.farmer_id,
+            commission_amount: breakdown.gross_amount,
+            description: payload
+                .description
+                .or_else(|| Some(format!("Settlement for batch {}", batch_id))),
+            created_by: payload.approved_by,
+            batch_id: Some(batch_id),
+        },
+    )
+    .await?;
+
+    let settlement = farmer_settlements::ActiveModel {
+        batch_id: Set(batch_id),
+        farmer_id: Set(batch.farmer_id),
+        rate_table_id: Set(breakdown.rate_table.rate_table_id),
+        birds_placed: Set(breakdown.birds_placed),
+        mortality: Set(breakdown.mortality),
+        mortality_pct: Set(breakdown.mortality_pct),
+        live_weight_kg: Set(breakdown.live_weight_kg),
+        production_cost: Set(breakdown.production_cost),
+        cost_per_kg: Set(breakdown.cost_per_kg),
+        base_amount: Set(breakdown.base_amount),
+        cost_incentive: Set(breakdown.cost_incentive),
+        mortality_penalty: Set(breakdown.mortality_penalty),
+        gross_amount: Set(breakdown.gross_amount),
+        advances_deducted: Set(advances),
+        net_amount: Set(breakdown.net_amount),
+        commission_id: Set(commission.id),
+        approved_by: Set(payload.approved_by),
+        approved_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    // 3. Advances come back out of the cash the commission credited and off
+    //    the farmer-advances account
+    if advances > Decimal::ZERO {
+        let cash = resolve_posting_rule(conn, PostingEvent::FarmerCommission, None)
+            .await?
+            .credit_account_id;
+        let advance_account = resolve_posting_rule(conn, PostingEvent::FarmerAdvance, None)
+            .await?
+            .debit_account_id;
+        post_journal(
+            conn,
+            &CreateJournalVoucher {
+                txn_date: Utc::now().date_naive(),
+                narration: Some(format!(
+                    "Advances recovered from batch {} settlement",
+                    batch_id
+                )),
+                reference_table: Some("farmer_settlements".to_string()),
+                reference_id: Some(settlement.settlement_id),
+                created_by: payload.approved_by,
+                lines: vec![
+                    JournalLine {
+                        account_id: cash,
+                        debit: Some(advances),
+                        credit: None,
+                        narration: None,
+                    },
+                    JournalLine {
+                        account_id: advance_account,
+                        debit: None,
+                        credit: Some(advances),
+                        narration: None,
+                    },
+                ],
+            },
+        )
+        .await?;
+    }
+
+    set_batch_status(conn, batch, BatchStatus::Settled, payload.approved_by, None).await?;
+
+    breakdown.settlement = Some(settlement);
+    Ok(breakdown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    /// 10.00 per kg, standard cost 80.00 per kg with half the saving shared,
+    /// 5% mortality allowed and 20.00 charged per bird above it
+    fn rate() -> settlement_rate_tables::Model {
+        settlement_rate_tables::Model {
+            rate_table_id: 1,
+            version: 1,
+            effective_from: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            base_rate_per_kg: dec("10.00"),
+            standard_cost_per_kg: dec("80.00"),
+            incentive_share_pct: dec("50.00"),
+            mortality_allowance_pct: dec("5.00"),
+            mortality_penalty_per_bird: dec("20.00"),
+            notes: None,
+            created_by: None,
+            created_at: Utc::now().into(),
+        }
+    }
+
+    #[test]
+    fn mortality_within_allowance() {
+        assert_eq!(mortality_split(1000, 30, &rate()), (50, 0));
+        assert_eq!(mortality_split(1000, 50, &rate()), (50, 0));
+    }
+
+    #[test]
+    fn mortality_above_allowance() {
+        assert_eq!(mortality_split(1000, 80, &rate()), (50, 30));
+    }
+
+    #[test]
+    fn mortality_allowance_rounds_down() {
+        // 5% of 1010 is 50.5 birds; only whole birds are allowed
+        assert_eq!(mortality_split(1010, 51, &rate()), (50, 1));
+    }
+
+    #[test]
+    fn settlement_with_saving_and_penalty() {
+        // 2000 kg sold for 155000 of cost: 5000 under the 80/kg standard
+        let amounts =
+            settlement_amounts(&rate(), dec("2000"), dec("155000"), 1000, 60, dec("3000"));
+
+        assert_eq!(amounts.cost_per_kg, Some(dec("77.50")));
+        assert_eq!(amounts.base_amount, dec("20000.00"));
+        assert_eq!(amounts.cost_incentive, dec("2500.00"));
+        assert_eq!(
+            (amounts.allowed_mortality, amounts.excess_mortality),
+            (50, 10)
+        );
+        assert_eq!(amounts.mortality_penalty, dec("200.00"));
+        assert_eq!(amounts.gross_amount, dec("22300.00"));
+        assert_eq!(amounts.advances_deducted, dec("3000"));
+        assert_eq!(amounts.net_amount, dec("19300.00"));
+    }
+
+    #[test]
+    fn cost_overrun_reduces_the_incentive() {
+        let amounts = settlement_amounts(&rate(), dec("1000"), dec("90000"), 500, 0, dec("0"));
+
+        assert_eq!(amounts.cost_per_kg, Some(dec("90.00")));
+        assert_eq!(amounts.cost_incentive, dec("-5000.00"));
+        assert_eq!(amounts.gross_amount, dec("5000.00"));
+    }
+
+    #[test]
+    fn gross_never_goes_negative_and_caps_advance_recovery() {
+        let amounts = settlement_amounts(&rate(), dec("100"), dec("20000"), 1000, 500, dec("750"));
+
+        assert_eq!(amounts.gross_amount, Decimal::ZERO);
+        assert_eq!(amounts.advances_deducted, Decimal::ZERO);
+        assert_eq!(amounts.net_amount, Decimal::ZERO);
+    }
+
+    #[test]
+    fn no_sales_means_no_cost_per_kg_or_incentive() {
+        let amounts = settlement_amounts(&rate(), Decimal::ZERO, dec("5000"), 100, 0, dec("0"));
+
+        assert_eq!(amounts.cost_per_kg, None);
+        assert_eq!(amounts.cost_incentive, Decimal::ZERO);
+        assert_eq!(amounts.gross_amount, Decimal::ZERO);
+    }
+}
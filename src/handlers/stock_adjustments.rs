@@ -0,0 +1,356 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::Utc;
+use entity::sea_orm_active_enums::{AdjustmentReason, MovementType, PostingEvent};
+use entity::{
+    inventory, inventory_movements, items, stock_adjustment_lines, stock_adjustments,
+    stock_receipts,
+};
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, Set,
+    TransactionTrait,
+};
+use thiserror::Error;
+
+use crate::handlers::costing::draw_lots;
+use crate::handlers::journal::{post_journal, JournalError};
+use crate::handlers::locations::{adjust_location_stock, resolve_location, LocationError};
+use crate::handlers::periods::{ensure_period_open, PeriodError};
+use crate::handlers::posting_rules::{resolve_posting_rule, PostingRuleError};
+use crate::models::{
+    CreateJournalVoucher, CreateStockAdjustment, JournalLine, ResponseMessage,
+    StockAdjustmentResponse,
+};
+
+#[derive(Debug, Error)]
+pub enum StockAdjustmentError {
+    #[error("Item {0} not found")]
+    ItemNotFound(String),
+    #[error("Quantity change must not be zero")]
+    ZeroQuantity,
+    #[error("Unit cost must not be negative")]
+    InvalidUnitCost,
+    #[error("Unit cost only applies to positive adjustments")]
+    UnexpectedUnitCost,
+    #[error("Insufficient stock for {item_code}: requested {requested}, available {available}")]
+    InsufficientStock {
+        item_code: String,
+        requested: Decimal,
+        available: Decimal,
+    },
+    #[error(transparent)]
+    Location(#[from] LocationError),
+    #[error(transparent)]
+    Period(#[from] PeriodError),
+    #[error(transparent)]
+    PostingRule(#[from] PostingRuleError),
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl StockAdjustmentError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            StockAdjustmentError::ItemNotFound(_) => StatusCode::NOT_FOUND,
+            StockAdjustmentError::ZeroQuantity
+            | StockAdjustmentError::InvalidUnitCost
+            | StockAdjustmentError::UnexpectedUnitCost
+            | StockAdjustmentError::InsufficientStock { .. } => StatusCode::BAD_REQUEST,
+            StockAdjustmentError::Location(e) => e.status(),
+            StockAdjustmentError::Period(e) => e.status(),
+            StockAdjustmentError::Journal(e) => e.status(),
+            StockAdjustmentError::PostingRule(_) | StockAdjustmentError::Db(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+pub async fn create_stock_adjustment(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateStockAdjustment>,
+) -> Result<Json<StockAdjustmentResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match record_stock_adjustment(&txn, payload).await {
+            Ok(adjustment) => txn
+                .commit()
+                .await
+                .map(|_| adjustment)
+                .map_err(StockAdjustmentError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(StockAdjustmentError::from(e)),
+    };
+
+    result.map(Json).map_err(|e| {
+        eprintln!("Failed to record stock adjustment: {}", e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    })
+}
+
+/// Write stock off or bring it back on. Negative adjustments consume lots
+/// oldest first and expense their cost; positive ones open a new lot at the
+/// given unit cost (zero if none) and book the gain.
+pub async fn record_stock_adjustment<C: ConnectionTrait>(
+    conn: &C,
+    payload: CreateStockAdjustment,
+) -> Result<StockAdjustmentResponse, StockAdjustmentError> {
+    record_adjustment_beyond_lots(conn, payload, Decimal::ZERO).await
+}
+
+/// Like [`record_stock_adjustment`], except that `uncosted` of a negative
+/// adjustment comes off the books without drawing any lot. It is for counts
+/// where the books hold more than the lots: that part has no lot cost to
+/// write off, so it corrects the quantity only.
+pub async fn record_adjustment_beyond_lots<C: ConnectionTrait>(
+    conn: &C,
+    payload: CreateStockAdjustment,
+    uncosted: Decimal,
+) -> Result<StockAdjustmentResponse, StockAdjustmentError> {
+    if payload.qty_change == Decimal::ZERO {
+        return Err(StockAdjustmentError::ZeroQuantity);
+    }
+    match payload.unit_cost {
+        Some(cost) if cost < Decimal::ZERO => return Err(StockAdjustmentError::InvalidUnitCost),
+        Some(_) if payload.qty_change < Decimal::ZERO => {
+            return Err(StockAdjustmentError::UnexpectedUnitCost)
+        }
+        _ => {}
+    }
+
+    let item = items::Entity::find_by_id(payload.item_code.clone())
+        .one(conn)
+        .await?
+        .ok_or_else(|| StockAdjustmentError::ItemNotFound(payload.item_code.clone()))?;
+
+    let adjustment_date = payload
+        .adjustment_date
+        .unwrap_or_else(|| Utc::now().date_naive());
+    ensure_period_open(conn, adjustment_date).await?;
+    let location = resolve_location(conn, payload.location_id).await?;
+
+    let on_hand = inventory::Entity::find_by_id(item.item_code.clone())
+        .one(conn)
+        .await?;
+    let available = on_hand
+        .as_ref()
+        .map(|inv| inv.current_qty)
+        .unwrap_or_default();
+    let new_qty = available + payload.qty_change;
+    if new_qty < Decimal::ZERO {
+        return Err(StockAdjustmentError::InsufficientStock {
+            item_code: item.item_code,
+            requested: -payload.qty_change,
+            available,
+        });
+    }
+
+    let mut adjustment = stock_adjustments::ActiveModel {
+        item_code: Set(item.item_code.clone()),
+        qty_change: Set(payload.qty_change),
+        reason: Set(payload.reason.clone()),
+        value: Set(Decimal::ZERO),
+        adjustment_date: Set(adjustment_date),
+        location_id: Set(Some(location.location_id)),
+        notes: Set(payload.notes.clone()),
+        created_by: Set(payload.created_by),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    let (event, value, lines) = if payload.qty_change < Decimal::ZERO {
+        let from_lots = -payload.qty_change - uncosted.clamp(Decimal::ZERO, -payload.qty_change);
+        let (value, lines) = if from_lots > Decimal::ZERO {
+            consume_lots(
+                conn,
+                adjustment.adjustment_id,
+                &item,
+                location.location_id,
+                from_lots,
+            )
+            .await?
+        } else {
+            (Decimal::ZERO, Vec::new())
+        };
+        (PostingEvent::StockLoss, value, lines)
+    } else {
+        let unit_cost = payload.unit_cost.unwrap_or_default();
+        let lot = stock_receipts::ActiveModel {
+            purchase_id: Set(None),
+            item_code: Set(item.item_code.clone()),
+            received_qty: Set(payload.qty_change),
+            remaining_qty: Set(payload.qty_change),
+            unit_cost: Set(unit_cost),
+            received_date: Set(adjustment_date),
+            supplier: Set(None),
+            location_id: Set(location.location_id),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+
+        let mut active: stock_adjustments::ActiveModel = adjustment.clone().into();
+        active.lot_id = Set(Some(lot.lot_id));
+        adjustment = active.update(conn).await?;
+
+        (
+            PostingEvent::StockGain,
+            payload.qty_change * unit_cost,
+            Vec::new(),
+        )
+    };
+
+    if value != Decimal::ZERO {
+        let mut active: stock_adjustments::ActiveModel = adjustment.into();
+        active.value = Set(value);
+        adjustment = active.update(conn).await?;
+    }
+
+    match on_hand {
+        Some(inv) => {
+            let mut active: inventory::ActiveModel = inv.into();
+            active.current_qty = Set(new_qty);
+            active.last_updated = Set(Utc::now().into());
+            active.update(conn).await?;
+        }
+        None => {
+            inventory::ActiveModel {
+                item_code: Set(item.item_code.clone()),
+                current_qty: Set(new_qty),
+                last_updated: Set(Utc::now().into()),
+            }
+            .insert(conn)
+            .await?;
+        }
+    }
+    adjust_location_stock(
+        conn,
+        &item.item_code,
+        location.location_id,
+        payload.qty_change,
+    )
+    .await?;
+
+    inventory_movements::ActiveModel {
+        item_code: Set(item.item_code.clone()),
+        movement_type: Set(MovementType::Adjustment),
+        qty_change: Set(payload.qty_change),
+        reference_id: Set(Some(adjustment.adjustment_id)),
+        movement_date: Set(Utc::now().into()),
+        location_id: Set(Some(location.location_id)),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    // A zero-cost lot found on a count moves quantity but no money
+    let journal_id = if value > Decimal::ZERO {
+        let rule = resolve_posting_rule(conn, event, Some(item.item_category.clone())).await?;
+        let narration = format!(
+            "Stock adjustment ({}) of {} {}",
+            reason_label(&payload.reason),
+            payload.qty_change,
+            item.item_name
+        );
+        Some(
+            post_journal(
+                conn,
+                &CreateJournalVoucher {
+                    txn_date: adjustment_date,
+                    narration: Some(narration),
+                    reference_table: Some("stock_adjustments".to_string()),
+                    reference_id: Some(adjustment.adjustment_id),
+                    created_by: payload.created_by,
+                    lines: vec![
+                        JournalLine {
+                            account_id: rule.debit_account_id,
+                            debit: Some(value),
+                            credit: None,
+                            narration: None,
+                        },
+                        JournalLine {
+                            account_id: rule.credit_account_id,
+                            debit: None,
+                            credit: Some(value),
+                            narration: None,
+                        },
+                    ],
+                },
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    Ok(StockAdjustmentResponse {
+        adjustment,
+        lines,
+        journal_id,
+    })
+}
+
+/// Take `quantity` out of the item's lots at a location by the category's
+/// costing method, recording which lots were drawn down. Expired lots are
+/// drawn like any other so write-offs can clear them. Returns the cost
+/// consumed and the lines written.
+async fn consume_lots<C: ConnectionTrait>(
+    conn: &C,
+    adjustment_id: i32,
+    item: &items::Model,
+    location_id: i32,
+    quantity: Decimal,
+) -> Result<(Decimal, Vec<stock_adjustment_lines::Model>), StockAdjustmentError> {
+    let issue = draw_lots(
+        conn,
+        &item.item_code,
+        &item.item_category,
+        location_id,
+        quantity,
+        None,
+    )
+    .await?;
+    if issue.shortage > Decimal::ZERO {
+        return Err(StockAdjustmentError::InsufficientStock {
+            item_code: item.item_code.clone(),
+            requested: quantity,
+            available: quantity - issue.shortage,
+        });
+    }
+
+    let mut lines = Vec::with_capacity(issue.draws.len());
+    for draw in issue.draws {
+        let line = stock_adjustment_lines::ActiveModel {
+            adjustment_id: Set(adjustment_id),
+            lot_id: Set(draw.lot_id),
+            qty: Set(draw.qty),
+            unit_cost: Set(draw.unit_cost),
+            line_value: Set(draw.value),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+        lines.push(line);
+    }
+
+    Ok((issue.total_value, lines))
+}
+
+fn reason_label(reason: &AdjustmentReason) -> &'static str {
+    match reason {
+        AdjustmentReason::Damaged => "damaged",
+        AdjustmentReason::Expired => "expired",
+        AdjustmentReason::Spillage => "spillage",
+        AdjustmentReason::CountCorrection => "count correction",
+        AdjustmentReason::Other => "other",
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::BTreeSet;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use entity::sea_orm_active_enums::{AdjustmentReason, StockTakeStatus};
+use entity::{inventory_locations, items, stock_receipts, stock_take_lines, stock_takes};
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use thiserror::Error;
+
+use crate::handlers::locations::{resolve_location, LocationError};
+use crate::handlers::stock_adjustments::{record_adjustment_beyond_lots, StockAdjustmentError};
+use crate::models::{
+    ApproveStockTake, CreateStockAdjustment, CreateStockTake, ResponseMessage, StockTakeLineView,
+    StockTakeResponse, SubmitStockTakeCounts,
+};
+
+#[derive(Debug, Error)]
+pub enum StockTakeError {
+    #[error("Stock take {0} not found")]
+    NotFound(i32),
+    #[error("Stock take {0} is not open")]
+    NotOpen(i32),
+    #[error("Nothing to count: no items given and none held at the location")]
+    NoItems,
+    #[error("Item {0} not found")]
+    ItemNotFound(String),
+    #[error("Item {0} is not part of this stock take")]
+    ItemNotInCount(String),
+    #[error("Counted quantity for {0} must not be negative")]
+    InvalidQuantity(String),
+    #[error("Items not counted yet: {}", .0.join(", "))]
+    Uncounted(Vec<String>),
+    #[error(transparent)]
+    Location(#[from] LocationError),
+    #[error(transparent)]
+    Adjustment(#[from] StockAdjustmentError),
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl StockTakeError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            StockTakeError::NotFound(_) | StockTakeError::ItemNotFound(_) => StatusCode::NOT_FOUND,
+            StockTakeError::NotOpen(_) | StockTakeError::Uncounted(_) => StatusCode::CONFLICT,
+            StockTakeError::NoItems
+            | StockTakeError::ItemNotInCount(_)
+            | StockTakeError::InvalidQuantity(_) => StatusCode::BAD_REQUEST,
+            StockTakeError::Location(e) => e.status(),
+            StockTakeError::Adjustment(e) => e.status(),
+            StockTakeError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+fn error_response(
+    action: &'static str,
+) -> impl FnOnce(StockTakeError) -> (StatusCode, Json<ResponseMessage>) {
+    move |e| {
+        eprintln!("Failed to {}: {}", action, e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    }
+}
+
+/// What the books and the lots say is held of an item at a location.
+async fn book_quantities<C: ConnectionTrait>(
+    conn: &C,
+    location_id: i32,
+    item_code: &str,
+) -> Result<(Decimal, Decimal), DbErr> {
+    let book = inventory_locations::Entity::find_by_id((item_code.to_string(), location_id))
+        .one(conn)
+        .await?
+        .map(|s| s.current_qty)
+        .unwrap_or_default();
+    let lots = stock_receipts::Entity::find()
+        .filter(stock_receipts::Column::ItemCode.eq(item_code))
+        .filter(stock_receipts::Column::LocationId.eq(location_id))
+        .filter(stock_receipts::Column::RemainingQty.gt(Decimal::ZERO))
+        .all(conn)
+        .await?
+        .iter()
+        .map(|r| r.remaining_qty)
+        .sum();
+
+    Ok((book, lots))
+}
+
+/// Cost of the item's most recent lot, used to value stock found on a count.
+async fn replacement_cost<C: ConnectionTrait>(conn: &C, item_code: &str) -> Result<Decimal, DbErr> {
+    Ok(stock_receipts::Entity::find()
+        .filter(stock_receipts::Column::ItemCode.eq(item_code))
+        .order_by_desc(stock_receipts::Column::ReceivedDate)
+        .order_by_desc(stock_receipts::Column::LotId)
+        .one(conn)
+        .await?
+        .map(|r| r.unit_cost)
+        .unwrap_or_default())
+}
+
+async fn load_stock_take<C: ConnectionTrait>(
+    conn: &C,
+    stock_take_id: i32,
+) -> Result<StockTakeResponse, StockTakeError> {
+    let stock_take = stock_takes::Entity::find_by_id(stock_take_id)
+        .one(conn)
+        .await?
+        .ok_or(StockTakeError::NotFound(stock_take_id))?;
+    let lines = stock_take_lines::Entity::find()
+        .filter(stock_take_lines::Column::StockTakeId.eq(stock_take_id))
+        .order_by_asc(stock_take_lines::Column::ItemCode)
+        .all(conn)
+        .await?;
+
+    let mut views = Vec::with_capacity(lines.len());
+    for line in lines {
+        let (current_book_qty, current_lot_qty) =
+            book_quantities(conn, stock_take.location_id, &line.item_code).await?;
+        let pending_variance = match (&stock_take.status, line.counted_qty) {
+            (StockTakeStatus::Open, Some(counted)) => Some(counted - current_book_qty),
+            _ => line.variance,
+        };
+        views.push(StockTakeLineView {
+            line,
+            current_book_qty,
+            current_lot_qty,
+            pending_variance,
+        });
+    }
+
+    Ok(StockTakeResponse {
+        stock_take,
+        lines: views,
+    })
+}
+
+pub async fn get_stock_takes_handler(
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<Vec<stock_takes::Model>>, (StatusCode, Json<ResponseMessage>)> {
+    stock_takes::Entity::find()
+        .order_by_desc(stock_takes::Column::StockTakeId)
+        .all(&db)
+        .await
+        .map(Json)
+        .map_err(|e| error_response("fetch stock takes")(StockTakeError::from(e)))
+}
+
+pub async fn get_stock_take_handler(
+    Path(stock_take_id): Path<i32>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<StockTakeResponse>, (StatusCode, Json<ResponseMessage>)> {
+    load_stock_take(&db, stock_take_id)
+        .await
+        .map(Json)
+        .map_err(error_response("fetch stock take"))
+}
+
+pub async fn open_stock_take_handler(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateStockTake>,
+) -> Result<Json<StockTakeResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match open_stock_take(&txn, payload).await {
+            Ok(response) => txn
+                .commit()
+                .await
+                .map(|_| response)
+                .map_err(StockTakeError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(StockTakeError::from(e)),
+    };
+
+    result.map(Json).map_err(error_response("open stock take"))
+}
+
+/// Open a count at a location, snapshotting book and lot quantities for each
+/// item so the sheet shows what the count was measured against.
+async fn open_stock_take<C: ConnectionTrait>(
+    conn: &C,
+    payload: CreateStockTake,
+) -> Result<StockTakeResponse, StockTakeError> {
+    let location = resolve_location(conn, payload.location_id).await?;
+
+    let item_codes: BTreeSet<String> = if payload.item_codes.is_empty() {
+        let mut held: BTreeSet<String> = inventory_locations::Entity::find()
+            .filter(inventory_locations::Column::LocationId.eq(location.location_id))
+            .filter(inventory_locations::Column::CurrentQty.ne(Decimal::ZERO))
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|s| s.item_code)
+            .collect();
+        held.extend(
+            stock_receipts::Entity::find()
+                .filter(stock_receipts::Column::LocationId.eq(location.location_id))
+                .filter(stock_receipts::Column::RemainingQty.gt(Decimal::ZERO))
+                .all(conn)
+                .await?
+                .into_iter()
+                .map(|r| r.item_code),
+        );
+        held
+    } else {
+        payload.item_codes.into_iter().collect()
+    };
+    if item_codes.is_empty() {
+        return Err(StockTakeError::NoItems);
+    }
+
+    let stock_take = stock_takes::ActiveModel {
+        location_id: Set(location.location_id),
+        status: Set(StockTakeStatus::Open),
+        count_date: Set(payload
+            .count_date
+            .unwrap_or_else(|| Utc::now().date_naive())),
+        notes: Set(payload.notes),
+        opened_by: Set(payload.opened_by),
+        opened_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    for item_code in item_codes {
+        items::Entity::find_by_id(item_code.clone())
+            .one(conn)
+            .await?
+            .ok_or_else(|| StockTakeError::ItemNotFound(item_code.clone()))?;
+
+        let (book_qty, lot_qty) = book_quantities(conn, location.location_id, &item_code).await?;
+        stock_take_lines::ActiveModel {
+            stock_take_id: Set(stock_take.stock_take_id),
+            item_code: Set(item_code),
+            book_qty: Set(book_qty),
+            lot_qty: Set(lot_qty),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+    }
+
+    load_stock_take(conn, stock_take.stock_take_id).await
+}
+
+pub async fn submit_stock_take_counts(
+    Path(stock_take_id): Path<i32>,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<SubmitStockTakeCounts>,
+) -> Result<Json<StockTakeResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match record_counts(&txn, stock_take_id, payload).await {
+            Ok(response) => txn
+                .commit()
+                .await
+                .map(|_| response)
+                .map_err(StockTakeError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(StockTakeError::from(e)),
+    };
+
+    result
+        .map(Json)
+        .map_err(error_response("record stock take counts"))
+}
+
+/// Enter counted quantities. A recount of the same item replaces the
+/// earlier figure while the count is still open.
+async fn record_counts<C: ConnectionTrait>(
+    conn: &C,
+    stock_take_id: i32,
+    payload: SubmitStockTakeCounts,
+) -> Result<StockTakeResponse, StockTakeError> {
+    let stock_take = open_stock_take_by_id(conn, stock_take_id).await?;
+
+    for count in payload.counts {
+        if count.counted_qty < Decimal::ZERO {
+            return Err(StockTakeError::InvalidQuantity(count.item_code));
+        }
+
+        let line = stock_take_lines::Entity::find()
+            .filter(stock_take_lines::Column::StockTakeId.eq(stock_take.stock_take_id))
+            .filter(stock_take_lines::Column::ItemCode.eq(count.item_code.clone()))
+            .one(conn)
+            .await?
+            .ok_or_else(|| StockTakeError::ItemNotInCount(count.item_code.clone()))?;
+
+        let mut active: stock_take_lines::ActiveModel = line.into();
+        active.counted_qty = Set(Some(count.counted_qty));
+        active.counted_by = Set(payload.counted_by);
+        active.counted_at = Set(Some(Utc::now().into()));
+        active.update(conn).await?;
+    }
+
+    load_stock_take(conn, stock_take_id).await
+}
+
+pub async fn approve_stock_take_handler(
+    Path(stock_take_id): Path<i32>,
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<ApproveStockTake>,
+) -> Result<Json<StockTakeResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match approve_stock_take(&txn, stock_take_id, payload).await {
+            Ok(response) => txn
+                .commit()
+                .await
+                .map(|_| response)
+                .map_err(StockTakeError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(StockTakeError::from(e)),
+    };
+
+    result
+        .map(Json)
+        .map_err(error_response("approve stock take"))
+}
+
+/// How much of a count shortfall the lots can cover at their cost, and how
+/// much lies beyond what the lots hold and is corrected at no cost.
+fn split_shortfall(shortfall: Decimal, lot_qty: Decimal) -> (Decimal, Decimal) {
+    let costed = shortfall.min(lot_qty.max(Decimal::ZERO));
+    (costed, shortfall - costed)
+}
+
+/// Post each line's variance against the books as it stands now as a
+/// count-correction adjustment. Shortfalls consume lots and are written off
+/// at their cost; where the lots hold less than the shortfall the rest only
+/// corrects the quantity. Surpluses open a lot at the item's latest cost.
+async fn approve_stock_take<C: ConnectionTrait>(
+    conn: &C,
+    stock_take_id: i32,
+    payload: ApproveStockTake,
+) -> Result<StockTakeResponse, StockTakeError> {
+    let stock_take = open_stock_take_by_id(conn, stock_take_id).await?;
+
+    let lines = stock_take_lines::Entity::find()
+        .filter(stock_take_lines::Column::StockTakeId.eq(stock_take_id))
+        .order_by_asc(stock_take_lines::Column::ItemCode)
+        .all(conn)
+        .await?;
+
+    let uncounted: Vec<String> = lines
+        .iter()
+        .filter(|l| l.counted_qty.is_none())
+        .map(|l| l.item_code.clone())
+        .collect();
+    if !uncounted.is_empty() {
+        return Err(StockTakeError::Uncounted(uncounted));
+    }
+
+    for line in lines {
+        let counted = line.counted_qty.unwrap_or_default();
+        let (book_qty, lot_qty) =
+            book_quantities(conn, stock_take.location_id, &line.item_code).await?;
+        let variance = counted - book_qty;
+
+        let adjustment_id = if variance != Decimal::ZERO {
+            let mut notes = format!("Stock take #{}", stock_take_id);
+            let (unit_cost, uncosted) = if variance > Decimal::ZERO {
+                (
+                    Some(replacement_cost(conn, &line.item_code).await?),
+                    Decimal::ZERO,
+                )
+            } else {
+                let (_, uncosted) = split_shortfall(-variance, lot_qty);
+                if uncosted > Decimal::ZERO {
+                    notes.push_str(&format!(
+                        "; {} beyond the lots corrected at no cost",
+                        uncosted
+                    ));
+                }
+                (None, uncosted)
+            };
+            let adjustment = record_adjustment_beyond_lots(
+                conn,
+                CreateStockAdjustment {
+                    item_code: line.item_code.clone(),
+                    qty_change: variance,
+                    reason: AdjustmentReason::CountCorrection,
+                    unit_cost,
+                    location_id: Some(stock_take.location_id),
+                    adjustment_date: Some(stock_take.count_date),
+                    notes: Some(notes),
+                    created_by: payload.approved_by,
+                },
+                uncosted,
+            )
+            .await?;
+            Some(adjustment.adjustment.adjustment_id)
+        } else {
+            None
+        };
+
+        let mut active: stock_take_lines::ActiveModel = line.into();
+        active.variance = Set(Some(variance));
+        active.adjustment_id = Set(adjustment_id);
+        active.update(conn).await?;
+    }
+
+    let mut active: stock_takes::ActiveModel = stock_take.into();
+    active.status = Set(StockTakeStatus::Approved);
+    active.approved_by = Set(payload.approved_by);
+    active.approved_at = Set(Some(Utc::now().into()));
+    active.update(conn).await?;
+
+    load_stock_take(conn, stock_take_id).await
+}
+
+pub async fn cancel_stock_take_handler(
+    Path(stock_take_id): Path<i32>,
+    State(db): State<DatabaseConnection>,
+) -> Result<Json<stock_takes::Model>, (StatusCode, Json<ResponseMessage>)> {
+    let cancel = async {
+        let stock_take = open_stock_take_by_id(&db, stock_take_id).await?;
+        let mut active: stock_takes::ActiveModel = stock_take.into();
+        active.status = Set(StockTakeStatus::Cancelled);
+        Ok(active.update(&db).await?)
+    };
+
+    cancel
+        .await
+        .map(Json)
+        .map_err(error_response("cancel stock take"))
+}
+
+async fn open_stock_take_by_id<C: ConnectionTrait>(
+    conn: &C,
+    stock_take_id: i32,
+) -> Result<stock_takes::Model, StockTakeError> {
+    let stock_take = stock_takes::Entity::find_by_id(stock_take_id)
+        .one(conn)
+        .await?
+        .ok_or(StockTakeError::NotFound(stock_take_id))?;
+    if stock_take.status != StockTakeStatus::Open {
+        return Err(StockTakeError::NotOpen(stock_take_id));
+    }
+
+    Ok(stock_take)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: i64) -> Decimal {
+        Decimal::from(value)
+    }
+
+    #[test]
+    fn shortfall_within_lots_is_costed() {
+        assert_eq!(split_shortfall(dec(30), dec(80)), (dec(30), dec(0)));
+        assert_eq!(split_shortfall(dec(80), dec(80)), (dec(80), dec(0)));
+    }
+
+    #[test]
+    fn shortfall_beyond_lots_is_split() {
+        // Books say 100, lots hold 20, 50 are counted
+        let (book_qty, lot_qty, counted) = (dec(100), dec(20), dec(50));
+        let shortfall = book_qty - counted;
+        assert_eq!(split_shortfall(shortfall, lot_qty), (dec(20), dec(30)));
+    }
+
+    #[test]
+    fn shortfall_without_lots_is_uncosted() {
+        assert_eq!(split_shortfall(dec(15), dec(0)), (dec(0), dec(15)));
+        assert_eq!(split_shortfall(dec(15), dec(-5)), (dec(0), dec(15)));
+    }
+}
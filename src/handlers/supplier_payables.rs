@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use entity::sea_orm_active_enums::PostingEvent;
+use entity::{purchases, supplier_payment_allocations, supplier_payments, suppliers};
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use thiserror::Error;
+
+use crate::handlers::journal::{load_journal, post_journal, JournalError};
+use crate::handlers::periods::{ensure_period_open, PeriodError};
+use crate::handlers::posting_rules::{resolve_posting_rule, PostingRuleError};
+use crate::handlers::purchases::internal_error;
+use crate::handlers::reports::add_to_ageing;
+use crate::models::{
+    AgeingBuckets, AsOfQuery, CreateJournalVoucher, CreateSupplierPayment, JournalLine,
+    OutstandingPurchase, ResponseMessage, SupplierPayableDetail, SupplierPayableLine,
+    SupplierPayablesResponse, SupplierPaymentResponse,
+};
+
+#[derive(Debug, Error)]
+pub enum SupplierPaymentError {
+    #[error("Payment amount must be positive")]
+    InvalidAmount,
+    #[error("Supplier {0} not found")]
+    SupplierNotFound(i32),
+    #[error("Purchase {0} has nothing outstanding for this supplier")]
+    NotOutstanding(i32),
+    #[error("Purchase {purchase_id} has only {outstanding} outstanding")]
+    OverSettled {
+        purchase_id: i32,
+        outstanding: Decimal,
+    },
+    #[error("Settlements total {settled} but the payment is {amount}")]
+    SettlementMismatch { settled: Decimal, amount: Decimal },
+    #[error("Payment of {amount} exceeds the {outstanding} outstanding with the supplier")]
+    ExceedsOutstanding {
+        amount: Decimal,
+        outstanding: Decimal,
+    },
+    #[error(transparent)]
+    Period(#[from] PeriodError),
+    #[error(transparent)]
+    PostingRule(#[from] PostingRuleError),
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl SupplierPaymentError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            SupplierPaymentError::SupplierNotFound(_) => StatusCode::NOT_FOUND,
+            SupplierPaymentError::Period(e) => e.status(),
+            SupplierPaymentError::Journal(e) => e.status(),
+            SupplierPaymentError::PostingRule(_) | SupplierPaymentError::Db(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+pub async fn create_supplier_payment(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateSupplierPayment>,
+) -> Result<Json<SupplierPaymentResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match record_supplier_payment(&txn, &payload).await {
+            Ok(resp) => txn
+                .commit()
+                .await
+                .map(|_| resp)
+                .map_err(SupplierPaymentError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(SupplierPaymentError::from(e)),
+    };
+
+    result.map(Json).map_err(|e| {
+        eprintln!("Failed to record supplier payment: {}", e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    })
+}
+
+/// Record a payment to a supplier: settle the chosen purchases (oldest first
+/// when none are given), then debit payables and credit cash or bank.
+async fn record_supplier_payment<C: ConnectionTrait>(
+    conn: &C,
+    payload: &CreateSupplierPayment,
+) -> Result<SupplierPaymentResponse, SupplierPaymentError> {
+    if payload.amount <= Decimal::ZERO {
+        return Err(SupplierPaymentError::InvalidAmount);
+    }
+
+    let supplier = suppliers::Entity::find_by_id(payload.supplier_id)
+        .one(conn)
+        .await?
+        .ok_or(SupplierPaymentError::SupplierNotFound(payload.supplier_id))?;
+
+    ensure_period_open(conn, payload.payment_date).await?;
+
+    let rule = resolve_posting_rule(conn, PostingEvent::SupplierPayment, None).await?;
+    let payables_account_id = rule.debit_account_id;
+    let payment_account_id = payload.payment_account_id.unwrap_or(rule.credit_account_id);
+
+    // 1. Decide how much goes to each purchase
+    let open_purchases = purchases::Entity::find()
+        .filter(purchases::Column::SupplierId.eq(supplier.supplier_id))
+        .filter(purchases::Column::VoidedAt.is_null())
+        .filter(purchases::Column::AmountDue.gt(Decimal::ZERO))
+        .order_by_asc(purchases::Column::PurchaseDate)
+        .order_by_asc(purchases::Column::PurchaseId)
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter(|p| p.amount_due > p.amount_paid)
+        .collect::<Vec<_>>();
+
+    let settlements = match &payload.settlements {
+        Some(requested) => {
+            let mut per_purchase: Vec<(i32, Decimal)> = Vec::new();
+            for s in requested {
+                if s.amount <= Decimal::ZERO {
+                    return Err(SupplierPaymentError::InvalidAmount);
+                }
+                match per_purchase.iter_mut().find(|(id, _)| *id == s.purchase_id) {
+                    Some((_, amount)) => *amount += s.amount,
+                    None => per_purchase.push((s.purchase_id, s.amount)),
+                }
+            }
+
+            for (purchase_id, amount) in &per_purchase {
+                let purchase = open_purchases
+                    .iter()
+                    .find(|p| p.purchase_id == *purchase_id)
+                    .ok_or(SupplierPaymentError::NotOutstanding(*purchase_id))?;
+                let outstanding = purchase.amount_due - purchase.amount_paid;
+                if *amount > outstanding {
+                    return Err(SupplierPaymentError::OverSettled {
+                        purchase_id: *purchase_id,
+                        outstanding,
+                    });
+                }
+            }
+
+            let settled: Decimal = per_purchase.iter().map(|(_, a)| *a).sum();
+            if settled != payload.amount {
+                return Err(SupplierPaymentError::SettlementMismatch {
+                    settled,
+                    amount: payload.amount,
+                });
+            }
+            per_purchase
+        }
+        None => {
+            let outstanding: Decimal = open_purchases
+                .iter()
+                .map(|p| p.amount_due - p.amount_paid)
+                .sum();
+            if payload.amount > outstanding {
+                return Err(SupplierPaymentError::ExceedsOutstanding {
+                    amount: payload.amount,
+                    outstanding,
+                });
+            }
+
+            let mut remaining = payload.amount;
+            let mut per_purchase = Vec::new();
+            for purchase in &open_purchases {
+                if remaining <= Decimal::ZERO {
+                    break;
+                }
+                let take = (purchase.amount_due - purchase.amount_paid).min(remaining);
+                per_purchase.push((purchase.purchase_id, take));
+                remaining -= take;
+            }
+            per_purchase
+        }
+    };
+
+    // 2. Record the payment and settle the purchases
+    let payment = supplier_payments::ActiveModel {
+        supplier_id: Set(supplier.supplier_id),
+        payment_date: Set(payload.payment_date),
+        amount: Set(payload.amount),
+        payment_account_id: Set(payment_account_id),
+        reference: Set(payload.reference.clone()),
+        notes: Set(payload.notes.clone()),
+        created_by: Set(payload.created_by),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    let mut saved_settlements = Vec::with_capacity(settlements.len());
+    for (purchase_id, amount) in settlements {
+        saved_settlements.push(
+            supplier_payment_allocations::ActiveModel {
+                payment_id: Set(payment.payment_id),
+                purchase_id: Set(purchase_id),
+                amount: Set(amount),
+                ..Default::default()
+            }
+            .insert(conn)
+            .await?,
+        );
+
+        let purchase = open_purchases
+            .iter()
+            .find(|p| p.purchase_id == purchase_id)
+            .cloned()
+            .ok_or(SupplierPaymentError::NotOutstanding(purchase_id))?;
+        let paid = purchase.amount_paid + amount;
+        let mut active: purchases::ActiveModel = purchase.into();
+        active.amount_paid = Set(paid);
+        active.update(conn).await?;
+    }
+
+    // 3. Debit payables, credit cash/bank
+    let narration = format!("Payment to supplier {}", supplier.name);
+    let voucher = CreateJournalVoucher {
+        txn_date: payload.payment_date,
+        narration: Some(narration.clone()),
+        reference_table: Some("supplier_payments".to_string()),
+        reference_id: Some(payment.payment_id),
+        created_by: payload.created_by,
+        lines: vec![
+            JournalLine {
+                account_id: payables_account_id,
+                debit: Some(payload.amount),
+                credit: None,
+                narration: Some(narration.clone()),
+            },
+            JournalLine {
+                account_id: payment_account_id,
+                debit: None,
+                credit: Some(payload.amount),
+                narration: Some(narration),
+            },
+        ],
+    };
+    let txn_group_id = post_journal(conn, &voucher).await?;
+
+    Ok(SupplierPaymentResponse {
+        payment,
+        settlements: saved_settlements,
+        journal: load_journal(conn, txn_group_id).await?,
+    })
+}
+
+/// Credit purchases with what was still owed on `as_of`, counting only
+/// payments dated on or before it.
+async fn outstanding_purchases<C: ConnectionTrait>(
+    conn: &C,
+    supplier_id: Option<i32>,
+    as_of: NaiveDate,
+) -> Result<Vec<(purchases::Model, Decimal)>, DbErr> {
+    let mut query = purchases::Entity::find()
+        .filter(purchases::Column::SupplierId.is_not_null())
+        .filter(purchases::Column::VoidedAt.is_null())
+        .filter(purchases::Column::AmountDue.gt(Decimal::ZERO))
+        .filter(purchases::Column::PurchaseDate.lte(as_of))
+        .order_by_asc(purchases::Column::PurchaseDate)
+        .order_by_asc(purchases::Column::PurchaseId);
+    if let Some(supplier_id) = supplier_id {
+        query = query.filter(purchases::Column::SupplierId.eq(supplier_id));
+    }
+    let purchases = query.all(conn).await?;
+
+    let purchase_ids: Vec<i32> = purchases.iter().map(|p| p.purchase_id).collect();
+    let mut paid: HashMap<i32, Decimal> = HashMap::new();
+    for (settlement, payment) in supplier_payment_allocations::Entity::find()
+        .filter(supplier_payment_allocations::Column::PurchaseId.is_in(purchase_ids))
+        .find_also_related(supplier_payments::Entity)
+        .all(conn)
+        .await?
+    {
+        if payment.is_some_and(|p| p.voided_at.is_none() && p.payment_date <= as_of) {
+            *paid.entry(settlement.purchase_id).or_default() += settlement.amount;
+        }
+    }
+
+    Ok(purchases
+        .into_iter()
+        .filter_map(|p| {
+            let outstanding = p.amount_due - paid.get(&p.purchase_id).copied().unwrap_or_default();
+            (outstanding > Decimal::ZERO).then_some((p, outstanding))
+        })
+        .collect())
+}
+
+pub async fn get_supplier_payables_handler(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<AsOfQuery>,
+) -> Result<Json<SupplierPayablesResponse>, StatusCode> {
+    let as_of = query.as_of.unwrap_or_else(|| Utc::now().date_naive());
+
+    let outstanding = outstanding_purchases(&db, None, as_of)
+        .await
+        .map_err(internal_error("fetch outstanding purchases"))?;
+
+    let names: HashMap<i32, String> = suppliers::Entity::find()
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch suppliers"))?
+        .into_iter()
+        .map(|s| (s.supplier_id, s.name))
+        .collect();
+
+    let mut per_supplier: HashMap<i32, (Decimal, AgeingBuckets)> = HashMap::new();
+    let mut total_ageing = AgeingBuckets::default();
+    let mut total_outstanding = Decimal::ZERO;
+
+    for (purchase, amount) in &outstanding {
+        let Some(supplier_id) = purchase.supplier_id else {
+            continue;
+        };
+        let age_days = (as_of - purchase.purchase_date).num_days();
+        let entry = per_supplier.entry(supplier_id).or_default();
+        entry.0 += *amount;
+        add_to_ageing(&mut entry.1, age_days, *amount);
+        add_to_ageing(&mut total_ageing, age_days, *amount);
+        total_outstanding += *amount;
+    }
+
+    let mut lines: Vec<SupplierPayableLine> = per_supplier
+        .into_iter()
+        .map(|(supplier_id, (outstanding, ageing))| SupplierPayableLine {
+            supplier_id,
+            supplier_name: names.get(&supplier_id).cloned().unwrap_or_default(),
+            outstanding,
+            ageing,
+        })
+        .collect();
+    lines.sort_by_key(|l| l.supplier_id);
+
+    Ok(Json(SupplierPayablesResponse {
+        as_of,
+        suppliers: lines,
+        total_outstanding,
+        ageing: total_ageing,
+    }))
+}
+
+pub async fn get_supplier_payable_detail_handler(
+    State(db): State<DatabaseConnection>,
+    Path(supplier_id): Path<i32>,
+    Query(query): Query<AsOfQuery>,
+) -> Result<Json<SupplierPayableDetail>, StatusCode> {
+    let as_of = query.as_of.unwrap_or_else(|| Utc::now().date_naive());
+
+    let supplier = suppliers::Entity::find_by_id(supplier_id)
+        .one(&db)
+        .await
+        .map_err(internal_error("fetch supplier"))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let outstanding = outstanding_purchases(&db, Some(supplier_id), as_of)
+        .await
+        .map_err(internal_error("fetch outstanding purchases"))?;
+
+    let mut ageing = AgeingBuckets::default();
+    let mut total = Decimal::ZERO;
+
+    let purchases = outstanding
+        .into_iter()
+        .map(|(p, amount)| {
+            let age_days = (as_of - p.purchase_date).num_days();
+            add_to_ageing(&mut ageing, age_days, amount);
+            total += amount;
+            OutstandingPurchase {
+                purchase_id: p.purchase_id,
+                purchase_date: p.purchase_date,
+                item_code: p.item_code,
+                amount_paid: p.amount_due - amount,
+                amount_due: p.amount_due,
+                outstanding: amount,
+                age_days,
+            }
+        })
+        .collect();
+
+    Ok(Json(SupplierPayableDetail {
+        supplier_id,
+        supplier_name: supplier.name,
+        as_of,
+        outstanding: total,
+        ageing,
+        purchases,
+    }))
+}
@@ -0,0 +1,577 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Datelike, NaiveDate, Utc};
+use entity::sea_orm_active_enums::PostingEvent;
+use entity::{batch_sales, trader_receipt_allocations, trader_receipts, traders};
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use thiserror::Error;
+
+use crate::handlers::journal::{load_journal, post_journal, JournalError};
+use crate::handlers::periods::{ensure_period_open, PeriodError};
+use crate::handlers::posting_rules::{resolve_posting_rule, PostingRuleError};
+use crate::handlers::purchases::internal_error;
+use crate::handlers::reports::{add_to_ageing, csv_response, csv_row};
+use crate::models::{
+    AgeingBuckets, AsOfQuery, CreateJournalVoucher, CreateTraderReceipt, JournalLine,
+    OutstandingSale, PeriodQuery, ReportFormat, ResponseMessage, TraderReceiptResponse,
+    TraderReceivableLine, TraderReceivablesResponse, TraderStatementLine, TraderStatementResponse,
+    UpdateCreditLimit,
+};
+
+#[derive(Debug, Error)]
+pub enum TraderReceiptError {
+    #[error("Receipt amount must be positive")]
+    InvalidAmount,
+    #[error("Trader {0} not found")]
+    TraderNotFound(i32),
+    #[error("Sale {0} has nothing outstanding for this trader")]
+    NotOutstanding(i32),
+    #[error("Sale {sale_id} has only {outstanding} outstanding")]
+    OverSettled { sale_id: i32, outstanding: Decimal },
+    #[error("Settlements total {settled} but the receipt is {amount}")]
+    SettlementMismatch { settled: Decimal, amount: Decimal },
+    #[error("Receipt of {amount} exceeds the {outstanding} outstanding from the trader")]
+    ExceedsOutstanding {
+        amount: Decimal,
+        outstanding: Decimal,
+    },
+    #[error(transparent)]
+    Period(#[from] PeriodError),
+    #[error(transparent)]
+    PostingRule(#[from] PostingRuleError),
+    #[error(transparent)]
+    Journal(#[from] JournalError),
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl TraderReceiptError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            TraderReceiptError::TraderNotFound(_) => StatusCode::NOT_FOUND,
+            TraderReceiptError::Period(e) => e.status(),
+            TraderReceiptError::Journal(e) => e.status(),
+            TraderReceiptError::PostingRule(_) | TraderReceiptError::Db(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Unpaid balance of a trader's credit sales right now.
+pub async fn trader_outstanding<C: ConnectionTrait>(
+    conn: &C,
+    trader_id: i32,
+) -> Result<Decimal, DbErr> {
+    Ok(open_credit_sales(conn, trader_id)
+        .await?
+        .iter()
+        .map(|s| s.amount_due - s.amount_received)
+        .sum())
+}
+
+async fn open_credit_sales<C: ConnectionTrait>(
+    conn: &C,
+    trader_id: i32,
+) -> Result<Vec<batch_sales::Model>, DbErr> {
+    Ok(batch_sales::Entity::find()
+        .filter(batch_sales::Column::TraderId.eq(trader_id))
+        .filter(batch_sales::Column::OnCredit.eq(true))
+        .filter(batch_sales::Column::VoidedAt.is_null())
+        .order_by_asc(batch_sales::Column::CreatedAt)
+        .order_by_asc(batch_sales::Column::Id)
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter(|s| s.amount_due > s.amount_received)
+        .collect())
+}
+
+pub async fn update_trader_credit_limit(
+    State(db): State<DatabaseConnection>,
+    Path(trader_id): Path<i32>,
+    Json(payload): Json<UpdateCreditLimit>,
+) -> Result<Json<traders::Model>, StatusCode> {
+    if payload.credit_limit.is_some_and(|l| l < Decimal::ZERO) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut trader: traders::ActiveModel = traders::Entity::find_by_id(trader_id)
+        .one(&db)
+        .await
+        .map_err(internal_error("fetch trader"))?
+        .ok_or(StatusCode::NOT_FOUND)?
+        .into();
+
+    trader.credit_limit = Set(payload.credit_limit);
+
+    trader
+        .update(&db)
+        .await
+        .map(Json)
+        .map_err(internal_error("update trader credit limit"))
+}
+
+pub async fn create_trader_receipt(
+    State(db): State<DatabaseConnection>,
+    Json(payload): Json<CreateTraderReceipt>,
+) -> Result<Json<TraderReceiptResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let result = match db.begin().await {
+        Ok(txn) => match record_trader_receipt(&txn, &payload).await {
+            Ok(resp) => txn
+                .commit()
+                .await
+                .map(|_| resp)
+                .map_err(TraderReceiptError::from),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(TraderReceiptError::from(e)),
+    };
+
+    result.map(Json).map_err(|e| {
+        eprintln!("Failed to record trader receipt: {}", e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    })
+}
+
+/// Record money received from a trader: settle the chosen credit sales
+/// (oldest first when none are given), then debit cash or bank and credit
+/// receivables.
+async fn record_trader_receipt<C: ConnectionTrait>(
+    conn: &C,
+    payload: &CreateTraderReceipt,
+) -> Result<TraderReceiptResponse, TraderReceiptError> {
+    if payload.amount <= Decimal::ZERO {
+        return Err(TraderReceiptError::InvalidAmount);
+    }
+
+    let trader = traders::Entity::find_by_id(payload.trader_id)
+        .one(conn)
+        .await?
+        .ok_or(TraderReceiptError::TraderNotFound(payload.trader_id))?;
+
+    ensure_period_open(conn, payload.receipt_date).await?;
+
+    let rule = resolve_posting_rule(conn, PostingEvent::TraderReceipt, None).await?;
+    let receipt_account_id = payload.receipt_account_id.unwrap_or(rule.debit_account_id);
+    let receivables_account_id = rule.credit_account_id;
+
+    // 1. Decide how much goes to each sale
+    let open_sales = open_credit_sales(conn, trader.trader_id).await?;
+
+    let settlements = match &payload.settlements {
+        Some(requested) => {
+            let mut per_sale: Vec<(i32, Decimal)> = Vec::new();
+            for s in requested {
+                if s.amount <= Decimal::ZERO {
+                    return Err(TraderReceiptError::InvalidAmount);
+                }
+                match per_sale.iter_mut().find(|(id, _)| *id == s.sale_id) {
+                    Some((_, amount)) => *amount += s.amount,
+                    None => per_sale.push((s.sale_id, s.amount)),
+                }
+            }
+
+            for (sale_id, amount) in &per_sale {
+                let sale = open_sales
+                    .iter()
+                    .find(|s| s.id == *sale_id)
+                    .ok_or(TraderReceiptError::NotOutstanding(*sale_id))?;
+                let outstanding = sale.amount_due - sale.amount_received;
+                if *amount > outstanding {
+                    return Err(TraderReceiptError::OverSettled {
+                        sale_id: *sale_id,
+                        outstanding,
+                    });
+                }
+            }
+
+            let settled: Decimal = per_sale.iter().map(|(_, a)| *a).sum();
+            if settled != payload.amount {
+                return Err(TraderReceiptError::SettlementMismatch {
+                    settled,
+                    amount: payload.amount,
+                });
+            }
+            per_sale
+        }
+        None => {
+            let outstanding: Decimal = open_sales
+                .iter()
+                .map(|s| s.amount_due - s.amount_received)
+                .sum();
+            if payload.amount > outstanding {
+                return Err(TraderReceiptError::ExceedsOutstanding {
+                    amount: payload.amount,
+                    outstanding,
+                });
+            }
+
+            let mut remaining = payload.amount;
+            let mut per_sale = Vec::new();
+            for sale in &open_sales {
+                if remaining <= Decimal::ZERO {
+                    break;
+                }
+                let take = (sale.amount_due - sale.amount_received).min(remaining);
+                per_sale.push((sale.id, take));
+                remaining -= take;
+            }
+            per_sale
+        }
+    };
+
+    // 2. Record the receipt and settle the sales
+    let receipt = trader_receipts::ActiveModel {
+        trader_id: Set(trader.trader_id),
+        receipt_date: Set(payload.receipt_date),
+        amount: Set(payload.amount),
+        receipt_account_id: Set(receipt_account_id),
+        reference: Set(payload.reference.clone()),
+        notes: Set(payload.notes.clone()),
+        created_by: Set(payload.created_by),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    let mut saved_settlements = Vec::with_capacity(settlements.len());
+    for (sale_id, amount) in settlements {
+        saved_settlements.push(
+            trader_receipt_allocations::ActiveModel {
+                receipt_id: Set(receipt.receipt_id),
+                sale_id: Set(sale_id),
+                amount: Set(amount),
+                ..Default::default()
+            }
+            .insert(conn)
+            .await?,
+        );
+
+        let sale = open_sales
+            .iter()
+            .find(|s| s.id == sale_id)
+            .cloned()
+            .ok_or(TraderReceiptError::NotOutstanding(sale_id))?;
+        let received = sale.amount_received + amount;
+        let mut active: batch_sales::ActiveModel = sale.into();
+        active.amount_received = Set(received);
+        active.update(conn).await?;
+    }
+
+    // 3. Debit cash/bank, credit receivables
+    let narration = format!("Payment received from trader {}", trader.name);
+    let voucher = CreateJournalVoucher {
+        txn_date: payload.receipt_date,
+        narration: Some(narration.clone()),
+        reference_table: Some("trader_receipts".to_string()),
+        reference_id: Some(receipt.receipt_id),
+        created_by: payload.created_by,
+        lines: vec![
+            JournalLine {
+                account_id: receipt_account_id,
+                debit: Some(payload.amount),
+                credit: None,
+                narration: Some(narration.clone()),
+            },
+            JournalLine {
+                account_id: receivables_account_id,
+                debit: None,
+                credit: Some(payload.amount),
+                narration: Some(narration),
+            },
+        ],
+    };
+    let txn_group_id = post_journal(conn, &voucher).await?;
+
+    Ok(TraderReceiptResponse {
+        receipt,
+        settlements: saved_settlements,
+        journal: load_journal(conn, txn_group_id).await?,
+    })
+}
+
+/// Credit sales with what was still owed on `as_of`, counting only receipts
+/// dated on or before it.
+async fn outstanding_sales<C: ConnectionTrait>(
+    conn: &C,
+    trader_id: Option<i32>,
+    as_of: NaiveDate,
+) -> Result<Vec<(batch_sales::Model, Decimal)>, DbErr> {
+    let mut query = batch_sales::Entity::find()
+        .filter(batch_sales::Column::OnCredit.eq(true))
+        .filter(batch_sales::Column::VoidedAt.is_null())
+        .order_by_asc(batch_sales::Column::CreatedAt)
+        .order_by_asc(batch_sales::Column::Id);
+    if let Some(trader_id) = trader_id {
+        query = query.filter(batch_sales::Column::TraderId.eq(trader_id));
+    }
+    let sales: Vec<batch_sales::Model> = query
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter(|s| s.created_at.date_naive() <= as_of)
+        .collect();
+
+    let sale_ids: Vec<i32> = sales.iter().map(|s| s.id).collect();
+    let mut received: HashMap<i32, Decimal> = HashMap::new();
+    for (settlement, receipt) in trader_receipt_allocations::Entity::find()
+        .filter(trader_receipt_allocations::Column::SaleId.is_in(sale_ids))
+        .find_also_related(trader_receipts::Entity)
+        .all(conn)
+        .await?
+    {
+        if receipt.is_some_and(|r| r.voided_at.is_none() && r.receipt_date <= as_of) {
+            *received.entry(settlement.sale_id).or_default() += settlement.amount;
+        }
+    }
+
+    Ok(sales
+        .into_iter()
+        .filter_map(|s| {
+            let outstanding = s.amount_due - received.get(&s.id).copied().unwrap_or_default();
+            (outstanding > Decimal::ZERO).then_some((s, outstanding))
+        })
+        .collect())
+}
+
+pub async fn get_trader_receivables_handler(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<AsOfQuery>,
+) -> Result<Json<TraderReceivablesResponse>, StatusCode> {
+    let as_of = query.as_of.unwrap_or_else(|| Utc::now().date_naive());
+
+    let outstanding = outstanding_sales(&db, None, as_of)
+        .await
+        .map_err(internal_error("fetch outstanding sales"))?;
+
+    let traders: HashMap<i32, traders::Model> = traders::Entity::find()
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch traders"))?
+        .into_iter()
+        .map(|t| (t.trader_id, t))
+        .collect();
+
+    let mut per_trader: HashMap<i32, (Decimal, AgeingBuckets)> = HashMap::new();
+    let mut total_ageing = AgeingBuckets::default();
+    let mut total_outstanding = Decimal::ZERO;
+
+    for (sale, amount) in &outstanding {
+        let age_days = (as_of - sale.created_at.date_naive()).num_days();
+        let entry = per_trader.entry(sale.trader_id).or_default();
+        entry.0 += *amount;
+        add_to_ageing(&mut entry.1, age_days, *amount);
+        add_to_ageing(&mut total_ageing, age_days, *amount);
+        total_outstanding += *amount;
+    }
+
+    let mut lines: Vec<TraderReceivableLine> = per_trader
+        .into_iter()
+        .map(|(trader_id, (outstanding, ageing))| {
+            let trader = traders.get(&trader_id);
+            TraderReceivableLine {
+                trader_id,
+                trader_name: trader.map(|t| t.name.clone()).unwrap_or_default(),
+                credit_limit: trader.and_then(|t| t.credit_limit),
+                outstanding,
+                ageing,
+            }
+        })
+        .collect();
+    lines.sort_by_key(|l| l.trader_id);
+
+    Ok(Json(TraderReceivablesResponse {
+        as_of,
+        traders: lines,
+        total_outstanding,
+        ageing: total_ageing,
+    }))
+}
+
+pub async fn get_trader_statement_handler(
+    State(db): State<DatabaseConnection>,
+    Path(trader_id): Path<i32>,
+    Query(query): Query<PeriodQuery>,
+) -> Result<Response, StatusCode> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = query.from.unwrap_or_else(|| to.with_day(1).unwrap_or(to));
+    if from > to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let trader = traders::Entity::find_by_id(trader_id)
+        .one(&db)
+        .await
+        .map_err(internal_error("fetch trader"))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let sales = batch_sales::Entity::find()
+        .filter(batch_sales::Column::TraderId.eq(trader_id))
+        .filter(batch_sales::Column::OnCredit.eq(true))
+        .filter(batch_sales::Column::VoidedAt.is_null())
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch trader sales"))?;
+
+    let receipts = trader_receipts::Entity::find()
+        .filter(trader_receipts::Column::TraderId.eq(trader_id))
+        .filter(trader_receipts::Column::VoidedAt.is_null())
+        .filter(trader_receipts::Column::ReceiptDate.lte(to))
+        .all(&db)
+        .await
+        .map_err(internal_error("fetch trader receipts"))?;
+
+    // (date, kind, id, description, debit, credit)
+    let mut movements: Vec<(NaiveDate, &str, i32, String, Decimal, Decimal)> = Vec::new();
+    for sale in &sales {
+        movements.push((
+            sale.created_at.date_naive(),
+            "sale",
+            sale.id,
+            format!(
+                "Sale from batch {} ({} birds)",
+                sale.batch_id, sale.quantity
+            ),
+            sale.amount_due,
+            Decimal::ZERO,
+        ));
+    }
+    for receipt in &receipts {
+        movements.push((
+            receipt.receipt_date,
+            "receipt",
+            receipt.receipt_id,
+            receipt
+                .reference
+                .clone()
+                .unwrap_or_else(|| "Payment received".to_string()),
+            Decimal::ZERO,
+            receipt.amount,
+        ));
+    }
+    movements.retain(|m| m.0 <= to);
+    movements.sort_by_key(|m| (m.0, m.1 == "receipt", m.2));
+
+    let opening_balance: Decimal = movements
+        .iter()
+        .filter(|m| m.0 < from)
+        .map(|m| m.4 - m.5)
+        .sum();
+
+    let mut balance = opening_balance;
+    let lines: Vec<TraderStatementLine> = movements
+        .into_iter()
+        .filter(|m| m.0 >= from)
+        .map(|(date, kind, reference_id, description, debit, credit)| {
+            balance += debit - credit;
+            TraderStatementLine {
+                date,
+                kind: kind.to_string(),
+                reference_id,
+                description,
+                debit,
+                credit,
+                balance,
+            }
+        })
+        .collect();
+
+    let mut ageing = AgeingBuckets::default();
+    let open_sales: Vec<OutstandingSale> = outstanding_sales(&db, Some(trader_id), to)
+        .await
+        .map_err(internal_error("fetch outstanding sales"))?
+        .into_iter()
+        .map(|(s, outstanding)| {
+            let sale_date = s.created_at.date_naive();
+            let age_days = (to - sale_date).num_days();
+            add_to_ageing(&mut ageing, age_days, outstanding);
+            OutstandingSale {
+                sale_id: s.id,
+                batch_id: s.batch_id,
+                sale_date,
+                amount_received: s.amount_due - outstanding,
+                amount_due: s.amount_due,
+                outstanding,
+                age_days,
+            }
+        })
+        .collect();
+
+    let statement = TraderStatementResponse {
+        trader_id,
+        trader_name: trader.name,
+        credit_limit: trader.credit_limit,
+        from,
+        to,
+        opening_balance,
+        lines,
+        closing_balance: balance,
+        ageing,
+        open_sales,
+    };
+
+    if query.format == ReportFormat::Csv {
+        let mut rows = vec![csv_row([
+            "date",
+            "kind",
+            "reference_id",
+            "description",
+            "debit",
+            "credit",
+            "balance",
+        ])];
+        rows.push(csv_row([
+            from.to_string(),
+            "opening".to_string(),
+            String::new(),
+            "Opening balance".to_string(),
+            String::new(),
+            String::new(),
+            statement.opening_balance.to_string(),
+        ]));
+        for line in &statement.lines {
+            rows.push(csv_row([
+                line.date.to_string(),
+                line.kind.clone(),
+                line.reference_id.to_string(),
+                line.description.clone(),
+                line.debit.to_string(),
+                line.credit.to_string(),
+                line.balance.to_string(),
+            ]));
+        }
+        rows.push(csv_row([
+            to.to_string(),
+            "closing".to_string(),
+            String::new(),
+            "Closing balance".to_string(),
+            String::new(),
+            String::new(),
+            statement.closing_balance.to_string(),
+        ]));
+        return Ok(csv_response(
+            &format!("trader_{}_statement_{}_{}.csv", trader_id, from, to),
+            rows,
+        ));
+    }
+
+    Ok(Json(statement).into_response())
+}
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use entity::{batch_requirements, item_unit_conversions, items};
+use sea_orm::prelude::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    ModelTrait, QueryFilter, QueryOrder, Set,
+};
+use thiserror::Error;
+
+use crate::models::{ItemUnitsResponse, ResponseMessage, SetItemUnit};
+
+#[derive(Debug, Error)]
+pub enum UnitError {
+    #[error("Item {0} not found")]
+    ItemNotFound(String),
+    #[error("Item {item_code} has no conversion for unit '{unit}'")]
+    UnknownUnit { item_code: String, unit: String },
+    #[error(
+        "{quantity} {unit} is {base_qty} in the base unit, which cannot be stored to two places"
+    )]
+    Fractional {
+        quantity: Decimal,
+        unit: String,
+        base_qty: Decimal,
+    },
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Database error: {0}")]
+    Db(#[from] DbErr),
+}
+
+impl UnitError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            UnitError::ItemNotFound(_) => StatusCode::NOT_FOUND,
+            UnitError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+pub fn unit_status(err: UnitError) -> StatusCode {
+    eprintln!("Unit conversion refused: {}", err);
+    err.status()
+}
+
+fn error_response(
+    action: &'static str,
+) -> impl FnOnce(UnitError) -> (StatusCode, Json<ResponseMessage>) {
+    move |e| {
+        eprintln!("Failed to {}: {}", action, e);
+        (
+            e.status(),
+            Json(ResponseMessage {
+                message: e.to_string(),
+            }),
+        )
+    }
+}
+
+/// How many base units one `unit` of the item holds. The item's own
+/// `unit` is the base and converts at one; unit names match ignoring case.
+pub async fn conversion_factor<C: ConnectionTrait>(
+    conn: &C,
+    item: &items::Model,
+    unit: &str,
+) -> Result<Decimal, UnitError> {
+    let unit = unit.trim();
+    if item
+        .unit
+        .as_deref()
+        .is_some_and(|base| base.eq_ignore_ascii_case(unit))
+    {
+        return Ok(Decimal::ONE);
+    }
+
+    item_unit_conversions::Entity::find()
+        .filter(item_unit_conversions::Column::ItemCode.eq(item.item_code.clone()))
+        .all(conn)
+        .await?
+        .into_iter()
+        .find(|c| c.unit.eq_ignore_ascii_case(unit))
+        .map(|c| c.factor)
+        .ok_or_else(|| UnitError::UnknownUnit {
+            item_code: item.item_code.clone(),
+            unit: unit.to_string(),
+        })
+}
+
+/// Convert `quantity` given in `unit` to the item's base unit. No unit means
+/// the quantity is already in the base unit. The result must fit the two
+/// places stock quantities are stored with.
+pub async fn to_base_quantity<C: ConnectionTrait>(
+    conn: &C,
+    item: &items::Model,
+    unit: Option<&str>,
+    quantity: Decimal,
+) -> Result<Decimal, UnitError> {
+    let Some(unit) = unit else {
+        return Ok(quantity);
+    };
+
+    let base_qty = (quantity * conversion_factor(conn, item, unit).await?).normalize();
+    if base_qty.round_dp(2) != base_qty {
+        return Err(UnitError::Fractional {
+            quantity,
+            unit: unit.to_string(),
+            base_qty,
+        });
+    }
+    Ok(base_qty)
+}
+
+/// Same as [`to_base_quantity`], looking the item up by code.
+pub async fn item_base_quantity<C: ConnectionTrait>(
+    conn: &C,
+    item_code: &str,
+    unit: Option<&str>,
+    quantity: Decimal,
+) -> Result<Decimal, UnitError> {
+    if unit.is_none() {
+        return Ok(quantity);
+    }
+
+    let item = items::Entity::find_by_id(item_code)
+        .one(conn)
+        .await?
+        .ok_or_else(|| UnitError::ItemNotFound(item_code.to_string()))?;
+    to_base_quantity(conn, &item, unit, quantity).await
+}
+
+/// Convert a quantity of a requirement's item to the base unit.
+pub async fn requirement_base_quantity<C: ConnectionTrait>(
+    conn: &C,
+    requirement_id: i32,
+    unit: Option<&str>,
+    quantity: Decimal,
+) -> Result<Decimal, UnitError> {
+    if unit.is_none() {
+        return Ok(quantity);
+    }
+
+    let requirement = batch_requirements::Entity::find_by_id(requirement_id)
+        .one(conn)
+        .await?
+        .ok_or_else(|| UnitError::Invalid(format!("Requirement {} not found", requirement_id)))?;
+    item_base_quantity(conn, &requirement.item_code, unit, quantity).await
+}
+
+/// Factor to `unit` for every item that can be shown in it, keyed by item
+/// code. Items whose base unit is `unit` convert at one.
+pub async fn display_factors<C: ConnectionTrait>(
+    conn: &C,
+    unit: &str,
+) -> Result<HashMap<String, Decimal>, DbErr> {
+    let unit = unit.trim();
+    let mut factors: HashMap<String, Decimal> = items::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter(|item| {
+            item.unit
+                .as_deref()
+                .is_some_and(|base| base.eq_ignore_ascii_case(unit))
+        })
+        .map(|item| (item.item_code, Decimal::ONE))
+        .collect();
+
+    for conversion in item_unit_conversions::Entity::find().all(conn).await? {
+        if conversion.unit.eq_ignore_ascii_case(unit) {
+            factors.insert(conversion.item_code, conversion.factor);
+        }
+    }
+
+    Ok(factors)
+}
+
+/// A base-unit quantity expressed in a unit holding `factor` base units.
+pub fn from_base_quantity(quantity: Decimal, factor: Decimal) -> Decimal {
+    (quantity / factor).round_dp(4).normalize()
+}
+
+async fn item_units<C: ConnectionTrait>(
+    conn: &C,
+    item_code: &str,
+) -> Result<ItemUnitsResponse, UnitError> {
+    let item = items::Entity::find_by_id(item_code)
+        .one(conn)
+        .await?
+        .ok_or_else(|| UnitError::ItemNotFound(item_code.to_string()))?;
+
+    let conversions = item_unit_conversions::Entity::find()
+        .filter(item_unit_conversions::Column::ItemCode.eq(item.item_code.clone()))
+        .order_by_asc(item_unit_conversions::Column::Factor)
+        .all(conn)
+        .await?;
+
+    Ok(ItemUnitsResponse {
+        item_code: item.item_code,
+        base_unit: item.unit,
+        conversions,
+    })
+}
+
+pub async fn get_item_units_handler(
+    State(db): State<DatabaseConnection>,
+    Path(item_code): Path<String>,
+) -> Result<Json<ItemUnitsResponse>, (StatusCode, Json<ResponseMessage>)> {
+    item_units(&db, &item_code)
+        .await
+        .map(Json)
+        .map_err(error_response("fetch item units"))
+}
+
+async fn set_item_unit<C: ConnectionTrait>(
+    conn: &C,
+    item_code: &str,
+    payload: SetItemUnit,
+) -> Result<ItemUnitsResponse, UnitError> {
+    let item = items::Entity::find_by_id(item_code)
+        .one(conn)
+        .await?
+        .ok_or_else(|| UnitError::ItemNotFound(item_code.to_string()))?;
+
+    let unit = payload.unit.trim();
+    if unit.is_empty() {
+        return Err(UnitError::Invalid("Unit name is required".to_string()));
+    }
+    if payload.factor <= Decimal::ZERO {
+        return Err(UnitError::Invalid(
+            "Conversion factor must be positive".to_string(),
+        ));
+    }
+    let Some(base_unit) = item.unit.as_deref() else {
+        return Err(UnitError::Invalid(format!(
+            "Item {} has no base unit to convert to",
+            item.item_code
+        )));
+    };
+    if base_unit.eq_ignore_ascii_case(unit) {
+        return Err(UnitError::Invalid(format!(
+            "'{}' is already the base unit of item {}",
+            unit, item.item_code
+        )));
+    }
+
+    let existing = item_unit_conversions::Entity::find()
+        .filter(item_unit_conversions::Column::ItemCode.eq(item.item_code.clone()))
+        .all(conn)
+        .await?
+        .into_iter()
+        .find(|c| c.unit.eq_ignore_ascii_case(unit));
+    match existing {
+        Some(conversion) => {
+            let mut active: item_unit_conversions::ActiveModel = conversion.into();
+            active.factor = Set(payload.factor);
+            active.update(conn).await?;
+        }
+        None => {
+            item_unit_conversions::ActiveModel {
+                item_code: Set(item.item_code.clone()),
+                unit: Set(unit.to_string()),
+                factor: Set(payload.factor),
+                created_at: Set(Utc::now().into()),
+                ..Default::default()
+            }
+            .insert(conn)
+            .await?;
+        }
+    }
+
+    item_units(conn, item_code).await
+}
+
+/// Add a unit the item can be entered in, or change its factor. Stock
+/// already held is in the base unit and is not touched.
+pub async fn set_item_unit_handler(
+    State(db): State<DatabaseConnection>,
+    Path(item_code): Path<String>,
+    Json(payload): Json<SetItemUnit>,
+) -> Result<Json<ItemUnitsResponse>, (StatusCode, Json<ResponseMessage>)> {
+    set_item_unit(&db, &item_code, payload)
+        .await
+        .map(Json)
+        .map_err(error_response("set item unit"))
+}
+
+pub async fn delete_item_unit_handler(
+    State(db): State<DatabaseConnection>,
+    Path((item_code, unit)): Path<(String, String)>,
+) -> Result<Json<ItemUnitsResponse>, (StatusCode, Json<ResponseMessage>)> {
+    let conversion = item_unit_conversions::Entity::find()
+        .filter(item_unit_conversions::Column::ItemCode.eq(item_code.clone()))
+        .all(&db)
+        .await
+        .map_err(UnitError::from)
+        .map_err(error_response("fetch item units"))?
+        .into_iter()
+        .find(|c| c.unit.eq_ignore_ascii_case(&unit))
+        .ok_or(UnitError::UnknownUnit {
+            item_code: item_code.clone(),
+            unit,
+        })
+        .map_err(error_response("delete item unit"))?;
+
+    conversion
+        .delete(&db)
+        .await
+        .map_err(UnitError::from)
+        .map_err(error_response("delete item unit"))?;
+
+    item_units(&db, &item_code)
+        .await
+        .map(Json)
+        .map_err(error_response("fetch item units"))
+}
@@ -13,10 +13,12 @@ mod handlers;
 mod models;
 mod routes;
 use crate::auth::login::login_handler;
+use crate::handlers::low_stock::run_low_stock_alert_job;
 use crate::handlers::visibility::get_visibility_handler;
 use crate::routes::admin::admin::admin;
 use crate::routes::fetch_by_id::fetch_by_id;
 use crate::routes::inserts::insert_routes;
+use crate::routes::reports::reports;
 use crate::{auth::middleware::auth_middleware, routes::fetch_all::fetch_all};
 use tower_http::cors::CorsLayer;
 
@@ -48,13 +50,22 @@ async fn main(#[shuttle_runtime::Secrets] secret_store: SecretStore) -> shuttle_
     // Migrator::up(&db, None).await.expect("Migration failed");
     // Migrator::fresh(&db).await.expect("failllled");
 
+    // Record low-stock alerts in the background
+    tokio::spawn(run_low_stock_alert_job(db.clone()));
+
     // 4. Build router
     let shared_secrets = Arc::new(secret_store);
 
     let cors = CorsLayer::new()
         // .allow_origin("https://rjagro.vercel.app".parse::<HeaderValue>().unwrap())
         .allow_origin("http://localhost:3000".parse::<HeaderValue>().unwrap())
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::OPTIONS])
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+            Method::OPTIONS,
+        ])
         .allow_headers([
             http::header::CONTENT_TYPE,
             http::header::ACCEPT,
@@ -67,6 +78,7 @@ async fn main(#[shuttle_runtime::Secrets] secret_store: SecretStore) -> shuttle_
         .nest("/getall", fetch_all())
         .nest("/getbyid", fetch_by_id())
         .nest("/insert", insert_routes())
+        .nest("/reports", reports())
         .route("/", get(hello_world))
         .route("/visibility", get(get_visibility_handler))
         // .route("/generate", post(generate))
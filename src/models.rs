@@ -1,10 +1,19 @@
 use chrono::NaiveDate;
 use entity::sea_orm_active_enums::{
-    BatchStatus, ItemCategory, LedgerAccountType, RequirementStatus, SupplierType, UserRole,
+    AdjustmentReason, BatchStatus, CostingMethod, FarmerAdvanceType, ItemCategory,
+    LedgerAccountType, LocationType, PostingEvent, RequirementStatus, SupplierType, UserRole,
+};
+use entity::{
+    accounting_periods, batch_health_tasks, batch_requirements, farmer_settlements, fiscal_years,
+    flock_logs, health_program_steps, health_programs, item_unit_conversions,
+    settlement_rate_tables, stock_adjustment_lines, stock_adjustments, stock_take_lines,
+    stock_takes, supplier_payment_allocations, supplier_payments, trader_receipt_allocations,
+    trader_receipts,
 };
 use sea_orm::prelude::{DateTimeWithTimeZone, Decimal};
 use sea_orm::FromQueryResult;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Serialize)]
 pub struct ResponseMessage {
@@ -21,6 +30,9 @@ pub struct PurchaseWithItem {
     pub quantity: Decimal,
     pub purchase_date: NaiveDate,
     pub supplier: Option<String>,
+    pub supplier_id: Option<i32>,
+    pub amount_due: Decimal,
+    pub amount_paid: Decimal,
     pub created_by: Option<i32>,
 }
 #[derive(serde::Deserialize)]
@@ -29,6 +41,9 @@ pub struct CreateItem {
     pub item_name: String,
     pub item_category: ItemCategory,
     pub unit: Option<String>,
+    pub reorder_point: Option<Decimal>,
+    pub safety_stock: Option<Decimal>,
+    pub preferred_supplier_id: Option<i32>,
 }
 
 #[derive(Deserialize)]
@@ -44,10 +59,21 @@ pub struct CreatePurchase {
     pub total_cost: Option<Decimal>,
     pub purchase_date: chrono::NaiveDate,
     pub supplier: Option<String>,
+    pub supplier_id: Option<i32>,
     pub quantity: Decimal,
     pub created_by: Option<i32>,
-    pub inventory_account_id: i32,
-    pub payment_account_id: i32,
+    pub inventory_account_id: Option<i32>,
+    pub payment_account_id: Option<i32>,
+    /// Book the cost to supplier payables instead of paying up front
+    pub on_credit: Option<bool>,
+    /// Where the stock is received; defaults to the central warehouse
+    pub location_id: Option<i32>,
+    /// Manufacturer's lot number, for medicines and vaccines
+    pub manufacturer_lot_no: Option<String>,
+    pub expiry_date: Option<NaiveDate>,
+    /// Unit `quantity` and `cost_per_unit` are given in; defaults to the
+    /// item's base unit
+    pub unit: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -57,9 +83,27 @@ pub struct CreateBatch {
     pub farmer_id: i32,
     pub start_date: chrono::NaiveDate,
     pub end_date: chrono::NaiveDate,
+    /// Total birds placed; must equal the sum of `chick_items`
     pub initial_bird_count: i32,
+    /// Single chick item supplying all `initial_bird_count` birds. Kept for
+    /// older clients; use `chick_items` to stock from several items.
+    #[serde(default)]
     pub chick_item_code: Vec<String>,
+    #[serde(default)]
+    pub chick_items: Vec<ChickStocking>,
     pub created_by: i32,
+    /// Breed standard the batch's growth curve is compared against
+    pub breed: Option<String>,
+    /// Defaults to the default health program, if there is one
+    pub health_program_id: Option<i32>,
+    /// Location the chicks are drawn from; defaults to the central warehouse
+    pub location_id: Option<i32>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ChickStocking {
+    pub item_code: String,
+    pub quantity: i32,
 }
 
 #[derive(Deserialize)]
@@ -70,6 +114,8 @@ pub struct CreateBatchRequirement {
     pub item_code: String,
     pub quantity: Decimal,
     pub request_date: chrono::NaiveDate,
+    /// Unit `quantity` is given in; defaults to the item's base unit
+    pub unit: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -78,6 +124,8 @@ pub struct CreateBatchAllocation {
     pub allocated_qty: Decimal,
     pub allocation_date: chrono::NaiveDate,
     pub allocated_by: i32,
+    /// Unit `allocated_qty` is given in; defaults to the item's base unit
+    pub unit: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -99,6 +147,7 @@ pub struct CreateTrader {
     pub bank_account_no: String,
     pub bank_name: String,
     pub ifsc_code: String,
+    pub credit_limit: Option<Decimal>,
 }
 
 #[derive(Deserialize)]
@@ -162,6 +211,7 @@ pub struct BatchResponse {
     pub current_bird_count: Option<i32>,
     pub status: BatchStatus,
     pub created_at: DateTimeWithTimeZone,
+    pub breed: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -186,11 +236,16 @@ pub struct ApprovePayload {
     pub allocated_qty: Decimal,
     pub allocation_date: NaiveDate,
     pub allocated_by: i32,
+    /// Location the stock is drawn from; defaults to the central warehouse
+    pub from_location_id: Option<i32>,
+    /// Unit `allocated_qty` is given in; defaults to the item's base unit
+    pub unit: Option<String>,
 }
 #[derive(Debug, Deserialize)]
 pub struct CreateLedgerAccount {
     pub name: String,
     pub account_type: LedgerAccountType,
+    /// Opening balance, posted against the opening balance equity account
     pub current_balance: Decimal,
 }
 
@@ -200,17 +255,27 @@ pub struct CreateFarmerCommission {
     pub commission_amount: Decimal,
     pub description: Option<String>,
     pub created_by: Option<i32>,
+    pub batch_id: Option<i32>,
 }
 
 #[derive(Deserialize)]
-pub struct CreateBatchClosureSummary {
-    pub batch_id: i32,
-    pub start_date: NaiveDate,
-    pub end_date: NaiveDate,
-    pub initial_chicken_count: i32,
-    pub available_chicken_count: i32,
-    pub revenue: Decimal,
-    pub gross_profit: Decimal,
+pub struct CloseBatch {
+    /// Defaults to today
+    pub end_date: Option<NaiveDate>,
+    pub closed_by: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct ChangeBatchStatus {
+    pub status: BatchStatus,
+    pub changed_by: Option<i32>,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ReopenBatch {
+    pub reopened_by: i32,
+    pub reason: String,
 }
 
 #[derive(serde::Deserialize)]
@@ -223,16 +288,913 @@ pub struct CreateBatchSale {
     pub quantity: Decimal,
     pub value: Decimal,
     pub created_by: i32,
+    /// Book the sale to trader receivables instead of cash
+    pub on_credit: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct CreateLedgerEntry {
+#[derive(Debug, Clone, Deserialize)]
+pub struct JournalLine {
     pub account_id: i32,
     pub debit: Option<Decimal>,
     pub credit: Option<Decimal>,
+    pub narration: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateJournalVoucher {
     pub txn_date: NaiveDate,
+    /// Default narration for lines that don't carry their own
+    pub narration: Option<String>,
+    pub reference_table: Option<String>,
+    pub reference_id: Option<i32>,
+    pub created_by: Option<i32>,
+    pub lines: Vec<JournalLine>,
+}
+
+#[derive(Serialize)]
+pub struct JournalEntryLine {
+    pub entry_id: i32,
+    pub account_id: i32,
+    pub account_name: Option<String>,
+    pub debit: Option<Decimal>,
+    pub credit: Option<Decimal>,
     pub narration: Option<String>,
     pub reference_table: Option<String>,
     pub reference_id: Option<i32>,
     pub created_by: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Serialize)]
+pub struct JournalResponse {
+    pub txn_group_id: Uuid,
+    pub txn_date: NaiveDate,
+    pub entries: Vec<JournalEntryLine>,
+    pub total_debit: Decimal,
+    pub total_credit: Decimal,
+    pub is_balanced: bool,
+}
+
+#[derive(Deserialize)]
+pub struct CreatePostingRule {
+    pub event: PostingEvent,
+    pub item_category: Option<ItemCategory>,
+    pub debit_account_id: i32,
+    pub credit_account_id: i32,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePostingRule {
+    pub debit_account_id: i32,
+    pub credit_account_id: i32,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PostingRuleResponse {
+    pub rule_id: i32,
+    pub event: PostingEvent,
+    pub item_category: Option<ItemCategory>,
+    pub debit_account_id: i32,
+    pub debit_account_name: Option<String>,
+    pub credit_account_id: i32,
+    pub credit_account_name: Option<String>,
+    pub description: Option<String>,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Deserialize)]
+pub struct AsOfQuery {
+    pub as_of: Option<NaiveDate>,
+}
+
+#[derive(Debug, FromQueryResult)]
+pub struct AccountTotals {
+    pub account_id: i32,
+    pub total_debit: Option<Decimal>,
+    pub total_credit: Option<Decimal>,
+}
+
+#[derive(Serialize)]
+pub struct TrialBalanceLine {
+    pub account_id: i32,
+    pub name: String,
+    pub account_type: LedgerAccountType,
+    pub total_debit: Decimal,
+    pub total_credit: Decimal,
+    /// Net balance on the account type's normal side
+    pub balance: Decimal,
+    pub debit_balance: Decimal,
+    pub credit_balance: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct TrialBalanceResponse {
+    pub as_of: NaiveDate,
+    pub lines: Vec<TrialBalanceLine>,
+    pub total_debit: Decimal,
+    pub total_credit: Decimal,
+    pub is_balanced: bool,
+}
+
+#[derive(Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize)]
+pub struct PeriodQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+#[derive(Deserialize)]
+pub struct BalanceSheetQuery {
+    pub as_of: Option<NaiveDate>,
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+#[derive(Serialize)]
+pub struct StatementLine {
+    pub account_id: i32,
+    pub name: String,
+    pub amount: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct ProfitAndLossResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub revenue: Vec<StatementLine>,
+    pub expenses: Vec<StatementLine>,
+    pub total_revenue: Decimal,
+    pub total_expenses: Decimal,
+    pub net_profit: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct BalanceSheetResponse {
+    pub as_of: NaiveDate,
+    pub assets: Vec<StatementLine>,
+    pub liabilities: Vec<StatementLine>,
+    pub equity: Vec<StatementLine>,
+    pub total_assets: Decimal,
+    pub total_liabilities: Decimal,
+    /// Equity accounts plus retained earnings
+    pub total_equity: Decimal,
+    /// Revenue less expenses not yet closed into an equity account
+    pub retained_earnings: Decimal,
+    pub total_liabilities_and_equity: Decimal,
+    pub is_balanced: bool,
+}
+
+#[derive(Serialize)]
+pub struct BalanceDriftLine {
+    pub account_id: i32,
+    pub name: String,
+    pub stored_balance: Decimal,
+    pub computed_balance: Decimal,
+    pub drift: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct ReconcileBalancesResponse {
+    pub fixed: bool,
+    pub drifted_accounts: Vec<BalanceDriftLine>,
+}
+
+#[derive(Deserialize)]
+pub struct ReverseTransaction {
+    pub txn_group_id: Option<Uuid>,
+    pub reference_table: Option<String>,
+    pub reference_id: Option<i32>,
+    pub reason: String,
+    pub txn_date: Option<NaiveDate>,
+    pub created_by: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct ReversalResponse {
+    pub reference_table: Option<String>,
+    pub reference_id: Option<i32>,
+    pub reversals: Vec<JournalResponse>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateFiscalYear {
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+#[derive(Serialize)]
+pub struct FiscalYearResponse {
+    #[serde(flatten)]
+    pub fiscal_year: fiscal_years::Model,
+    pub periods: Vec<accounting_periods::Model>,
+}
+
+#[derive(Deserialize)]
+pub struct LockPeriod {
+    pub user_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct CloseFiscalYear {
+    pub closed_by: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct CloseFiscalYearResponse {
+    pub fiscal_year: fiscal_years::Model,
+    pub net_profit: Decimal,
+    pub closing_entries: Option<JournalResponse>,
+}
+
+#[derive(Deserialize)]
+pub struct PurchaseSettlement {
+    pub purchase_id: i32,
+    pub amount: Decimal,
+}
+
+#[derive(Deserialize)]
+pub struct CreateSupplierPayment {
+    pub supplier_id: i32,
+    pub payment_date: NaiveDate,
+    pub amount: Decimal,
+    /// Cash or bank account paid from; defaults to the posting rule's account
+    pub payment_account_id: Option<i32>,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+    /// Purchases to settle; when omitted the oldest outstanding ones are settled first
+    pub settlements: Option<Vec<PurchaseSettlement>>,
+}
+
+#[derive(Serialize)]
+pub struct SupplierPaymentResponse {
+    pub payment: supplier_payments::Model,
+    pub settlements: Vec<supplier_payment_allocations::Model>,
+    pub journal: Option<JournalResponse>,
+}
+
+#[derive(Serialize, Default, Clone, Copy)]
+pub struct AgeingBuckets {
+    pub days_0_30: Decimal,
+    pub days_31_60: Decimal,
+    pub days_61_90: Decimal,
+    pub days_over_90: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct SupplierPayableLine {
+    pub supplier_id: i32,
+    pub supplier_name: String,
+    pub outstanding: Decimal,
+    pub ageing: AgeingBuckets,
+}
+
+#[derive(Serialize)]
+pub struct SupplierPayablesResponse {
+    pub as_of: NaiveDate,
+    pub suppliers: Vec<SupplierPayableLine>,
+    pub total_outstanding: Decimal,
+    pub ageing: AgeingBuckets,
+}
+
+#[derive(Serialize)]
+pub struct OutstandingPurchase {
+    pub purchase_id: i32,
+    pub purchase_date: NaiveDate,
+    pub item_code: String,
+    pub amount_due: Decimal,
+    pub amount_paid: Decimal,
+    pub outstanding: Decimal,
+    pub age_days: i64,
+}
+
+#[derive(Serialize)]
+pub struct SupplierPayableDetail {
+    pub supplier_id: i32,
+    pub supplier_name: String,
+    pub as_of: NaiveDate,
+    pub outstanding: Decimal,
+    pub ageing: AgeingBuckets,
+    pub purchases: Vec<OutstandingPurchase>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateCreditLimit {
+    pub credit_limit: Option<Decimal>,
+}
+
+#[derive(Deserialize)]
+pub struct SaleSettlement {
+    pub sale_id: i32,
+    pub amount: Decimal,
+}
+
+#[derive(Deserialize)]
+pub struct CreateTraderReceipt {
+    pub trader_id: i32,
+    pub receipt_date: NaiveDate,
+    pub amount: Decimal,
+    /// Cash or bank account received into; defaults to the posting rule's account
+    pub receipt_account_id: Option<i32>,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+    /// Sales to settle; when omitted the oldest outstanding ones are settled first
+    pub settlements: Option<Vec<SaleSettlement>>,
+}
+
+#[derive(Serialize)]
+pub struct TraderReceiptResponse {
+    pub receipt: trader_receipts::Model,
+    pub settlements: Vec<trader_receipt_allocations::Model>,
+    pub journal: Option<JournalResponse>,
+}
+
+#[derive(Serialize)]
+pub struct TraderReceivableLine {
+    pub trader_id: i32,
+    pub trader_name: String,
+    pub credit_limit: Option<Decimal>,
+    pub outstanding: Decimal,
+    pub ageing: AgeingBuckets,
+}
+
+#[derive(Serialize)]
+pub struct TraderReceivablesResponse {
+    pub as_of: NaiveDate,
+    pub traders: Vec<TraderReceivableLine>,
+    pub total_outstanding: Decimal,
+    pub ageing: AgeingBuckets,
+}
+
+#[derive(Serialize)]
+pub struct OutstandingSale {
+    pub sale_id: i32,
+    pub batch_id: i32,
+    pub sale_date: NaiveDate,
+    pub amount_due: Decimal,
+    pub amount_received: Decimal,
+    pub outstanding: Decimal,
+    pub age_days: i64,
+}
+
+#[derive(Serialize)]
+pub struct TraderStatementLine {
+    pub date: NaiveDate,
+    /// `sale` or `receipt`
+    pub kind: String,
+    pub reference_id: i32,
+    pub description: String,
+    pub debit: Decimal,
+    pub credit: Decimal,
+    pub balance: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct TraderStatementResponse {
+    pub trader_id: i32,
+    pub trader_name: String,
+    pub credit_limit: Option<Decimal>,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub opening_balance: Decimal,
+    pub lines: Vec<TraderStatementLine>,
+    pub closing_balance: Decimal,
+    pub ageing: AgeingBuckets,
+    pub open_sales: Vec<OutstandingSale>,
+}
+
+#[derive(Deserialize)]
+pub struct BatchComparisonQuery {
+    /// Batches started within `from..=to`
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub farmer_id: Option<i32>,
+    pub line_id: Option<i32>,
+}
+
+/// Grower performance for a batch. Weights are in kg; ratios are `None` until
+/// there is something to divide by.
+#[derive(Serialize)]
+pub struct BatchPerformance {
+    pub batch_id: i32,
+    pub farmer_id: i32,
+    pub farmer_name: String,
+    pub line_id: i32,
+    pub line_name: String,
+    pub status: BatchStatus,
+    pub start_date: NaiveDate,
+    pub metrics: PerformanceMetrics,
+}
+
+#[derive(Serialize, Default)]
+pub struct PerformanceMetrics {
+    pub birds_placed: i32,
+    pub mortality: i32,
+    pub mortality_pct: Option<Decimal>,
+    pub birds_sold: Decimal,
+    pub live_weight_kg: Decimal,
+    pub avg_weight: Option<Decimal>,
+    pub feed_kg: Decimal,
+    pub feed_cost: Decimal,
+    /// Allocated stock plus farmer commission
+    pub total_cost: Decimal,
+    /// Feed conversion ratio: feed consumed per kg of live weight sold
+    pub fcr: Option<Decimal>,
+    pub cost_per_kg: Option<Decimal>,
+}
+
+/// Metrics summed over all batches of a farmer or production line.
+#[derive(Serialize)]
+pub struct PerformanceGroup {
+    pub id: i32,
+    pub name: String,
+    pub batch_count: usize,
+    pub metrics: PerformanceMetrics,
+}
+
+#[derive(Serialize)]
+pub struct BatchComparisonResponse {
+    pub batches: Vec<BatchPerformance>,
+    pub by_farmer: Vec<PerformanceGroup>,
+    pub by_line: Vec<PerformanceGroup>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateFlockLog {
+    pub batch_id: i32,
+    pub log_date: NaiveDate,
+    pub feed_consumed_kg: Option<Decimal>,
+    pub sample_size: Option<i32>,
+    pub sample_avg_weight_kg: Option<Decimal>,
+    pub water_litres: Option<Decimal>,
+    pub notes: Option<String>,
+    pub recorded_by: Option<i32>,
+}
+
+/// Fields left out keep their recorded value.
+#[derive(Deserialize)]
+pub struct CorrectFlockLog {
+    pub feed_consumed_kg: Option<Decimal>,
+    pub sample_size: Option<i32>,
+    pub sample_avg_weight_kg: Option<Decimal>,
+    pub water_litres: Option<Decimal>,
+    pub notes: Option<String>,
+    pub corrected_by: i32,
+}
+
+#[derive(Serialize)]
+pub struct FlockLogResponse {
+    pub batch_id: i32,
+    /// Feed allocated to the farm, what the logs say was eaten, and the rest
+    pub feed_allocated_kg: Decimal,
+    pub feed_consumed_kg: Decimal,
+    pub feed_balance_kg: Decimal,
+    pub logs: Vec<flock_logs::Model>,
+}
+
+#[derive(Deserialize)]
+pub struct BreedStandardRow {
+    pub age_days: i32,
+    pub body_weight_kg: Decimal,
+    pub cumulative_feed_kg: Decimal,
+}
+
+/// Replaces the breed's whole standard table.
+#[derive(Deserialize)]
+pub struct SetBreedStandards {
+    pub breed: String,
+    pub rows: Vec<BreedStandardRow>,
+}
+
+#[derive(Serialize)]
+pub struct GrowthCurvePoint {
+    pub log_date: NaiveDate,
+    pub age_days: i64,
+    pub live_birds: i32,
+    pub sample_avg_weight_kg: Option<Decimal>,
+    pub standard_weight_kg: Option<Decimal>,
+    /// Sampled weight above (+) or below (-) the standard, in percent
+    pub weight_variance_pct: Option<Decimal>,
+    pub cumulative_feed_per_bird_kg: Option<Decimal>,
+    pub standard_cumulative_feed_kg: Option<Decimal>,
+}
+
+#[derive(Serialize)]
+pub struct GrowthCurveResponse {
+    pub batch_id: i32,
+    pub breed: Option<String>,
+    pub points: Vec<GrowthCurvePoint>,
+}
+
+#[derive(Deserialize)]
+pub struct HealthProgramStepInput {
+    pub day_of_age: i32,
+    pub task_name: String,
+    pub item_code: Option<String>,
+    pub dose_per_bird: Option<Decimal>,
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateHealthProgram {
+    pub name: String,
+    /// Makes this the program new batches get unless they name another
+    pub is_default: Option<bool>,
+    pub steps: Vec<HealthProgramStepInput>,
+}
+
+#[derive(Serialize)]
+pub struct HealthProgramResponse {
+    #[serde(flatten)]
+    pub program: health_programs::Model,
+    pub steps: Vec<health_program_steps::Model>,
+}
+
+#[derive(Deserialize)]
+pub struct HealthTaskQuery {
+    /// Defaults to today
+    pub as_of: Option<NaiveDate>,
+    /// Also list tasks falling due this many days ahead; defaults to 0
+    pub within_days: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct HealthTaskLine {
+    #[serde(flatten)]
+    pub task: batch_health_tasks::Model,
+    pub line_id: i32,
+    pub farmer_id: i32,
+    pub farmer_name: String,
+    /// Negative while the task is not yet due
+    pub days_overdue: i64,
+}
+
+#[derive(Serialize)]
+pub struct SupervisorHealthTasks {
+    pub supervisor_id: i32,
+    pub as_of: NaiveDate,
+    pub overdue: Vec<HealthTaskLine>,
+    pub due: Vec<HealthTaskLine>,
+}
+
+#[derive(Deserialize)]
+pub struct CompleteHealthTask {
+    pub completed_by: i32,
+    pub notes: Option<String>,
+    /// Raise a pending requirement for the task's medicine item
+    pub raise_requirement: Option<bool>,
+    /// Requirement quantity; defaults to the dose per bird times live birds
+    pub quantity: Option<Decimal>,
+}
+
+#[derive(Serialize)]
+pub struct CompleteHealthTaskResponse {
+    pub task: batch_health_tasks::Model,
+    pub requirement: Option<batch_requirements::Model>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateSettlementRateTable {
+    pub effective_from: NaiveDate,
+    pub base_rate_per_kg: Decimal,
+    pub standard_cost_per_kg: Decimal,
+    /// Share of the saving (or overrun) against the standard cost, in percent
+    pub incentive_share_pct: Decimal,
+    pub mortality_allowance_pct: Decimal,
+    pub mortality_penalty_per_bird: Decimal,
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct SettlementBreakdown {
+    pub batch_id: i32,
+    pub farmer_id: i32,
+    pub rate_table: settlement_rate_tables::Model,
+    pub birds_placed: i32,
+    pub mortality: i32,
+    pub mortality_pct: Decimal,
+    /// Deaths covered by the allowance, and those charged for
+    pub allowed_mortality: i32,
+    pub excess_mortality: i32,
+    pub live_weight_kg: Decimal,
+    /// Chick, feed and medicine cost from the cost sheet
+    pub production_cost: Decimal,
+    pub cost_per_kg: Option<Decimal>,
+    pub base_amount: Decimal,
+    /// Negative when production cost ran over the standard
+    pub cost_incentive: Decimal,
+    pub mortality_penalty: Decimal,
+    pub gross_amount: Decimal,
+    /// Outstanding advances recovered, up to the gross amount
+    pub advances_deducted: Decimal,
+    pub net_amount: Decimal,
+    /// Present once the settlement has been approved
+    pub settlement: Option<farmer_settlements::Model>,
+}
+
+#[derive(Deserialize)]
+pub struct ApproveSettlement {
+    pub approved_by: Option<i32>,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateFarmerAdvance {
+    pub farmer_id: i32,
+    pub batch_id: Option<i32>,
+    pub advance_type: FarmerAdvanceType,
+    pub amount: Decimal,
+    /// Defaults to today
+    pub advance_date: Option<NaiveDate>,
+    pub description: Option<String>,
+    pub created_by: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct FarmerStatementLine {
+    pub date: NaiveDate,
+    /// `advance`, `deduction`, `commission`, `settlement` or `payment`
+    pub kind: String,
+    pub reference_id: i32,
+    pub description: String,
+    pub debit: Decimal,
+    pub credit: Decimal,
+    pub balance: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct FarmerStatementResponse {
+    pub farmer_id: i32,
+    pub farmer_name: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Positive while the farmer owes advances back
+    pub opening_balance: Decimal,
+    pub lines: Vec<FarmerStatementLine>,
+    pub closing_balance: Decimal,
+}
+
+#[derive(Deserialize)]
+pub struct CreateStockAdjustment {
+    pub item_code: String,
+    /// Negative to write stock off, positive for stock found on a count
+    pub qty_change: Decimal,
+    pub reason: AdjustmentReason,
+    /// Cost of the lot opened by a positive adjustment; defaults to zero
+    pub unit_cost: Option<Decimal>,
+    /// Defaults to the central warehouse
+    pub location_id: Option<i32>,
+    /// Defaults to today
+    pub adjustment_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct StockAdjustmentResponse {
+    pub adjustment: stock_adjustments::Model,
+    /// Lots consumed by a write-off
+    pub lines: Vec<stock_adjustment_lines::Model>,
+    /// None when the adjustment carried no value
+    pub journal_id: Option<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateStorageLocation {
+    pub name: String,
+    pub location_type: LocationType,
+    /// Required for farm locations
+    pub farmer_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateStockTransfer {
+    pub item_code: String,
+    pub from_location_id: i32,
+    pub to_location_id: i32,
+    pub quantity: Decimal,
+    /// Defaults to today
+    pub transfer_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateStockTake {
+    /// Defaults to the central warehouse
+    pub location_id: Option<i32>,
+    /// Items to count; empty counts everything held at the location
+    #[serde(default)]
+    pub item_codes: Vec<String>,
+    /// Defaults to today
+    pub count_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub opened_by: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct StockTakeCount {
+    pub item_code: String,
+    pub counted_qty: Decimal,
+}
+
+#[derive(Deserialize)]
+pub struct SubmitStockTakeCounts {
+    pub counted_by: Option<i32>,
+    pub counts: Vec<StockTakeCount>,
+}
+
+#[derive(Deserialize)]
+pub struct ApproveStockTake {
+    pub approved_by: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct StockTakeLineView {
+    #[serde(flatten)]
+    pub line: stock_take_lines::Model,
+    /// Stock on the books at the location now
+    pub current_book_qty: Decimal,
+    /// Remaining quantity in the location's lots now
+    pub current_lot_qty: Decimal,
+    /// Counted less current book; None until counted
+    pub pending_variance: Option<Decimal>,
+}
+
+#[derive(Serialize)]
+pub struct StockTakeResponse {
+    pub stock_take: stock_takes::Model,
+    pub lines: Vec<StockTakeLineView>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateReorderLevels {
+    /// None stops tracking the item for low stock
+    pub reorder_point: Option<Decimal>,
+    pub safety_stock: Option<Decimal>,
+    pub preferred_supplier_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct LowStockQuery {
+    /// Also list tracked items that are above their reorder point
+    #[serde(default)]
+    pub include_all: bool,
+    /// Show quantities in this unit for items that have it
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+#[derive(Serialize)]
+pub struct LowStockLine {
+    pub item_code: String,
+    pub item_name: String,
+    pub unit: Option<String>,
+    pub on_hand_qty: Decimal,
+    /// Asked for by pending batch requirements
+    pub committed_qty: Decimal,
+    /// On hand less committed
+    pub available_qty: Decimal,
+    pub reorder_point: Decimal,
+    pub safety_stock: Decimal,
+    pub below_reorder_point: bool,
+    pub below_safety_stock: bool,
+    /// Enough to bring available stock back to reorder point plus safety stock
+    pub suggested_order_qty: Decimal,
+    pub preferred_supplier_id: Option<i32>,
+    pub preferred_supplier_name: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct LowStockResponse {
+    pub as_of: NaiveDate,
+    pub items: Vec<LowStockLine>,
+}
+
+#[derive(Deserialize)]
+pub struct InventoryValuationQuery {
+    pub as_of: Option<NaiveDate>,
+    /// Show quantities in this unit for items that have it
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+#[derive(Serialize)]
+pub struct InventoryValuationLine {
+    pub item_code: String,
+    pub item_name: String,
+    pub item_category: ItemCategory,
+    pub unit: Option<String>,
+    /// Sum of what the lots held on the valuation date
+    pub lot_qty: Decimal,
+    pub value: Decimal,
+    pub lot_count: usize,
+    /// Value by lot age in days since receipt
+    pub ageing: AgeingBuckets,
+    /// Inventory quantity on the valuation date
+    pub book_qty: Decimal,
+    /// Book quantity less lot quantity; anything but zero needs a look
+    pub qty_difference: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct InventoryValuationCategory {
+    pub item_category: ItemCategory,
+    pub value: Decimal,
+    pub ageing: AgeingBuckets,
+}
+
+/// Lot value against the balance of the inventory asset account the
+/// category's purchases are debited to.
+#[derive(Serialize)]
+pub struct InventoryAccountCheck {
+    pub account_id: i32,
+    pub account_name: String,
+    pub item_categories: Vec<ItemCategory>,
+    pub stock_value: Decimal,
+    pub ledger_balance: Decimal,
+    pub difference: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct InventoryValuationResponse {
+    pub as_of: NaiveDate,
+    pub items: Vec<InventoryValuationLine>,
+    pub categories: Vec<InventoryValuationCategory>,
+    pub accounts: Vec<InventoryAccountCheck>,
+    pub total_value: Decimal,
+    pub ageing: AgeingBuckets,
+    /// True when every item's quantities and every account's balance agree
+    pub reconciled: bool,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateCostingMethod {
+    pub item_category: ItemCategory,
+    pub costing_method: CostingMethod,
+}
+
+#[derive(Deserialize)]
+pub struct ExpiringLotsQuery {
+    /// Look this many days ahead; defaults to 30
+    pub days: Option<i64>,
+    pub item_code: Option<String>,
+    pub location_id: Option<i32>,
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+#[derive(Serialize)]
+pub struct ExpiringLot {
+    pub lot_id: i32,
+    pub item_code: String,
+    pub item_name: String,
+    pub location_id: i32,
+    pub location_name: String,
+    pub manufacturer_lot_no: Option<String>,
+    pub expiry_date: NaiveDate,
+    /// Negative once the lot has expired
+    pub days_to_expiry: i64,
+    pub expired: bool,
+    pub remaining_qty: Decimal,
+    pub unit_cost: Decimal,
+    pub value: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct ExpiringLotsResponse {
+    pub as_of: NaiveDate,
+    pub days: i64,
+    pub lots: Vec<ExpiringLot>,
+    pub total_value: Decimal,
+}
+
+#[derive(Deserialize)]
+pub struct SetItemUnit {
+    pub unit: String,
+    /// How many base units one of `unit` holds, e.g. 50 for a 50 kg bag
+    pub factor: Decimal,
+}
+
+#[derive(Serialize)]
+pub struct ItemUnitsResponse {
+    pub item_code: String,
+    pub base_unit: Option<String>,
+    pub conversions: Vec<item_unit_conversions::Model>,
 }
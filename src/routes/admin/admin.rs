@@ -1,6 +1,6 @@
 use axum::{
     middleware,
-    routing::{post, put},
+    routing::{delete, get, post, put},
     Router,
 };
 use entity::sea_orm_active_enums::UserRole;
@@ -8,8 +8,33 @@ use sea_orm::DatabaseConnection;
 
 use crate::{
     auth::middleware::{require_roles_middleware, RequireRoles},
-    handlers::batch_requirements::{
-        approve_batch_requirement_handler, decline_batch_requirement_handler,
+    handlers::{
+        batch_closure::close_batch_handler,
+        batch_lifecycle::{change_batch_status_handler, reopen_batch_handler},
+        batch_requirements::{
+            approve_batch_requirement_handler, decline_batch_requirement_handler,
+        },
+        costing::{get_costing_methods_handler, update_costing_method},
+        flock_logs::{get_breed_standards_handler, set_breed_standards},
+        health_programs::{create_health_program, get_health_programs_handler},
+        locations::{create_storage_location, get_storage_locations_handler},
+        low_stock::update_reorder_levels,
+        periods::{
+            close_fiscal_year_handler, create_fiscal_year, get_fiscal_years_handler,
+            lock_period_handler, unlock_period_handler,
+        },
+        posting_rules::{create_posting_rule, get_posting_rules_handler, update_posting_rule},
+        reconcile::{get_balance_drift_handler, reconcile_balances_handler},
+        reversals::reverse_transaction_handler,
+        settlements::{
+            approve_settlement_handler, create_settlement_rate_table, get_settlement_rates_handler,
+        },
+        stock_takes::{
+            approve_stock_take_handler, cancel_stock_take_handler, get_stock_take_handler,
+            get_stock_takes_handler, open_stock_take_handler,
+        },
+        trader_receivables::update_trader_credit_limit,
+        units::{delete_item_unit_handler, set_item_unit_handler},
     },
 };
 
@@ -23,6 +48,79 @@ pub fn admin() -> Router<DatabaseConnection> {
             "/approve_batch_requirement",
             post(approve_batch_requirement_handler),
         )
+        .route("/close_batch/{batch_id}", post(close_batch_handler))
+        .route(
+            "/batches/{batch_id}/status",
+            put(change_batch_status_handler),
+        )
+        .route("/batches/{batch_id}/reopen", post(reopen_batch_handler))
+        .route(
+            "/breed_standards",
+            get(get_breed_standards_handler).post(set_breed_standards),
+        )
+        .route(
+            "/costing_methods",
+            get(get_costing_methods_handler).put(update_costing_method),
+        )
+        .route(
+            "/health_programs",
+            get(get_health_programs_handler).post(create_health_program),
+        )
+        .route(
+            "/posting_rules",
+            get(get_posting_rules_handler).post(create_posting_rule),
+        )
+        .route("/posting_rules/{rule_id}", put(update_posting_rule))
+        .route(
+            "/reconcile_balances",
+            get(get_balance_drift_handler).post(reconcile_balances_handler),
+        )
+        .route("/items/{item_code}/reorder", put(update_reorder_levels))
+        .route("/items/{item_code}/units", post(set_item_unit_handler))
+        .route(
+            "/items/{item_code}/units/{unit}",
+            delete(delete_item_unit_handler),
+        )
+        .route("/reverse", post(reverse_transaction_handler))
+        .route(
+            "/storage_locations",
+            get(get_storage_locations_handler).post(create_storage_location),
+        )
+        .route(
+            "/stock_takes",
+            get(get_stock_takes_handler).post(open_stock_take_handler),
+        )
+        .route("/stock_takes/{stock_take_id}", get(get_stock_take_handler))
+        .route(
+            "/stock_takes/{stock_take_id}/approve",
+            post(approve_stock_take_handler),
+        )
+        .route(
+            "/stock_takes/{stock_take_id}/cancel",
+            post(cancel_stock_take_handler),
+        )
+        .route(
+            "/settlement_rates",
+            get(get_settlement_rates_handler).post(create_settlement_rate_table),
+        )
+        .route(
+            "/settlements/{batch_id}/approve",
+            post(approve_settlement_handler),
+        )
+        .route(
+            "/fiscal_years",
+            get(get_fiscal_years_handler).post(create_fiscal_year),
+        )
+        .route(
+            "/fiscal_years/{fiscal_year_id}/close",
+            post(close_fiscal_year_handler),
+        )
+        .route("/periods/{period_id}/lock", put(lock_period_handler))
+        .route("/periods/{period_id}/unlock", put(unlock_period_handler))
+        .route(
+            "/traders/{trader_id}/credit_limit",
+            put(update_trader_credit_limit),
+        )
         .layer(middleware::from_fn_with_state(
             RequireRoles::new(&[UserRole::Admin]),
             require_roles_middleware,
@@ -1,11 +1,42 @@
-use axum::{routing::get, Router};
+use axum::{middleware, routing::get, Router};
+use entity::sea_orm_active_enums::UserRole;
 use sea_orm::DatabaseConnection;
 
-use crate::handlers::fetch_by_id::get_farmer_commission_history_by_id_handler;
+use crate::{
+    auth::middleware::{require_roles_middleware, RequireRoles},
+    handlers::{
+        farmer_advances::get_farmer_statement_handler,
+        fetch_by_id::{get_farmer_commission_history_by_id_handler, get_journal_handler},
+        flock_logs::get_flock_logs_handler,
+        health_programs::get_supervisor_health_tasks_handler,
+        locations::get_location_stock_handler,
+        units::get_item_units_handler,
+    },
+};
 
 pub fn fetch_by_id() -> Router<DatabaseConnection> {
-    Router::new().route(
-        "/farmer_commission/{id}",
-        get(get_farmer_commission_history_by_id_handler),
-    )
+    Router::new()
+        .route("/journal/{txn_group_id}", get(get_journal_handler))
+        .route(
+            "/farmer_statement/{farmer_id}",
+            get(get_farmer_statement_handler),
+        )
+        .layer(middleware::from_fn_with_state(
+            RequireRoles::new(&[UserRole::Admin, UserRole::Accountant]),
+            require_roles_middleware,
+        ))
+        .route(
+            "/farmer_commission/{id}",
+            get(get_farmer_commission_history_by_id_handler),
+        )
+        .route("/flock_logs/{batch_id}", get(get_flock_logs_handler))
+        .route(
+            "/health_tasks/{supervisor_id}",
+            get(get_supervisor_health_tasks_handler),
+        )
+        .route(
+            "/location_stock/{location_id}",
+            get(get_location_stock_handler),
+        )
+        .route("/item_units/{item_code}", get(get_item_units_handler))
 }
@@ -1,13 +1,23 @@
 use axum::middleware::from_fn_with_state;
-use axum::{routing::post, Router};
+use axum::{
+    routing::{post, put},
+    Router,
+};
 use entity::sea_orm_active_enums::UserRole;
 use sea_orm::DatabaseConnection;
 
 use crate::handlers::batch_sales::create_batch_sale;
 use crate::handlers::batches::create_batch;
-use crate::handlers::inserts::{
-    create_batch_closure_summary, create_farmer_commission, create_ledger_entry,
-};
+use crate::handlers::farmer_advances::create_farmer_advance;
+use crate::handlers::flock_logs::{correct_flock_log, create_flock_log};
+use crate::handlers::health_programs::complete_health_task_handler;
+use crate::handlers::inserts::create_farmer_commission;
+use crate::handlers::journal::create_journal_voucher;
+use crate::handlers::locations::create_stock_transfer;
+use crate::handlers::stock_adjustments::create_stock_adjustment;
+use crate::handlers::stock_takes::submit_stock_take_counts;
+use crate::handlers::supplier_payables::create_supplier_payment;
+use crate::handlers::trader_receivables::create_trader_receipt;
 use crate::{
     auth::middleware::{require_roles_middleware, RequireRoles},
     handlers::{
@@ -23,9 +33,13 @@ use crate::{
 pub fn insert_routes() -> Router<DatabaseConnection> {
     Router::new()
         .route("/ledger_account", post(create_ledger_account))
-        .route("/batch_closure_summary", post(create_batch_closure_summary))
         .route("/batch_sales", post(create_batch_sale))
-        .route("/ledger_entry", post(create_ledger_entry))
+        .route("/journal", post(create_journal_voucher))
+        .route("/supplier_payment", post(create_supplier_payment))
+        .route("/trader_receipt", post(create_trader_receipt))
+        .route("/farmer_advances", post(create_farmer_advance))
+        .route("/stock_adjustments", post(create_stock_adjustment))
+        .route("/stock_transfers", post(create_stock_transfer))
         .layer(from_fn_with_state(
             RequireRoles::new(&[UserRole::Admin]),
             require_roles_middleware,
@@ -40,6 +54,16 @@ pub fn insert_routes() -> Router<DatabaseConnection> {
         .route("/traders", post(create_trader))
         .route("/suppliers", post(create_supplier))
         .route("/bird_count_history", post(create_bird_count_history))
+        .route("/flock_logs", post(create_flock_log))
+        .route("/flock_logs/{log_id}", put(correct_flock_log))
+        .route(
+            "/health_tasks/{task_id}/complete",
+            post(complete_health_task_handler),
+        )
+        .route(
+            "/stock_takes/{stock_take_id}/counts",
+            post(submit_stock_take_counts),
+        )
         .route("/bird_sell_history", post(create_bird_sell_history))
         .route("/farmer_commission", post(create_farmer_commission))
 }
@@ -2,3 +2,4 @@ pub mod admin;
 pub mod fetch_all;
 pub mod fetch_by_id;
 pub mod inserts;
+pub mod reports;
@@ -0,0 +1,52 @@
+use axum::{middleware, routing::get, Router};
+use entity::sea_orm_active_enums::UserRole;
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    auth::middleware::{require_roles_middleware, RequireRoles},
+    handlers::{
+        batch_performance::{get_batch_comparison_handler, get_batch_performance_handler},
+        flock_logs::get_growth_curve_handler,
+        inventory_valuation::get_inventory_valuation_handler,
+        lot_expiry::get_expiring_lots_handler,
+        low_stock::{get_low_stock_alerts_handler, get_low_stock_handler},
+        reports::{
+            get_balance_sheet_handler, get_profit_and_loss_handler, get_trial_balance_handler,
+        },
+        settlements::get_settlement_handler,
+        supplier_payables::{get_supplier_payable_detail_handler, get_supplier_payables_handler},
+        trader_receivables::{get_trader_receivables_handler, get_trader_statement_handler},
+    },
+};
+
+pub fn reports() -> Router<DatabaseConnection> {
+    Router::new()
+        .route("/trial_balance", get(get_trial_balance_handler))
+        .route("/profit_and_loss", get(get_profit_and_loss_handler))
+        .route("/balance_sheet", get(get_balance_sheet_handler))
+        .route("/batch_performance", get(get_batch_comparison_handler))
+        .route(
+            "/batch_performance/{batch_id}",
+            get(get_batch_performance_handler),
+        )
+        .route("/expiring_lots", get(get_expiring_lots_handler))
+        .route("/growth_curve/{batch_id}", get(get_growth_curve_handler))
+        .route("/inventory_valuation", get(get_inventory_valuation_handler))
+        .route("/low_stock", get(get_low_stock_handler))
+        .route("/low_stock/alerts", get(get_low_stock_alerts_handler))
+        .route("/settlement/{batch_id}", get(get_settlement_handler))
+        .route("/supplier_payables", get(get_supplier_payables_handler))
+        .route(
+            "/supplier_payables/{supplier_id}",
+            get(get_supplier_payable_detail_handler),
+        )
+        .route("/trader_receivables", get(get_trader_receivables_handler))
+        .route(
+            "/trader_receivables/{trader_id}/statement",
+            get(get_trader_statement_handler),
+        )
+        .layer(middleware::from_fn_with_state(
+            RequireRoles::new(&[UserRole::Admin, UserRole::Accountant]),
+            require_roles_middleware,
+        ))
+}